@@ -0,0 +1,53 @@
+//! Measures the dispatch loop on code dominated by cheap opcodes.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use evm::{Contract, Interpreter};
+
+/// Runs `bytecode`, leaving the setup of the interpreter, which builds a
+/// default environment, out of the measurement.
+fn execute(c: &mut Criterion, name: &str, bytecode: &str) {
+    let bytes: alloy_primitives::Bytes = bytecode.parse().unwrap();
+    c.bench_function(name, |b| {
+        b.iter_batched(
+            || {
+                let contract = Contract::new(Default::default(), bytes.clone(), Default::default());
+                Interpreter::new(Box::new(contract))
+            },
+            |mut interpreter| black_box(interpreter.execute().expect("should finish execution")),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn interpreter(c: &mut Criterion) {
+    // PUSH3 100000
+    // loop:
+    // JUMPDEST
+    // PUSH1 0x01
+    // SWAP1
+    // SUB
+    // DUP1
+    // PUSH1 loop
+    // JUMPI
+    // STOP
+    execute(c, "countdown", "0x620186a05b600190038060045700");
+
+    // PUSH1 0x00
+    // loop:
+    // JUMPDEST
+    // PUSH1 0x01
+    // ADD
+    // DUP1
+    // PUSH1 0x00
+    // MSTORE
+    // DUP1
+    // PUSH2 10000
+    // GT
+    // PUSH1 loop
+    // JUMPI
+    // STOP
+    execute(c, "memory_loop", "0x60005b60010180600052806127101160025700");
+}
+
+criterion_group!(benches, interpreter);
+criterion_main!(benches);
@@ -0,0 +1,190 @@
+//! The alloc JSON format used by geth and the transition tool to describe
+//! world state: a map from addresses to balances, nonces, code and storage.
+//! Genesis files embed it in their `alloc` field.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use alloy_primitives::{Address, Bytes, B256, B64, U256};
+use eyre::anyhow;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::state::{Account, State};
+
+pub type Alloc = BTreeMap<Address, GenesisAccount>;
+
+/// A geth genesis file: the header fields of the genesis block along with
+/// the initial world state.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Genesis {
+    /// The chain configuration, kept as is.
+    pub config: serde_json::Value,
+    pub nonce: B64,
+    pub timestamp: U256,
+    pub extra_data: Bytes,
+    pub gas_limit: U256,
+    pub difficulty: U256,
+    pub mix_hash: B256,
+    pub coinbase: Address,
+    pub number: U256,
+    pub gas_used: U256,
+    pub parent_hash: B256,
+    pub base_fee_per_gas: Option<U256>,
+    pub excess_blob_gas: Option<U256>,
+    pub blob_gas_used: Option<U256>,
+    pub alloc: Alloc,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisAccount {
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default, skip_serializing_if = "U256::is_zero")]
+    pub nonce: U256,
+    #[serde(default, skip_serializing_if = "<[u8]>::is_empty")]
+    pub code: Bytes,
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        serialize_with = "serialize_storage"
+    )]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl From<GenesisAccount> for Account {
+    fn from(account: GenesisAccount) -> Self {
+        Self {
+            balance: account.balance,
+            nonce: account.nonce.saturating_to(),
+            code: account.code,
+            storage: account
+                .storage
+                .into_iter()
+                .filter(|(_, value)| !value.is_zero())
+                .collect(),
+        }
+    }
+}
+
+impl From<&Account> for GenesisAccount {
+    fn from(account: &Account) -> Self {
+        Self {
+            balance: account.balance,
+            nonce: U256::from(account.nonce),
+            code: account.code.clone(),
+            storage: account
+                .storage
+                .iter()
+                .map(|(key, value)| (*key, *value))
+                .collect(),
+        }
+    }
+}
+
+/// Storage slots and values are written as full words, like geth does.
+fn serialize_storage<S: Serializer>(
+    storage: &BTreeMap<U256, U256>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        storage
+            .iter()
+            .map(|(key, value)| (B256::from(*key), B256::from(*value))),
+    )
+}
+
+/// Parses either a genesis file or a bare alloc, returning the alloc.
+pub fn parse(json: &str) -> eyre::Result<Alloc> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    if value.get("alloc").is_some_and(|alloc| alloc.is_object()) {
+        let genesis: Genesis = serde_json::from_value(value)?;
+        return Ok(genesis.alloc);
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Reads the genesis file or bare alloc at `path`.
+pub fn load(path: &Path) -> eyre::Result<Alloc> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| anyhow!("failed to read {}: {e}", path.display()))?;
+    parse(&contents).map_err(|e| anyhow!("invalid alloc {}: {e}", path.display()))
+}
+
+pub fn to_state(alloc: Alloc) -> State {
+    alloc
+        .into_iter()
+        .map(|(address, account)| (address, account.into()))
+        .collect()
+}
+
+pub fn from_state(state: &State) -> Alloc {
+    state
+        .iter()
+        .map(|(address, account)| (*address, account.into()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+    use pretty_assertions::assert_eq;
+
+    use super::{from_state, parse, to_state, Alloc};
+
+    #[test]
+    fn round_trips_alloc() {
+        let json = r#"{
+            "0x00000000000000000000000000000000000000a1": {
+                "balance": "0x10",
+                "nonce": "0x1",
+                "code": "0x6001",
+                "storage": { "0x01": "0x02" }
+            },
+            "0x00000000000000000000000000000000000000a2": { "balance": "0x0" }
+        }"#;
+        let alloc: Alloc = serde_json::from_str(json).expect("should parse the alloc");
+        let state = to_state(alloc.clone());
+        let account = &state[&Address::with_last_byte(0xA1)];
+        assert_eq!(1, account.nonce);
+        assert_eq!(U256::from(2), account.storage[&U256::from(1)]);
+        assert_eq!(alloc, from_state(&state));
+
+        let dumped = serde_json::to_value(from_state(&state)).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "0x00000000000000000000000000000000000000a1": {
+                    "balance": "0x10",
+                    "nonce": "0x1",
+                    "code": "0x6001",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001":
+                            "0x0000000000000000000000000000000000000000000000000000000000000002"
+                    }
+                },
+                "0x00000000000000000000000000000000000000a2": { "balance": "0x0" }
+            }),
+            dumped
+        );
+    }
+
+    #[test]
+    fn parses_genesis_files() {
+        let genesis = r#"{
+            "config": { "chainId": 1337 },
+            "difficulty": "0x1",
+            "gasLimit": "0x1c9c380",
+            "extraData": "0x",
+            "alloc": {
+                "0x00000000000000000000000000000000000000a1": { "balance": "1000" }
+            }
+        }"#;
+        let alloc = parse(genesis).expect("should parse the genesis file");
+        let alice = Address::with_last_byte(0xA1);
+        assert_eq!(U256::from(1000), alloc[&alice].balance);
+
+        let bare = r#"{ "0x00000000000000000000000000000000000000a1": { "balance": "0x3e8" } }"#;
+        assert_eq!(alloc, parse(bare).expect("should parse the alloc"));
+
+        assert_eq!(true, parse(r#"{ "alloc": [] }"#).is_err());
+    }
+}
@@ -0,0 +1,1061 @@
+//! Blocks and their execution: the pre-block system calls, the
+//! transactions, withdrawals, the requests to the consensus layer and,
+//! before the merge, mining rewards.
+
+use std::{cmp::Ordering, collections::BTreeMap, rc::Rc};
+
+use alloy_primitives::{address, b256, keccak256, Address, Bloom, Bytes, B256, B64, U256};
+use alloy_rlp::{RlpDecodable, RlpEncodable};
+use eyre::anyhow;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    env::{CfgEnv, Env},
+    envelope::SignedTransaction,
+    frame::{self, CallInputs, CallKind, ExitStatus},
+    journal::JournaledState,
+    receipt::{self, Receipt},
+    spec::SpecId,
+    state::Log,
+    transaction::{self, transact, ExecutionResult, GAS_PER_BLOB},
+    trie, Contract,
+};
+
+/// The caller of system contracts.
+pub const SYSTEM_ADDRESS: Address = address!("fffffffffffffffffffffffffffffffffffffffe");
+/// Stores the parent beacon block root of recent blocks (EIP-4788).
+pub const BEACON_ROOTS_ADDRESS: Address = address!("000F3df6D732807Ef1319fB7B8bB8522d0Beac02");
+/// Stores the hashes of recent blocks (EIP-2935).
+pub const HISTORY_STORAGE_ADDRESS: Address = address!("0000F90827F1C53a10cb7A02335B175320002935");
+/// Queues the withdrawals requested by validators' withdrawal credentials
+/// (EIP-7002).
+pub const WITHDRAWAL_REQUEST_ADDRESS: Address =
+    address!("00000961Ef480Eb55e80D19ad83579A64c007002");
+/// Queues the consolidations of validators (EIP-7251).
+pub const CONSOLIDATION_REQUEST_ADDRESS: Address =
+    address!("0000BBdDc7CE488642fb579F8B00f3a590007251");
+/// The beacon chain deposit contract of mainnet, whose logs are turned into
+/// deposit requests (EIP-6110).
+pub const DEPOSIT_CONTRACT_ADDRESS: Address = address!("00000000219ab540356cBB839Cbe05303d7705Fa");
+/// The topic of the deposit contract's `DepositEvent`.
+const DEPOSIT_EVENT_TOPIC: B256 =
+    b256!("649bbc62d0e31342afea4e5cd82d4049e7e1ee912fc0889aa790803be39038c5");
+/// The offset and size of the public key, withdrawal credentials, amount,
+/// signature and index in the ABI encoded data of a `DepositEvent`.
+const DEPOSIT_LAYOUT: [(usize, usize); 5] = [(160, 48), (256, 32), (320, 8), (384, 96), (512, 8)];
+/// The EIP-7685 types of requests.
+pub const DEPOSIT_REQUEST_TYPE: u8 = 0;
+pub const WITHDRAWAL_REQUEST_TYPE: u8 = 1;
+pub const CONSOLIDATION_REQUEST_TYPE: u8 = 2;
+/// The gas given to system calls, which is not charged to anyone.
+pub const SYSTEM_CALL_GAS: u64 = 30_000_000;
+/// The most blob gas a block can use (EIP-4844, EIP-7691).
+pub const MAX_BLOB_GAS_PER_BLOCK: u64 = 6 * GAS_PER_BLOB;
+pub const MAX_BLOB_GAS_PER_BLOCK_PRAGUE: u64 = 9 * GAS_PER_BLOB;
+/// The blob gas a block is expected to use. Blocks using more raise the
+/// blob base fee of their children, and blocks using less lower it.
+pub const TARGET_BLOB_GAS_PER_BLOCK: u64 = 3 * GAS_PER_BLOB;
+pub const TARGET_BLOB_GAS_PER_BLOCK_PRAGUE: u64 = 6 * GAS_PER_BLOB;
+/// The gas limit moves by less than `1 / 1024` of the parent's each block.
+pub const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+pub const MIN_GAS_LIMIT: u64 = 5000;
+/// The gas target of a block is its gas limit divided by this (EIP-1559).
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+/// The base fee moves by at most `1 / 8` each block.
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+/// The base fee of the first London block.
+pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+/// The most ommers a block can include, and how many blocks older than it
+/// they can be.
+pub const MAX_OMMERS: usize = 2;
+pub const MAX_OMMER_DEPTH: u64 = 6;
+/// The hash of an empty list of ommers, which all blocks have after the
+/// merge.
+pub const EMPTY_OMMERS_HASH: B256 =
+    b256!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347");
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+#[rlp(trailing)]
+pub struct Header {
+    pub parent_hash: B256,
+    pub ommers_hash: B256,
+    pub beneficiary: Address,
+    pub state_root: B256,
+    pub transactions_root: B256,
+    pub receipts_root: B256,
+    pub logs_bloom: Bloom,
+    pub difficulty: U256,
+    pub number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Bytes,
+    pub mix_hash: B256,
+    pub nonce: B64,
+    /// Added by London (EIP-1559).
+    pub base_fee_per_gas: Option<u64>,
+    /// Added by Shanghai (EIP-4895).
+    pub withdrawals_root: Option<B256>,
+    /// Added by Cancun (EIP-4844).
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    /// Added by Cancun (EIP-4788).
+    pub parent_beacon_block_root: Option<B256>,
+    /// Added by Prague (EIP-7685).
+    pub requests_hash: Option<B256>,
+}
+
+impl Header {
+    pub fn hash(&self) -> B256 {
+        keccak256(alloy_rlp::encode(self))
+    }
+}
+
+/// A withdrawal from the beacon chain (EIP-4895).
+#[derive(Clone, Debug, Default, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct Withdrawal {
+    pub index: u64,
+    pub validator_index: u64,
+    pub address: Address,
+    /// The amount in gwei.
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+#[rlp(trailing)]
+pub struct Block {
+    pub header: Header,
+    pub transactions: Vec<SignedTransaction>,
+    pub ommers: Vec<Header>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+/// The outcome of a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockResult {
+    pub results: Vec<ExecutionResult>,
+    pub receipts: Vec<Receipt>,
+    pub gas_used: u64,
+    pub blob_gas_used: u64,
+    pub state_root: B256,
+    pub receipts_root: B256,
+    pub logs_bloom: Bloom,
+    /// The requests to the consensus layer, each prefixed with its type.
+    /// Only made from Prague on.
+    pub requests: Option<Vec<Bytes>>,
+}
+
+impl BlockResult {
+    pub fn requests_hash(&self) -> Option<B256> {
+        self.requests.as_deref().map(requests_hash)
+    }
+}
+
+/// Checks the parts of `header` that don't depend on its execution against
+/// its parent, under the rules of `spec`.
+pub fn validate_header(header: &Header, parent: &Header, spec: SpecId) -> eyre::Result<()> {
+    if header.parent_hash != parent.hash() {
+        return Err(anyhow!("unknown parent"));
+    }
+    if parent.number.checked_add(1) != Some(header.number) {
+        return Err(anyhow!("invalid block number"));
+    }
+    if header.timestamp <= parent.timestamp {
+        return Err(anyhow!("timestamp older than parent"));
+    }
+    if header.gas_used > header.gas_limit {
+        return Err(anyhow!("gas used exceeds gas limit"));
+    }
+    if header.extra_data.len() > 32 {
+        return Err(anyhow!("extra data too long"));
+    }
+
+    // The gas limit of the last block before London counts as a target.
+    let is_london_fork = spec.is_enabled_in(SpecId::London) && parent.base_fee_per_gas.is_none();
+    let parent_gas_limit = if is_london_fork {
+        parent
+            .gas_limit
+            .checked_mul(ELASTICITY_MULTIPLIER)
+            .ok_or_else(|| anyhow!("invalid gas limit"))?
+    } else {
+        parent.gas_limit
+    };
+    if header.gas_limit.abs_diff(parent_gas_limit) >= parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR
+        || header.gas_limit < MIN_GAS_LIMIT
+    {
+        return Err(anyhow!("invalid gas limit"));
+    }
+    if spec.is_enabled_in(SpecId::London) {
+        let base_fee = next_base_fee(parent);
+        if header.base_fee_per_gas != Some(base_fee) {
+            return Err(anyhow!(
+                "invalid base fee: have {:?}, want {base_fee}",
+                header.base_fee_per_gas
+            ));
+        }
+    }
+    if spec.is_enabled_in(SpecId::Cancun) {
+        let excess_blob_gas = next_excess_blob_gas(parent, spec)?;
+        if header.excess_blob_gas != Some(excess_blob_gas) {
+            return Err(anyhow!(
+                "invalid excess blob gas: have {:?}, want {excess_blob_gas}",
+                header.excess_blob_gas
+            ));
+        }
+    }
+    if spec.is_enabled_in(SpecId::Merge) {
+        if !header.difficulty.is_zero() {
+            return Err(anyhow!("invalid difficulty"));
+        }
+        if header.nonce != B64::ZERO {
+            return Err(anyhow!("invalid nonce"));
+        }
+        if header.ommers_hash != EMPTY_OMMERS_HASH {
+            return Err(anyhow!("invalid ommers hash"));
+        }
+    }
+    Ok(())
+}
+
+/// The base fee of the child of `parent`, which rises when `parent` used
+/// more than its gas target and falls when it used less (EIP-1559).
+pub fn next_base_fee(parent: &Header) -> u64 {
+    let Some(base_fee) = parent.base_fee_per_gas else {
+        return INITIAL_BASE_FEE;
+    };
+    let target = parent.gas_limit / ELASTICITY_MULTIPLIER;
+    let change = |delta: u64| {
+        (base_fee as u128 * delta as u128
+            / target.max(1) as u128
+            / BASE_FEE_MAX_CHANGE_DENOMINATOR as u128) as u64
+    };
+    match parent.gas_used.cmp(&target) {
+        Ordering::Equal => base_fee,
+        Ordering::Greater => base_fee.saturating_add(change(parent.gas_used - target).max(1)),
+        Ordering::Less => base_fee - change(target - parent.gas_used),
+    }
+}
+
+/// The excess blob gas of the child of `parent`: the blob gas used above
+/// the target, accumulated over the blocks (EIP-4844).
+pub fn next_excess_blob_gas(parent: &Header, spec: SpecId) -> eyre::Result<u64> {
+    let target = if spec.is_enabled_in(SpecId::Prague) {
+        TARGET_BLOB_GAS_PER_BLOCK_PRAGUE
+    } else {
+        TARGET_BLOB_GAS_PER_BLOCK
+    };
+    let excess = parent.excess_blob_gas.unwrap_or_default();
+    let used = parent.blob_gas_used.unwrap_or_default();
+    let total = excess
+        .checked_add(used)
+        .ok_or_else(|| anyhow!("excess blob gas overflow"))?;
+    Ok(total.saturating_sub(target))
+}
+
+/// Checks `block` against the outcome of executing it.
+pub fn validate_result(block: &Block, result: &BlockResult) -> eyre::Result<()> {
+    let header = &block.header;
+    if header.gas_used != result.gas_used {
+        return Err(anyhow!(
+            "invalid gas used: have {}, want {}",
+            result.gas_used,
+            header.gas_used
+        ));
+    }
+    if header
+        .blob_gas_used
+        .is_some_and(|gas| gas != result.blob_gas_used)
+    {
+        return Err(anyhow!("invalid blob gas used"));
+    }
+    if header.state_root != result.state_root {
+        return Err(anyhow!(
+            "invalid state root: have {}, want {}",
+            result.state_root,
+            header.state_root
+        ));
+    }
+    if header.receipts_root != result.receipts_root {
+        return Err(anyhow!(
+            "invalid receipts root: have {}, want {}",
+            result.receipts_root,
+            header.receipts_root
+        ));
+    }
+    if header.logs_bloom != result.logs_bloom {
+        return Err(anyhow!("invalid logs bloom"));
+    }
+    if header.requests_hash != result.requests_hash() {
+        return Err(anyhow!("invalid requests hash"));
+    }
+    if header.ommers_hash != keccak256(alloy_rlp::encode(&block.ommers)) {
+        return Err(anyhow!("invalid ommers hash"));
+    }
+    let transactions_root =
+        trie::ordered_trie_root(block.transactions.iter().map(|tx| &tx.encoded));
+    if header.transactions_root != transactions_root {
+        return Err(anyhow!("invalid transactions root"));
+    }
+    let withdrawals_root = block
+        .withdrawals
+        .as_ref()
+        .map(|withdrawals| trie::ordered_trie_root(withdrawals.iter().map(alloy_rlp::encode)));
+    if header.withdrawals_root != withdrawals_root {
+        return Err(anyhow!("invalid withdrawals root"));
+    }
+    Ok(())
+}
+
+/// The environment shared by the transactions of the block with `header`.
+pub fn block_env(cfg: CfgEnv, header: &Header) -> Env {
+    let spec = cfg.spec;
+    let mut env = Env {
+        cfg,
+        ..Default::default()
+    };
+    env.block.number = U256::from(header.number);
+    env.block.coinbase = header.beneficiary;
+    env.block.timestamp = U256::from(header.timestamp);
+    env.block.gas_limit = U256::from(header.gas_limit);
+    env.block.basefee = U256::from(header.base_fee_per_gas.unwrap_or_default());
+    env.block.difficulty = header.difficulty;
+    env.block.prevrandao = spec.is_enabled_in(SpecId::Merge).then_some(header.mix_hash);
+    env.block.blob_basefee = header
+        .excess_blob_gas
+        .map(|excess| transaction::blob_basefee(excess, spec));
+    env
+}
+
+/// Executes `block` on top of `journal`, which is left in an unspecified
+/// state if the block turns out to be invalid. `block_hashes` holds the
+/// hashes of its ancestors by number, for `BLOCKHASH`.
+pub fn execute_block(
+    journal: &mut JournaledState,
+    cfg: CfgEnv,
+    block: &Block,
+    block_hashes: &BTreeMap<u64, B256>,
+) -> eyre::Result<BlockResult> {
+    let header = &block.header;
+    let spec = cfg.spec;
+    validate_ommers(block)?;
+    let mut env = block_env(cfg, header);
+    env.block.block_hashes = block_hashes.clone();
+
+    if spec.is_enabled_in(SpecId::Cancun) {
+        let root = header
+            .parent_beacon_block_root
+            .ok_or_else(|| anyhow!("missing parent beacon block root"))?;
+        system_call(journal, &env, BEACON_ROOTS_ADDRESS, root.as_slice())?;
+    }
+    if spec.is_enabled_in(SpecId::Prague) {
+        system_call(
+            journal,
+            &env,
+            HISTORY_STORAGE_ADDRESS,
+            header.parent_hash.as_slice(),
+        )?;
+    }
+
+    let mut usage = GasUsage::default();
+    let mut results = Vec::with_capacity(block.transactions.len());
+    for (i, tx) in block.transactions.iter().enumerate() {
+        let result = apply_transaction(journal, &mut env, &mut usage, tx)
+            .map_err(|e| anyhow!("transaction {i}: {e}"))?;
+        results.push(result);
+    }
+
+    let receipts = receipts(
+        spec,
+        block
+            .transactions
+            .iter()
+            .zip(&results)
+            .map(|(tx, result)| (tx.transaction.tx_type(), result)),
+    );
+    let logs: Vec<&Log> = receipts.iter().flat_map(|receipt| &receipt.logs).collect();
+
+    apply_withdrawals(journal, block.withdrawals.iter().flatten());
+    let requests = if spec.is_enabled_in(SpecId::Prague) {
+        Some(collect_requests(journal, &env, logs.iter().copied())?)
+    } else {
+        None
+    };
+    reward_miners(journal, spec, block);
+    // Zero withdrawals and rewards still touch their recipients, which are
+    // then removed if empty (EIP-161).
+    if spec.is_enabled_in(SpecId::SpuriousDragon) {
+        journal.remove_touched_empty();
+    }
+    journal.finish_transaction();
+
+    Ok(BlockResult {
+        gas_used: usage.gas_used,
+        blob_gas_used: usage.blob_gas_used,
+        state_root: trie::state_root(&journal.state),
+        receipts_root: receipt::receipts_root(&receipts),
+        logs_bloom: receipt::logs_bloom(logs),
+        requests,
+        results,
+        receipts,
+    })
+}
+
+/// The gas and blob gas used by the transactions of a block so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasUsage {
+    pub gas_used: u64,
+    pub blob_gas_used: u64,
+}
+
+/// Applies `tx` after the transactions accounted for in `usage`, checking
+/// that it fits in the gas and blob gas left in the block of `env`.
+pub fn apply_transaction(
+    journal: &mut JournaledState,
+    env: &mut Env,
+    usage: &mut GasUsage,
+    tx: &SignedTransaction,
+) -> eyre::Result<ExecutionResult> {
+    let sender = tx.recover_sender()?;
+    env.tx = tx.transaction.tx_env(sender);
+    let gas_limit: u64 = env.block.gas_limit.saturating_to();
+    if env.tx.gas_limit > gas_limit.saturating_sub(usage.gas_used) {
+        return Err(anyhow!("block gas limit reached"));
+    }
+    let max_blob_gas = if env.cfg.spec.is_enabled_in(SpecId::Prague) {
+        MAX_BLOB_GAS_PER_BLOCK_PRAGUE
+    } else {
+        MAX_BLOB_GAS_PER_BLOCK
+    };
+    let blob_gas = GAS_PER_BLOB * env.tx.blob_hashes.len() as u64;
+    if usage.blob_gas_used + blob_gas > max_blob_gas {
+        return Err(anyhow!("block blob gas limit reached"));
+    }
+
+    let result = transact(journal, env.clone())?;
+    usage.gas_used += result.gas_used;
+    usage.blob_gas_used += blob_gas;
+    Ok(result)
+}
+
+/// Builds the receipts of the transactions of a block from the type and
+/// result of each. Before Byzantium, they carry the state root after the
+/// transaction rather than its status (EIP-658).
+pub fn receipts<'a>(
+    spec: SpecId,
+    results: impl IntoIterator<Item = (u8, &'a ExecutionResult)>,
+) -> Vec<Receipt> {
+    let results: Vec<_> = results.into_iter().collect();
+    let mut receipts = receipt::from_results(results.iter().copied());
+    if !spec.is_enabled_in(SpecId::Byzantium) {
+        for (receipt, (_, result)) in receipts.iter_mut().zip(&results) {
+            receipt.state_root = Some(result.state_root);
+        }
+    }
+    receipts
+}
+
+/// Credits the withdrawn amounts, in gwei, to their recipients.
+pub fn apply_withdrawals<'a>(
+    journal: &mut JournaledState,
+    withdrawals: impl IntoIterator<Item = &'a Withdrawal>,
+) {
+    for withdrawal in withdrawals {
+        let amount = U256::from(withdrawal.amount) * U256::from(1_000_000_000);
+        let balance = journal.balance(&withdrawal.address);
+        journal.set_balance(withdrawal.address, balance + amount);
+    }
+}
+
+/// Calls a system contract with `data`, if it has been deployed, returning
+/// its output.
+pub fn system_call(
+    journal: &mut JournaledState,
+    env: &Env,
+    address: Address,
+    data: &[u8],
+) -> eyre::Result<Bytes> {
+    if journal.code(&address).is_empty() {
+        return Ok(Bytes::new());
+    }
+
+    let mut contract = Contract::new(address, Bytes::new(), Bytes::copy_from_slice(data));
+    contract.caller = SYSTEM_ADDRESS;
+    let inputs = CallInputs {
+        contract,
+        code_address: address,
+        gas_limit: SYSTEM_CALL_GAS,
+        kind: CallKind::Call,
+        is_static: false,
+        depth: 0,
+    };
+    let result = frame::call(journal, Rc::new(env.clone()), inputs);
+    // System calls only exist after EIP-161.
+    journal.remove_touched_empty();
+    journal.finish_transaction();
+    if result.status != ExitStatus::Success {
+        return Err(anyhow!("system call to {address} failed"));
+    }
+    Ok(result.output)
+}
+
+/// Collects the requests of a block to the consensus layer (EIP-7685):
+/// deposits from the logs of the chain's deposit contract, then the withdrawal and
+/// consolidation requests dequeued by calling their system contracts. Each
+/// is prefixed with its type, and types without requests are left out.
+pub fn collect_requests<'a>(
+    journal: &mut JournaledState,
+    env: &Env,
+    logs: impl IntoIterator<Item = &'a Log>,
+) -> eyre::Result<Vec<Bytes>> {
+    let mut deposits = Vec::new();
+    for log in logs {
+        if log.address == env.cfg.deposit_contract
+            && log.topics.first() == Some(&DEPOSIT_EVENT_TOPIC)
+        {
+            deposits.extend(deposit_request(&log.data)?);
+        }
+    }
+    let mut requests = vec![(DEPOSIT_REQUEST_TYPE, Bytes::from(deposits))];
+    for (request_type, address) in [
+        (WITHDRAWAL_REQUEST_TYPE, WITHDRAWAL_REQUEST_ADDRESS),
+        (CONSOLIDATION_REQUEST_TYPE, CONSOLIDATION_REQUEST_ADDRESS),
+    ] {
+        // Unlike the other system contracts, these must have been deployed.
+        if journal.code(&address).is_empty() {
+            return Err(anyhow!("missing system contract {address}"));
+        }
+        requests.push((request_type, system_call(journal, env, address, &[])?));
+    }
+
+    Ok(requests
+        .into_iter()
+        .filter(|(_, data)| !data.is_empty())
+        .map(|(request_type, data)| [&[request_type][..], &data].concat().into())
+        .collect())
+}
+
+/// Turns the data of a `DepositEvent` into a deposit request: the public key,
+/// withdrawal credentials, amount, signature and index, concatenated.
+fn deposit_request(data: &[u8]) -> eyre::Result<Vec<u8>> {
+    let word = |offset: usize| U256::from_be_slice(&data[offset..offset + 32]);
+    if data.len() != 576 {
+        return Err(anyhow!("invalid deposit log"));
+    }
+    let mut request = Vec::with_capacity(192);
+    for (i, (offset, size)) in DEPOSIT_LAYOUT.into_iter().enumerate() {
+        if word(i * 32) != U256::from(offset) || word(offset) != U256::from(size) {
+            return Err(anyhow!("invalid deposit log"));
+        }
+        request.extend_from_slice(&data[offset + 32..offset + 32 + size]);
+    }
+    Ok(request)
+}
+
+/// The commitment to `requests` in the header (EIP-7685): the hash of the
+/// hashes of each request.
+pub fn requests_hash(requests: &[Bytes]) -> B256 {
+    let mut hasher = Sha256::new();
+    for request in requests {
+        hasher.update(Sha256::digest(request));
+    }
+    B256::from_slice(&hasher.finalize())
+}
+
+/// Checks that `block` has at most two ommers, each one to six blocks
+/// older than it.
+fn validate_ommers(block: &Block) -> eyre::Result<()> {
+    if block.ommers.len() > MAX_OMMERS {
+        return Err(anyhow!("too many ommers"));
+    }
+    for ommer in &block.ommers {
+        let depth = block.header.number.checked_sub(ommer.number);
+        if !depth.is_some_and(|depth| (1..=MAX_OMMER_DEPTH).contains(&depth)) {
+            return Err(anyhow!("invalid ommer number {}", ommer.number));
+        }
+    }
+    Ok(())
+}
+
+/// Credits the block and ommer rewards of proof-of-work blocks. The ommers
+/// must have been validated.
+fn reward_miners(journal: &mut JournaledState, spec: SpecId, block: &Block) {
+    let reward = if spec.is_enabled_in(SpecId::Merge) {
+        return;
+    } else if spec.is_enabled_in(SpecId::Constantinople) {
+        U256::from(2_000_000_000_000_000_000u128)
+    } else if spec.is_enabled_in(SpecId::Byzantium) {
+        U256::from(3_000_000_000_000_000_000u128)
+    } else {
+        U256::from(5_000_000_000_000_000_000u128)
+    };
+
+    let number = block.header.number;
+    for ommer in &block.ommers {
+        // Ommers get less the older they are.
+        let depth = number.saturating_sub(ommer.number);
+        let ommer_reward = reward * U256::from(8u64.saturating_sub(depth)) / U256::from(8);
+        let balance = journal.balance(&ommer.beneficiary);
+        journal.set_balance(ommer.beneficiary, balance + ommer_reward);
+    }
+    let miner_reward = reward + reward / U256::from(32) * U256::from(block.ommers.len());
+    let balance = journal.balance(&block.header.beneficiary);
+    journal.set_balance(block.header.beneficiary, balance + miner_reward);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, rc::Rc};
+
+    use alloy_primitives::{b256, Address, Bytes, B256, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        env::{CfgEnv, Env},
+        frame::{self, CallInputs, CallKind, ExitStatus},
+        journal::JournaledState,
+        spec::SpecId,
+        state::{Account, Log, State},
+        trie::EMPTY_ROOT,
+        Contract,
+    };
+
+    use crate::transaction::GAS_PER_BLOB;
+
+    use super::{
+        block_env, collect_requests, execute_block, next_base_fee, next_excess_blob_gas,
+        requests_hash, validate_header, validate_result, Block, Header, Withdrawal,
+        BEACON_ROOTS_ADDRESS, CONSOLIDATION_REQUEST_ADDRESS, DEPOSIT_CONTRACT_ADDRESS,
+        DEPOSIT_EVENT_TOPIC, DEPOSIT_LAYOUT, EMPTY_OMMERS_HASH, HISTORY_STORAGE_ADDRESS,
+        INITIAL_BASE_FEE, WITHDRAWAL_REQUEST_ADDRESS,
+    };
+
+    #[test]
+    fn round_trips_headers() {
+        let header = Header {
+            number: 1,
+            base_fee_per_gas: Some(7),
+            ..Default::default()
+        };
+        let encoded = alloy_rlp::encode(&header);
+        let decoded: Header =
+            alloy_rlp::Decodable::decode(&mut encoded.as_slice()).expect("should decode");
+        assert_eq!(header, decoded);
+        assert_eq!(None, decoded.withdrawals_root);
+    }
+
+    #[test]
+    fn validates_headers() {
+        let parent = Header {
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        let mut header = Header {
+            parent_hash: parent.hash(),
+            number: 1,
+            timestamp: 12,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            true,
+            validate_header(&header, &parent, SpecId::Frontier).is_ok()
+        );
+
+        header.timestamp = 0;
+        let err = validate_header(&header, &parent, SpecId::Frontier).unwrap_err();
+        assert_eq!("timestamp older than parent", err.to_string());
+
+        header.extra_data = Bytes::from(vec![0; 33]);
+        header.timestamp = 12;
+        let err = validate_header(&header, &parent, SpecId::Frontier).unwrap_err();
+        assert_eq!("extra data too long", err.to_string());
+
+        // The limit can move by less than 29296 gas.
+        header.extra_data = Bytes::new();
+        header.gas_limit = 30_029_296;
+        let err = validate_header(&header, &parent, SpecId::Frontier).unwrap_err();
+        assert_eq!("invalid gas limit", err.to_string());
+        header.gas_limit = 30_029_295;
+        assert_eq!(
+            true,
+            validate_header(&header, &parent, SpecId::Frontier).is_ok()
+        );
+    }
+
+    #[test]
+    fn validates_fee_market_headers() {
+        let parent = Header {
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(1_000),
+            gas_used: 30_000_000,
+            excess_blob_gas: Some(0),
+            blob_gas_used: Some(3 * GAS_PER_BLOB),
+            ..Default::default()
+        };
+        let mut header = Header {
+            parent_hash: parent.hash(),
+            ommers_hash: EMPTY_OMMERS_HASH,
+            number: 1,
+            timestamp: 12,
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(1_125),
+            excess_blob_gas: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            true,
+            validate_header(&header, &parent, SpecId::Cancun).is_ok()
+        );
+
+        header.base_fee_per_gas = Some(1_000);
+        let err = validate_header(&header, &parent, SpecId::Cancun).unwrap_err();
+        assert_eq!(
+            "invalid base fee: have Some(1000), want 1125",
+            err.to_string()
+        );
+
+        header.base_fee_per_gas = Some(1_125);
+        header.difficulty = U256::from(1);
+        let err = validate_header(&header, &parent, SpecId::Cancun).unwrap_err();
+        assert_eq!("invalid difficulty", err.to_string());
+
+        // Prague raised the blob target above what the parent used.
+        header.difficulty = U256::ZERO;
+        header.excess_blob_gas = Some(0);
+        assert_eq!(
+            true,
+            validate_header(&header, &parent, SpecId::Prague).is_ok()
+        );
+        assert_eq!(
+            3 * GAS_PER_BLOB,
+            next_excess_blob_gas(
+                &Header {
+                    blob_gas_used: Some(6 * GAS_PER_BLOB),
+                    ..parent.clone()
+                },
+                SpecId::Cancun
+            )
+            .expect("should not overflow")
+        );
+
+        // Overflowing parents are rejected rather than panicking.
+        let overflowing = Header {
+            number: u64::MAX,
+            ..parent.clone()
+        };
+        header.parent_hash = overflowing.hash();
+        let err = validate_header(&header, &overflowing, SpecId::Cancun).unwrap_err();
+        assert_eq!("invalid block number", err.to_string());
+
+        let overflowing = Header {
+            excess_blob_gas: Some(u64::MAX),
+            ..parent.clone()
+        };
+        header.parent_hash = overflowing.hash();
+        let err = validate_header(&header, &overflowing, SpecId::Cancun).unwrap_err();
+        assert_eq!("excess blob gas overflow", err.to_string());
+    }
+
+    #[test]
+    fn computes_base_fees() {
+        let parent = |gas_used| Header {
+            gas_limit: 30_000_000,
+            gas_used,
+            base_fee_per_gas: Some(1_000_000_000),
+            ..Default::default()
+        };
+        assert_eq!(1_000_000_000, next_base_fee(&parent(15_000_000)));
+        assert_eq!(1_125_000_000, next_base_fee(&parent(30_000_000)));
+        assert_eq!(875_000_000, next_base_fee(&parent(0)));
+        // The first London block.
+        assert_eq!(INITIAL_BASE_FEE, next_base_fee(&Header::default()));
+    }
+
+    #[test]
+    fn processes_withdrawals() {
+        let mut journal = JournaledState::new(State::new());
+        let cfg = CfgEnv {
+            spec: SpecId::Shanghai,
+            ..Default::default()
+        };
+        let block = Block {
+            withdrawals: Some(vec![Withdrawal {
+                address: Address::with_last_byte(1),
+                amount: 2,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let result =
+            execute_block(&mut journal, cfg, &block, &BTreeMap::new()).expect("should execute");
+        assert_eq!(0, result.gas_used);
+        assert_eq!(
+            U256::from(2_000_000_000),
+            journal.balance(&Address::with_last_byte(1))
+        );
+    }
+
+    #[test]
+    fn removes_empty_withdrawal_recipients() {
+        let mut journal = JournaledState::new(State::new());
+        let cfg = CfgEnv {
+            spec: SpecId::Shanghai,
+            ..Default::default()
+        };
+        let recipient = Address::with_last_byte(9);
+        let block = Block {
+            withdrawals: Some(vec![Withdrawal {
+                address: recipient,
+                amount: 0,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let result =
+            execute_block(&mut journal, cfg, &block, &BTreeMap::new()).expect("should execute");
+        assert_eq!(false, journal.exists(&recipient));
+        assert_eq!(EMPTY_ROOT, result.state_root);
+    }
+
+    #[test]
+    fn rewards_miners() {
+        let mut journal = JournaledState::new(State::new());
+        let cfg = CfgEnv {
+            spec: SpecId::Byzantium,
+            ..Default::default()
+        };
+        let miner = Address::with_last_byte(1);
+        let uncle = Address::with_last_byte(2);
+        let block = Block {
+            header: Header {
+                number: 10,
+                beneficiary: miner,
+                ..Default::default()
+            },
+            ommers: vec![Header {
+                number: 9,
+                beneficiary: uncle,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        execute_block(&mut journal, cfg, &block, &BTreeMap::new()).expect("should execute");
+
+        let reward = U256::from(3_000_000_000_000_000_000u128);
+        assert_eq!(reward + reward / U256::from(32), journal.balance(&miner));
+        assert_eq!(
+            reward * U256::from(7) / U256::from(8),
+            journal.balance(&uncle)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_ommers() {
+        let cfg = CfgEnv {
+            spec: SpecId::Byzantium,
+            ..Default::default()
+        };
+        let ommer = |number| Header {
+            number,
+            ..Default::default()
+        };
+        let mut block = Block {
+            header: Header {
+                number: 10,
+                ..Default::default()
+            },
+            ommers: vec![ommer(3)],
+            ..Default::default()
+        };
+        for (ommers, error) in [
+            (vec![ommer(3)], "invalid ommer number 3"),
+            (vec![ommer(10)], "invalid ommer number 10"),
+            (vec![ommer(11)], "invalid ommer number 11"),
+            (vec![ommer(9), ommer(8), ommer(7)], "too many ommers"),
+        ] {
+            block.ommers = ommers;
+            let mut journal = JournaledState::new(State::new());
+            let err =
+                execute_block(&mut journal, cfg.clone(), &block, &BTreeMap::new()).unwrap_err();
+            assert_eq!(error, err.to_string());
+        }
+
+        // The header commits to the ommers.
+        block.ommers = vec![ommer(4)];
+        block.header.ommers_hash = EMPTY_OMMERS_HASH;
+        let mut journal = JournaledState::new(State::new());
+        let result =
+            execute_block(&mut journal, cfg, &block, &BTreeMap::new()).expect("should execute");
+        block.header.state_root = result.state_root;
+        block.header.receipts_root = result.receipts_root;
+        let err = validate_result(&block, &result).unwrap_err();
+        assert_eq!("invalid ommers hash", err.to_string());
+    }
+
+    #[test]
+    fn collects_requests() {
+        // PUSH1 0x2a
+        // PUSH1 0x00
+        // MSTORE8
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // RETURN
+        let mut state = State::new();
+        state.insert(
+            WITHDRAWAL_REQUEST_ADDRESS,
+            Account {
+                code: "0x602a60005360016000f3".parse().unwrap(),
+                ..Default::default()
+            },
+        );
+        state.insert(
+            CONSOLIDATION_REQUEST_ADDRESS,
+            Account {
+                code: "0x00".parse().unwrap(),
+                ..Default::default()
+            },
+        );
+        let mut journal = JournaledState::new(state);
+
+        // A deposit whose fields are filled with 1, 2, 3, 4 and 5.
+        let mut data = vec![0; 576];
+        let mut expected = vec![0];
+        for (i, (offset, size)) in DEPOSIT_LAYOUT.into_iter().enumerate() {
+            data[i * 32 + 30..i * 32 + 32].copy_from_slice(&(offset as u16).to_be_bytes());
+            data[offset + 31] = size as u8;
+            data[offset + 32..offset + 32 + size].fill(i as u8 + 1);
+            expected.extend(vec![i as u8 + 1; size]);
+        }
+        let deposit = Log {
+            address: DEPOSIT_CONTRACT_ADDRESS,
+            topics: vec![DEPOSIT_EVENT_TOPIC],
+            data: data.into(),
+        };
+        let requests = collect_requests(&mut journal, &Env::default(), [&deposit])
+            .expect("should collect requests");
+        assert_eq!(
+            vec![Bytes::from(expected), Bytes::from_static(&[1, 0x2a])],
+            requests
+        );
+
+        let invalid = Log {
+            data: Bytes::from_static(&[0; 32]),
+            ..deposit
+        };
+        let err = collect_requests(&mut journal, &Env::default(), [&invalid]).unwrap_err();
+        assert_eq!("invalid deposit log", err.to_string());
+    }
+
+    #[test]
+    fn hashes_requests() {
+        assert_eq!(
+            b256!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            requests_hash(&[])
+        );
+    }
+
+    /// The code deployed at `BEACON_ROOTS_ADDRESS` by EIP-4788.
+    const BEACON_ROOTS_CODE: &str = "0x3373fffffffffffffffffffffffffffffffffffffffe14604d57602036146024575f5ffd5b5f35801560495762001fff810690815414603c575f5ffd5b62001fff01545f5260205ff35b5f5ffd5b62001fff42064281555f359062001fff015500";
+    /// The code deployed at `HISTORY_STORAGE_ADDRESS` by EIP-2935.
+    const HISTORY_STORAGE_CODE: &str = "0x3373fffffffffffffffffffffffffffffffffffffffe14604657602036036042575f35600143038111604257611fff81430311604257611fff9006545f5260205ff35b5f5ffd5b5f35611fff60014303065500";
+
+    /// Calls `address` with `input` from an ordinary account.
+    fn query(journal: &mut JournaledState, env: &Env, address: Address, input: U256) -> Bytes {
+        let input = Bytes::copy_from_slice(&input.to_be_bytes::<32>());
+        let mut contract = Contract::new(address, Bytes::new(), input);
+        contract.caller = Address::with_last_byte(1);
+        let inputs = CallInputs {
+            contract,
+            code_address: address,
+            gas_limit: 100_000,
+            kind: CallKind::Call,
+            is_static: false,
+            depth: 0,
+        };
+        let result = frame::call(journal, Rc::new(env.clone()), inputs);
+        assert_eq!(ExitStatus::Success, result.status);
+        result.output
+    }
+
+    #[test]
+    fn runs_system_contracts() {
+        let mut state = State::new();
+        for (address, code) in [
+            (BEACON_ROOTS_ADDRESS, BEACON_ROOTS_CODE),
+            (HISTORY_STORAGE_ADDRESS, HISTORY_STORAGE_CODE),
+            (WITHDRAWAL_REQUEST_ADDRESS, "0x00"),
+            (CONSOLIDATION_REQUEST_ADDRESS, "0x00"),
+        ] {
+            state.insert(
+                address,
+                Account {
+                    code: code.parse().unwrap(),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut journal = JournaledState::new(state);
+        let cfg = CfgEnv {
+            spec: SpecId::Prague,
+            ..Default::default()
+        };
+        let beacon_root = B256::repeat_byte(0xBE);
+        let parent_hash = B256::repeat_byte(0xAB);
+        let block = Block {
+            header: Header {
+                parent_hash,
+                number: 1,
+                timestamp: 12,
+                parent_beacon_block_root: Some(beacon_root),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        execute_block(&mut journal, cfg.clone(), &block, &BTreeMap::new()).expect("should execute");
+
+        // Both ring buffers hold 8191 entries.
+        assert_eq!(
+            U256::from(12),
+            journal.sload(&BEACON_ROOTS_ADDRESS, U256::from(12))
+        );
+        assert_eq!(
+            U256::from_be_bytes(beacon_root.0),
+            journal.sload(&BEACON_ROOTS_ADDRESS, U256::from(12 + 8191))
+        );
+        assert_eq!(
+            U256::from_be_bytes(parent_hash.0),
+            journal.sload(&HISTORY_STORAGE_ADDRESS, U256::ZERO)
+        );
+
+        let env = block_env(cfg, &block.header);
+        assert_eq!(
+            beacon_root.as_slice(),
+            query(&mut journal, &env, BEACON_ROOTS_ADDRESS, U256::from(12)).as_ref()
+        );
+        assert_eq!(
+            parent_hash.as_slice(),
+            query(&mut journal, &env, HISTORY_STORAGE_ADDRESS, U256::ZERO).as_ref()
+        );
+    }
+
+    #[test]
+    fn requires_request_contracts() {
+        let mut journal = JournaledState::new(State::new());
+        let cfg = CfgEnv {
+            spec: SpecId::Prague,
+            ..Default::default()
+        };
+        let block = Block {
+            header: Header {
+                parent_beacon_block_root: Some(B256::ZERO),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = execute_block(&mut journal, cfg, &block, &BTreeMap::new()).unwrap_err();
+        assert_eq!(
+            format!("missing system contract {WITHDRAWAL_REQUEST_ADDRESS}"),
+            err.to_string()
+        );
+    }
+}
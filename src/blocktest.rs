@@ -0,0 +1,375 @@
+//! Runs the `BlockchainTests` of ethereum/tests.
+//!
+//! Each test imports a chain of RLP encoded blocks on top of a genesis
+//! block and pre-state. Blocks flagged with an exception must be rejected
+//! without affecting the chain. The test passes if the final state and head
+//! match the expected ones.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use alloy_primitives::{Bytes, B256, U256};
+use alloy_rlp::Decodable;
+use eyre::anyhow;
+use serde::Deserialize;
+
+use crate::{
+    alloc::{self, Alloc},
+    block::{self, Block, Header},
+    env::CfgEnv,
+    journal::JournaledState,
+    spec::SpecId,
+    statetest::{self, TestResult},
+    trie,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTest {
+    /// The fork, or a transition between two forks such as
+    /// `ShanghaiToCancunAtTime15k`.
+    pub network: String,
+    #[serde(rename = "genesisRLP")]
+    pub genesis_rlp: Bytes,
+    pub pre: Alloc,
+    pub post_state: Option<Alloc>,
+    pub post_state_hash: Option<B256>,
+    #[serde(rename = "lastblockhash")]
+    pub last_block_hash: B256,
+    pub blocks: Vec<TestBlock>,
+    pub config: Option<TestConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestBlock {
+    pub rlp: Bytes,
+    /// Set when the block is invalid and must be rejected.
+    pub expect_exception: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestConfig {
+    #[serde(rename = "chainid")]
+    pub chain_id: Option<U256>,
+}
+
+/// Runs every test in the file or, recursively, the directory at `path`.
+pub fn run_path(path: &Path) -> eyre::Result<Vec<TestResult>> {
+    let mut results = Vec::new();
+    for file in statetest::json_files(path)? {
+        results.extend(run_file(&file)?);
+    }
+    Ok(results)
+}
+
+/// Runs every test in the JSON file at `path`.
+pub fn run_file(path: &Path) -> eyre::Result<Vec<TestResult>> {
+    let contents = fs::read_to_string(path)?;
+    let tests: BTreeMap<String, BlockTest> = serde_json::from_str(&contents)
+        .map_err(|e| anyhow!("invalid test file {}: {e}", path.display()))?;
+    Ok(tests
+        .iter()
+        .map(|(name, test)| run_test(name, test))
+        .collect())
+}
+
+pub fn run_test(name: &str, test: &BlockTest) -> TestResult {
+    let mut result = TestResult {
+        name: name.to_owned(),
+        pass: false,
+        state_root: None,
+        fork: test.network.clone(),
+        error: String::new(),
+    };
+    match import(test) {
+        Ok(root) => {
+            result.pass = true;
+            result.state_root = Some(root);
+        }
+        Err((root, e)) => {
+            result.state_root = root;
+            result.error = e.to_string();
+        }
+    }
+    result
+}
+
+/// Imports the blocks of `test`, returning the final state root.
+fn import(test: &BlockTest) -> Result<B256, (Option<B256>, eyre::Report)> {
+    let mut journal = JournaledState::new(alloc::to_state(test.pre.clone()));
+    let genesis = Block::decode(&mut test.genesis_rlp.as_ref())
+        .map_err(|e| (None, anyhow!("invalid genesis block: {e}")))?;
+    let root = trie::state_root(&journal.state);
+    if genesis.header.state_root != root {
+        return Err((Some(root), anyhow!("genesis state root mismatch")));
+    }
+
+    let chain_id = test
+        .config
+        .as_ref()
+        .and_then(|config| config.chain_id)
+        .map_or(1, |id| id.saturating_to());
+    let mut head = genesis.header;
+    let mut block_hashes = BTreeMap::from([(head.number, head.hash())]);
+    for (i, test_block) in test.blocks.iter().enumerate() {
+        let imported = Block::decode(&mut test_block.rlp.as_ref())
+            .map_err(|e| anyhow!("invalid rlp: {e}"))
+            .and_then(|block| {
+                let spec = spec_at(&test.network, &block.header)?;
+                let cfg = CfgEnv {
+                    chain_id,
+                    spec,
+                    ..Default::default()
+                };
+                // Invalid blocks must leave the chain untouched.
+                let mut candidate = journal.clone();
+                block::validate_header(&block.header, &head, spec)?;
+                let result = block::execute_block(&mut candidate, cfg, &block, &block_hashes)?;
+                block::validate_result(&block, &result)?;
+                Ok((block, candidate))
+            });
+
+        match (imported, &test_block.expect_exception) {
+            (Ok((block, candidate)), None) => {
+                journal = candidate;
+                head = block.header;
+                block_hashes.insert(head.number, head.hash());
+                // `BLOCKHASH` only reaches 256 blocks back.
+                block_hashes.retain(|number, _| number + 256 >= head.number);
+            }
+            (Ok(_), Some(exception)) => {
+                let root = trie::state_root(&journal.state);
+                let e = anyhow!("block {i} should have been rejected: {exception}");
+                return Err((Some(root), e));
+            }
+            (Err(_), Some(_)) => {}
+            (Err(e), None) => {
+                let root = trie::state_root(&journal.state);
+                return Err((Some(root), anyhow!("block {i}: {e}")));
+            }
+        }
+    }
+
+    let root = trie::state_root(&journal.state);
+    let expected_root = match (&test.post_state, test.post_state_hash) {
+        (Some(post), _) => trie::state_root(&alloc::to_state(post.clone())),
+        (None, Some(hash)) => hash,
+        (None, None) => return Err((Some(root), anyhow!("missing post state"))),
+    };
+    if root != expected_root {
+        let e = anyhow!("post state root mismatch: got {root}, want {expected_root}");
+        return Err((Some(root), e));
+    }
+    let hash = head.hash();
+    if hash != test.last_block_hash {
+        let e = anyhow!(
+            "last block hash mismatch: got {hash}, want {}",
+            test.last_block_hash
+        );
+        return Err((Some(root), e));
+    }
+    Ok(root)
+}
+
+/// Resolves the spec of the block with `header` on `network`. Transitions
+/// are named `{from}To{to}At{activation}`, activating at a block number or,
+/// with a `Time` prefix, at a timestamp in thousands of seconds.
+fn spec_at(network: &str, header: &Header) -> eyre::Result<SpecId> {
+    let Some((from, rest)) = network.split_once("To") else {
+        return network.parse();
+    };
+    let (to, activation) = rest
+        .split_once("At")
+        .ok_or_else(|| anyhow!("unknown network: {network}"))?;
+    let active = match activation.strip_prefix("Time") {
+        Some(time) => {
+            let thousands: u64 = time
+                .trim_end_matches('k')
+                .parse()
+                .map_err(|_| anyhow!("unknown network: {network}"))?;
+            header.timestamp >= thousands * 1000
+        }
+        None => {
+            let number: u64 = activation
+                .parse()
+                .map_err(|_| anyhow!("unknown network: {network}"))?;
+            header.number >= number
+        }
+    };
+    if active {
+        to.parse()
+    } else {
+        from.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use alloy_primitives::{Address, Bytes, B256, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        alloc::GenesisAccount,
+        block::{Block, Header, Withdrawal, EMPTY_OMMERS_HASH},
+        spec::SpecId,
+        state::{Account, State},
+        trie::{self, EMPTY_ROOT},
+    };
+
+    use super::{run_test, spec_at, BlockTest, TestBlock};
+
+    const ALICE: Address = Address::with_last_byte(0xA1);
+
+    fn state_root(balance: u64) -> B256 {
+        let mut state = State::new();
+        state.insert(
+            ALICE,
+            Account {
+                balance: U256::from(balance),
+                ..Default::default()
+            },
+        );
+        trie::state_root(&state)
+    }
+
+    #[test]
+    fn imports_blocks() {
+        let genesis = Block {
+            header: Header {
+                ommers_hash: EMPTY_OMMERS_HASH,
+                gas_limit: 30_000_000,
+                base_fee_per_gas: Some(7),
+                state_root: state_root(1),
+                transactions_root: EMPTY_ROOT,
+                withdrawals_root: Some(EMPTY_ROOT),
+                ..Default::default()
+            },
+            withdrawals: Some(vec![]),
+            ..Default::default()
+        };
+        let withdrawals = vec![Withdrawal {
+            address: ALICE,
+            amount: 1,
+            ..Default::default()
+        }];
+        let block = Block {
+            header: Header {
+                parent_hash: genesis.header.hash(),
+                number: 1,
+                timestamp: 12,
+                ommers_hash: EMPTY_OMMERS_HASH,
+                gas_limit: 30_000_000,
+                base_fee_per_gas: Some(7),
+                state_root: state_root(1_000_000_001),
+                transactions_root: EMPTY_ROOT,
+                receipts_root: EMPTY_ROOT,
+                withdrawals_root: Some(trie::ordered_trie_root(
+                    withdrawals.iter().map(alloy_rlp::encode),
+                )),
+                ..Default::default()
+            },
+            withdrawals: Some(withdrawals),
+            ..Default::default()
+        };
+        // A child claiming the withdrawal didn't happen.
+        let invalid = Block {
+            header: Header {
+                parent_hash: block.header.hash(),
+                number: 2,
+                timestamp: 24,
+                ommers_hash: EMPTY_OMMERS_HASH,
+                gas_limit: 30_000_000,
+                base_fee_per_gas: Some(7),
+                state_root: state_root(1),
+                transactions_root: EMPTY_ROOT,
+                receipts_root: EMPTY_ROOT,
+                withdrawals_root: Some(EMPTY_ROOT),
+                ..Default::default()
+            },
+            withdrawals: Some(vec![]),
+            ..Default::default()
+        };
+
+        let mut test = BlockTest {
+            network: "Shanghai".to_owned(),
+            genesis_rlp: Bytes::from(alloy_rlp::encode(&genesis)),
+            pre: BTreeMap::from([(
+                ALICE,
+                GenesisAccount {
+                    balance: U256::from(1),
+                    ..Default::default()
+                },
+            )]),
+            post_state: None,
+            post_state_hash: Some(state_root(1_000_000_001)),
+            last_block_hash: block.header.hash(),
+            blocks: vec![
+                TestBlock {
+                    rlp: Bytes::from(alloy_rlp::encode(&block)),
+                    expect_exception: None,
+                },
+                TestBlock {
+                    rlp: Bytes::from(alloy_rlp::encode(&invalid)),
+                    expect_exception: Some("InvalidStateRoot".to_owned()),
+                },
+            ],
+            config: None,
+        };
+        let result = run_test("withdrawal", &test);
+        assert_eq!("", result.error);
+        assert_eq!(true, result.pass);
+
+        test.blocks[1].expect_exception = None;
+        let result = run_test("withdrawal", &test);
+        assert_eq!(false, result.pass);
+        assert_eq!(
+            format!(
+                "block 1: invalid state root: have {}, want {}",
+                state_root(1_000_000_001),
+                state_root(1)
+            ),
+            result.error
+        );
+    }
+
+    #[test]
+    fn resolves_transitions() {
+        let header = |number, timestamp| Header {
+            number,
+            timestamp,
+            ..Default::default()
+        };
+        assert_eq!(
+            SpecId::Cancun,
+            spec_at("Cancun", &header(1, 0)).expect("should resolve")
+        );
+
+        let network = "ShanghaiToCancunAtTime15k";
+        assert_eq!(
+            SpecId::Shanghai,
+            spec_at(network, &header(3, 14_999)).expect("should resolve")
+        );
+        assert_eq!(
+            SpecId::Cancun,
+            spec_at(network, &header(4, 15_000)).expect("should resolve")
+        );
+
+        let network = "BerlinToLondonAt5";
+        assert_eq!(
+            SpecId::Berlin,
+            spec_at(network, &header(4, 0)).expect("should resolve")
+        );
+        assert_eq!(
+            SpecId::London,
+            spec_at(network, &header(5, 0)).expect("should resolve")
+        );
+
+        let err = spec_at("FrontierToHomesteadAtNoon", &header(5, 0)).unwrap_err();
+        assert_eq!(
+            "unknown network: FrontierToHomesteadAtNoon",
+            err.to_string()
+        );
+    }
+}
@@ -1,14 +1,35 @@
-use alloy_primitives::Bytes;
+use alloy_primitives::{Address, Bytes};
 use bitvec::prelude::{bitvec, BitVec, Lsb0};
 
-use crate::instructions::opcode;
+use crate::{eof::Eof, instructions::opcode};
 
 type JumpSet = BitVec<usize, Lsb0>;
 
+/// The prefix of a delegation designator, the code an account gets when it
+/// delegates to another account's code (EIP-7702).
+pub const DELEGATION_PREFIX: [u8; 3] = [0xEF, 0x01, 0x00];
+
+/// Returns the address `code` delegates to, if it is a delegation designator.
+pub fn delegation(code: &[u8]) -> Option<Address> {
+    match code.strip_prefix(&DELEGATION_PREFIX) {
+        Some(address) if address.len() == Address::len_bytes() => {
+            Some(Address::from_slice(address))
+        }
+        _ => None,
+    }
+}
+
+/// The delegation designator of an account delegating to `address`.
+pub fn delegation_designator(address: Address) -> Bytes {
+    [&DELEGATION_PREFIX[..], address.as_slice()].concat().into()
+}
+
 #[derive(Debug)]
 pub struct Bytecode {
     pub bytes: Bytes,
     pub jumpset: JumpSet,
+    /// The parsed container, if the code is EOF rather than legacy code.
+    pub eof: Option<Eof>,
 }
 
 impl Default for Bytecode {
@@ -16,19 +37,45 @@ impl Default for Bytecode {
         // A single STOP opcode.
         let bytes = Bytes::from([0; 2]);
         let jumpset = create_jumpset(&bytes);
-        Self { bytes, jumpset }
+        Self {
+            bytes,
+            jumpset,
+            eof: None,
+        }
     }
 }
 
 impl Bytecode {
+    /// Code starting with the EOF magic is parsed as a container. Its code
+    /// is expected to have been validated when it was deployed. Anything
+    /// else, including malformed containers, is legacy code.
     pub fn new(bytes: Bytes) -> Self {
-        let jumpset = create_jumpset(&bytes);
-        Self { bytes, jumpset }
+        let eof = Eof::is_eof(&bytes)
+            .then(|| Eof::decode(bytes.clone()).ok())
+            .flatten();
+        let jumpset = match eof {
+            // EOF code has no dynamic jumps.
+            Some(_) => bitvec![usize, Lsb0; 0; bytes.len()],
+            None => create_jumpset(&bytes),
+        };
+        Self {
+            bytes,
+            jumpset,
+            eof,
+        }
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.eof.is_some()
     }
 
     pub fn len(&self) -> usize {
         self.bytes.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
 }
 
 fn create_jumpset(bytes: &Bytes) -> JumpSet {
@@ -56,7 +103,7 @@ fn create_jumpset(bytes: &Bytes) -> JumpSet {
 mod tests {
     use pretty_assertions::assert_eq;
 
-    use super::create_jumpset;
+    use super::{create_jumpset, Bytecode};
     use bitvec::prelude::{bits, Lsb0};
 
     #[test]
@@ -78,4 +125,19 @@ mod tests {
         let expected = bits![u8, Lsb0; 0,0,0,0,0,1,0,0,0,0,0,0,0,0,1,0];
         assert_eq!(expected, jumpset);
     }
+
+    #[test]
+    fn detects_eof() {
+        // A container whose only section is STOP.
+        let bytecode = Bytecode::new("0xef00010100040200010001ff00000000800000".parse().unwrap());
+        assert_eq!(false, bytecode.is_eof());
+        let bytecode = Bytecode::new(
+            "0xef00010100040200010001ff0000000080000000"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(true, bytecode.is_eof());
+        assert_eq!(1, bytecode.eof.unwrap().code_sections[0].len());
+        assert_eq!(false, Bytecode::new("0x600000".parse().unwrap()).is_eof());
+    }
 }
@@ -0,0 +1,315 @@
+//! Chains and the forks they went through. Forks up to the merge activate
+//! at a block number, later ones at a timestamp.
+
+use std::{fs, path::Path, str::FromStr};
+
+use alloy_primitives::{address, Address, U256};
+use eyre::anyhow;
+use serde::Deserialize;
+
+use crate::{
+    block::DEPOSIT_CONTRACT_ADDRESS,
+    env::{BlockEnv, CfgEnv},
+    spec::SpecId,
+};
+
+/// When a fork activates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForkCondition {
+    Block(u64),
+    Timestamp(u64),
+}
+
+impl ForkCondition {
+    pub fn is_active(self, number: u64, timestamp: u64) -> bool {
+        match self {
+            Self::Block(block) => number >= block,
+            Self::Timestamp(time) => timestamp >= time,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    /// The forks after Frontier, in the order they activate. Forks a chain
+    /// skipped or hasn't scheduled are left out.
+    pub forks: Vec<(SpecId, ForkCondition)>,
+    /// Where deposit requests come from (EIP-6110).
+    pub deposit_contract: Address,
+}
+
+impl ChainSpec {
+    pub fn mainnet() -> Self {
+        use ForkCondition::{Block, Timestamp};
+        Self {
+            chain_id: 1,
+            forks: vec![
+                (SpecId::Homestead, Block(1_150_000)),
+                (SpecId::Tangerine, Block(2_463_000)),
+                (SpecId::SpuriousDragon, Block(2_675_000)),
+                (SpecId::Byzantium, Block(4_370_000)),
+                (SpecId::Constantinople, Block(7_280_000)),
+                (SpecId::Petersburg, Block(7_280_000)),
+                (SpecId::Istanbul, Block(9_069_000)),
+                (SpecId::Berlin, Block(12_244_000)),
+                (SpecId::London, Block(12_965_000)),
+                (SpecId::Merge, Block(15_537_394)),
+                (SpecId::Shanghai, Timestamp(1_681_338_455)),
+                (SpecId::Cancun, Timestamp(1_710_338_135)),
+                (SpecId::Prague, Timestamp(1_746_612_311)),
+                (SpecId::Osaka, Timestamp(1_764_798_551)),
+            ],
+            deposit_contract: DEPOSIT_CONTRACT_ADDRESS,
+        }
+    }
+
+    pub fn sepolia() -> Self {
+        use ForkCondition::{Block, Timestamp};
+        Self {
+            chain_id: 11_155_111,
+            forks: [
+                SpecId::Homestead,
+                SpecId::Tangerine,
+                SpecId::SpuriousDragon,
+                SpecId::Byzantium,
+                SpecId::Constantinople,
+                SpecId::Petersburg,
+                SpecId::Istanbul,
+                SpecId::Berlin,
+                SpecId::London,
+            ]
+            .into_iter()
+            .map(|spec| (spec, Block(0)))
+            .chain([
+                (SpecId::Merge, Block(1_735_371)),
+                (SpecId::Shanghai, Timestamp(1_677_557_088)),
+                (SpecId::Cancun, Timestamp(1_706_655_072)),
+                (SpecId::Prague, Timestamp(1_741_159_776)),
+                (SpecId::Osaka, Timestamp(1_760_427_360)),
+            ])
+            .collect(),
+            deposit_contract: address!("7f02C3E3c98b133055B8B348B2Ac625669Ed295D"),
+        }
+    }
+
+    pub fn holesky() -> Self {
+        use ForkCondition::{Block, Timestamp};
+        Self {
+            chain_id: 17_000,
+            forks: [
+                SpecId::Homestead,
+                SpecId::Tangerine,
+                SpecId::SpuriousDragon,
+                SpecId::Byzantium,
+                SpecId::Constantinople,
+                SpecId::Petersburg,
+                SpecId::Istanbul,
+                SpecId::Berlin,
+                SpecId::London,
+                SpecId::Merge,
+            ]
+            .into_iter()
+            .map(|spec| (spec, Block(0)))
+            .chain([
+                (SpecId::Shanghai, Timestamp(1_696_000_704)),
+                (SpecId::Cancun, Timestamp(1_707_305_664)),
+                (SpecId::Prague, Timestamp(1_740_434_112)),
+                (SpecId::Osaka, Timestamp(1_759_308_480)),
+            ])
+            .collect(),
+            deposit_contract: address!("4242424242424242424242424242424242424242"),
+        }
+    }
+
+    /// Builds the chain described by the `config` of a geth genesis file.
+    pub fn from_config(config: &serde_json::Value) -> eyre::Result<Self> {
+        let config = ChainConfig::deserialize(config)?;
+        use ForkCondition::{Block, Timestamp};
+        // Devnets that start merged have a terminal total difficulty of zero
+        // instead of a merge block.
+        let merge_block = config.merge_netsplit_block.or_else(|| {
+            config
+                .terminal_total_difficulty
+                .is_some_and(|ttd| ttd.is_zero())
+                .then_some(0)
+        });
+        let forks = [
+            (SpecId::Homestead, config.homestead_block.map(Block)),
+            (SpecId::Tangerine, config.eip150_block.map(Block)),
+            (SpecId::SpuriousDragon, config.eip158_block.map(Block)),
+            (SpecId::Byzantium, config.byzantium_block.map(Block)),
+            (
+                SpecId::Constantinople,
+                config.constantinople_block.map(Block),
+            ),
+            (SpecId::Petersburg, config.petersburg_block.map(Block)),
+            (SpecId::Istanbul, config.istanbul_block.map(Block)),
+            (SpecId::Berlin, config.berlin_block.map(Block)),
+            (SpecId::London, config.london_block.map(Block)),
+            (SpecId::Merge, merge_block.map(Block)),
+            (SpecId::Shanghai, config.shanghai_time.map(Timestamp)),
+            (SpecId::Cancun, config.cancun_time.map(Timestamp)),
+            (SpecId::Prague, config.prague_time.map(Timestamp)),
+            (SpecId::Osaka, config.osaka_time.map(Timestamp)),
+        ]
+        .into_iter()
+        .filter_map(|(spec, condition)| Some((spec, condition?)))
+        .collect();
+
+        Ok(Self {
+            chain_id: config.chain_id,
+            forks,
+            deposit_contract: config
+                .deposit_contract_address
+                .unwrap_or(DEPOSIT_CONTRACT_ADDRESS),
+        })
+    }
+
+    /// Reads the chain of a genesis file, or of a bare chain config, at
+    /// `path`.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read {}: {e}", path.display()))?;
+        let value: serde_json::Value = serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("invalid chain config {}: {e}", path.display()))?;
+        let config = value.get("config").unwrap_or(&value);
+        Self::from_config(config)
+            .map_err(|e| anyhow!("invalid chain config {}: {e}", path.display()))
+    }
+
+    /// The latest fork active in the block with `number` and `timestamp`.
+    pub fn spec_at(&self, number: u64, timestamp: u64) -> SpecId {
+        self.forks
+            .iter()
+            .filter(|(_, condition)| condition.is_active(number, timestamp))
+            .map(|(spec, _)| *spec)
+            .max()
+            .unwrap_or(SpecId::Frontier)
+    }
+
+    /// The fork active in `block`.
+    pub fn spec(&self, block: &BlockEnv) -> SpecId {
+        self.spec_at(
+            block.number.saturating_to(),
+            block.timestamp.saturating_to(),
+        )
+    }
+
+    /// The configuration of the interpreter running in `block`.
+    pub fn cfg(&self, block: &BlockEnv) -> CfgEnv {
+        CfgEnv {
+            chain_id: self.chain_id,
+            spec: self.spec(block),
+            deposit_contract: self.deposit_contract,
+            ..Default::default()
+        }
+    }
+}
+
+impl FromStr for ChainSpec {
+    type Err = eyre::Report;
+
+    /// Parses the name of a built-in chain.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(Self::mainnet()),
+            "sepolia" => Ok(Self::sepolia()),
+            "holesky" => Ok(Self::holesky()),
+            _ => Err(anyhow!("unknown chain: {s}")),
+        }
+    }
+}
+
+/// The fork schedule of a geth chain config.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainConfig {
+    chain_id: u64,
+    homestead_block: Option<u64>,
+    eip150_block: Option<u64>,
+    eip158_block: Option<u64>,
+    byzantium_block: Option<u64>,
+    constantinople_block: Option<u64>,
+    petersburg_block: Option<u64>,
+    istanbul_block: Option<u64>,
+    berlin_block: Option<u64>,
+    london_block: Option<u64>,
+    merge_netsplit_block: Option<u64>,
+    terminal_total_difficulty: Option<U256>,
+    shanghai_time: Option<u64>,
+    cancun_time: Option<u64>,
+    prague_time: Option<u64>,
+    osaka_time: Option<u64>,
+    deposit_contract_address: Option<Address>,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::{env::BlockEnv, spec::SpecId};
+
+    use super::ChainSpec;
+
+    #[test]
+    fn selects_mainnet_forks() {
+        let mainnet = ChainSpec::mainnet();
+        assert_eq!(SpecId::Frontier, mainnet.spec_at(0, 0));
+        assert_eq!(SpecId::Homestead, mainnet.spec_at(1_150_000, 0));
+        assert_eq!(SpecId::Petersburg, mainnet.spec_at(7_280_000, 0));
+        assert_eq!(SpecId::Merge, mainnet.spec_at(15_537_394, 1_663_224_179));
+        assert_eq!(SpecId::Cancun, mainnet.spec_at(20_000_000, 1_717_281_407));
+
+        let block = BlockEnv {
+            number: U256::from(22_431_084),
+            timestamp: U256::from(1_746_612_311),
+            ..Default::default()
+        };
+        let cfg = mainnet.cfg(&block);
+        assert_eq!(SpecId::Prague, cfg.spec);
+        assert_eq!(1, cfg.chain_id);
+    }
+
+    #[test]
+    fn parses_presets() {
+        let sepolia: ChainSpec = "sepolia".parse().expect("should be a preset");
+        assert_eq!(11_155_111, sepolia.chain_id);
+        assert_eq!(SpecId::London, sepolia.spec_at(1, 0));
+        assert_eq!(
+            SpecId::Merge,
+            "holesky".parse::<ChainSpec>().unwrap().spec_at(0, 0)
+        );
+        assert_eq!(true, "ropsten".parse::<ChainSpec>().is_err());
+    }
+
+    #[test]
+    fn loads_devnet_configs() {
+        let config = serde_json::json!({
+            "chainId": 1337,
+            "homesteadBlock": 0,
+            "eip150Block": 0,
+            "eip155Block": 0,
+            "eip158Block": 0,
+            "byzantiumBlock": 0,
+            "constantinopleBlock": 0,
+            "petersburgBlock": 0,
+            "istanbulBlock": 0,
+            "berlinBlock": 0,
+            "londonBlock": 0,
+            "terminalTotalDifficulty": 0,
+            "shanghaiTime": 0,
+            "cancunTime": 100,
+            "depositContractAddress": "0x00000000000000000000000000000000000000aa"
+        });
+        let chain = ChainSpec::from_config(&config).expect("should load the config");
+        assert_eq!(1337, chain.chain_id);
+        assert_eq!(SpecId::Shanghai, chain.spec_at(0, 99));
+        assert_eq!(SpecId::Cancun, chain.spec_at(0, 100));
+        assert_eq!(Address::with_last_byte(0xAA), chain.deposit_contract);
+
+        let err = ChainSpec::from_config(&serde_json::json!({})).unwrap_err();
+        assert_eq!(true, err.to_string().contains("chainId"));
+    }
+}
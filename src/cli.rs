@@ -1,10 +1,27 @@
-use alloy_primitives::Bytes;
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+use alloy_primitives::{Bytes, U256};
 use clap::{Parser, Subcommand};
 use clap_stdin::FileOrStdin;
 
+use eyre::anyhow;
+
 use crate::{
-    instructions::opcode::{opcode, OPCODE_NAMES, PUSH0},
-    Contract, Interpreter,
+    alloc, blocktest,
+    env::{CfgEnv, Env},
+    envelope::SignedTransaction,
+    eof::{self, Eof},
+    frame::ExitStatus,
+    instructions::{opcode, table::InstructionTable},
+    receipt::{self, Receipt},
+    spec::SpecId,
+    statetest,
+    t8n::{self, T8nInput},
+    transaction, Contract, Interpreter, JournaledState,
 };
 
 /// This is the entry point to the executable.
@@ -23,6 +40,12 @@ enum Commands {
     Disassemble(Disassemble),
     #[command(name = "run")]
     Run(Run),
+    #[command(name = "statetest")]
+    StateTest(StateTest),
+    #[command(name = "blocktest")]
+    BlockTest(BlockTest),
+    #[command(name = "t8n")]
+    T8n(T8n),
 }
 
 /// Turn assembly code into bytecode.
@@ -36,17 +59,7 @@ impl Assemble {
     fn run(&self) -> eyre::Result<()> {
         // TODO: Avoid cloning.
         let contents = self.file.clone().contents()?;
-        let mut bytes = Vec::with_capacity(contents.lines().count());
-        for l in contents.lines() {
-            let instruction = l.split_whitespace().collect::<Vec<_>>();
-            bytes.push(opcode(instruction[0]));
-            if instruction.len() > 1 {
-                let data: Bytes = instruction[1].parse().expect("should be a hex number");
-                for byte in data {
-                    bytes.push(byte);
-                }
-            }
-        }
+        let bytes = assemble(&contents, &InstructionTable::standard())?;
 
         for byte in bytes {
             print!("{byte:0>2x}");
@@ -72,24 +85,7 @@ pub struct Disassemble {
 impl Disassemble {
     fn run(&self) {
         let bytecode: Bytes = self.code.parse().unwrap();
-        let mut i = 0;
-        let mut instructions = Vec::with_capacity(bytecode.len());
-        while i < bytecode.len() {
-            let opcode = bytecode[i];
-            if let Some(name) = OPCODE_NAMES[opcode as usize] {
-                if name.starts_with("PUSH") {
-                    let data_length = (opcode - PUSH0) as usize;
-                    let bytes = bytecode.slice(i + 1..i + 1 + data_length);
-                    instructions.push((i, format!("{name} {bytes}")));
-                    i += data_length;
-                } else {
-                    instructions.push((i, name.to_owned()));
-                }
-            } else {
-                instructions.push((i, "UNKNOWN".to_owned()));
-            };
-            i += 1;
-        }
+        let instructions = disassemble(&bytecode, &InstructionTable::standard());
 
         for (byte, instruction) in instructions {
             if self.verbose {
@@ -101,39 +97,397 @@ impl Disassemble {
     }
 }
 
-/// Execute bytecode.
+/// Turns assembly code, one instruction per line, into bytecode. Immediate
+/// data follows the instruction name as a hex number.
+pub fn assemble(source: &str, table: &InstructionTable) -> eyre::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(source.lines().count());
+    for l in source.lines() {
+        let instruction = l.split_whitespace().collect::<Vec<_>>();
+        let Some(name) = instruction.first() else {
+            continue;
+        };
+        let opcode = table
+            .opcode(name)
+            .ok_or_else(|| anyhow!("unknown instruction {name}"))?;
+        bytes.push(opcode);
+        if instruction.len() > 1 {
+            let data: Bytes = instruction[1]
+                .parse()
+                .map_err(|_| anyhow!("invalid immediate {}", instruction[1]))?;
+            bytes.extend_from_slice(&data);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Turns bytecode into assembly code, returning each instruction along with
+/// its byte offset. Only the code sections of EOF containers are
+/// disassembled, with offsets counted from the start of the container.
+pub fn disassemble(bytecode: &Bytes, table: &InstructionTable) -> Vec<(usize, String)> {
+    let Some(eof) = Eof::is_eof(bytecode)
+        .then(|| Eof::decode(bytecode.clone()).ok())
+        .flatten()
+    else {
+        return disassemble_code(bytecode, table, false);
+    };
+    eof.code_sections
+        .iter()
+        .zip(&eof.code_offsets)
+        .flat_map(|(code, offset)| {
+            disassemble_code(code, table, true)
+                .into_iter()
+                .map(move |(i, instruction)| (offset + i, instruction))
+        })
+        .collect()
+}
+
+fn disassemble_code(
+    bytecode: &Bytes,
+    table: &InstructionTable,
+    is_eof: bool,
+) -> Vec<(usize, String)> {
+    let mut i = 0;
+    let mut instructions = Vec::with_capacity(bytecode.len());
+    while i < bytecode.len() {
+        let opcode = bytecode[i];
+        match table.info(opcode) {
+            Some(info) if info.immediate_size > 0 => {
+                // The jump table of RJUMPV is sized by its first immediate.
+                let size = if is_eof && opcode == opcode::RJUMPV {
+                    eof::immediate_size(bytecode, i)
+                } else {
+                    info.immediate_size as usize
+                };
+                let end = bytecode.len().min(i + 1 + size);
+                let bytes = bytecode.slice(i + 1..end);
+                instructions.push((i, format!("{} {bytes}", info.name)));
+                i = end - 1;
+            }
+            Some(info) => instructions.push((i, info.name.to_owned())),
+            None => instructions.push((i, "UNKNOWN".to_owned())),
+        }
+        i += 1;
+    }
+    instructions
+}
+
+/// Execute bytecode, or a signed transaction.
 #[derive(Parser, Debug)]
 pub struct Run {
     /// The hex string representing the bytecode to run.
-    #[arg(long)]
-    code: String,
+    #[arg(long, required_unless_present = "raw_tx")]
+    code: Option<String>,
     /// The hex string representing the calldata passed as input.
+    #[arg(long, required_unless_present = "raw_tx")]
+    calldata: Option<String>,
+    /// The hex string of a signed transaction envelope to run instead of
+    /// `--code`, as sent to `eth_sendRawTransaction`.
+    #[arg(long, conflicts_with_all = ["code", "calldata"])]
+    raw_tx: Option<String>,
+    /// The fork whose rules apply to `--raw-tx`.
+    #[arg(long)]
+    fork: Option<SpecId>,
+    /// A genesis file or alloc JSON holding the initial world state.
     #[arg(long)]
-    calldata: String,
+    state: Option<PathBuf>,
+    /// Print the post-state as alloc JSON after running.
+    #[arg(long, default_value = "false")]
+    dump: bool,
+    /// Print the receipt of the run as JSON, as if it were alone in its
+    /// block. Bytecode gets a legacy receipt whose gas is that of the
+    /// execution alone, without intrinsic gas or refunds.
+    #[arg(long, default_value = "false")]
+    receipt: bool,
 }
 
 impl Run {
-    fn run(&self) {
-        let bytecode = self.code.parse().unwrap();
-        let input = self.calldata.parse().unwrap_or_default();
+    fn run(&self) -> eyre::Result<()> {
+        let mut journal = match &self.state {
+            Some(path) => JournaledState::new(alloc::to_state(alloc::load(path)?)),
+            None => JournaledState::default(),
+        };
+        let receipt = match &self.raw_tx {
+            Some(raw_tx) => self.run_raw_tx(raw_tx, &mut journal)?,
+            None => self.run_code(&mut journal),
+        };
+        if self.receipt {
+            println!("{}", serde_json::to_string_pretty(&receipt)?);
+        }
+        if self.dump {
+            let alloc = alloc::from_state(&journal.state);
+            println!("{}", serde_json::to_string_pretty(&alloc)?);
+        }
+        Ok(())
+    }
+
+    fn run_code(&self, journal: &mut JournaledState) -> Receipt {
+        let bytecode = self.code.as_deref().unwrap_or_default().parse().unwrap();
+        let input = self
+            .calldata
+            .as_deref()
+            .unwrap_or_default()
+            .parse()
+            .unwrap_or_default();
         let contract = Box::new(Contract::new(
             alloy_primitives::Address::ZERO,
             bytecode,
             input,
         ));
-        let mut interpreter = Interpreter::new(contract);
-        match interpreter.execute() {
-            Ok(bytes) => println!("{bytes}"),
-            Err(e) => eprintln!("{e}"),
+        let mut interpreter = Interpreter::with_journal(contract, std::mem::take(journal));
+        let status = match interpreter.execute() {
+            Ok(bytes) => {
+                println!("{bytes}");
+                !interpreter.reverted.get()
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                false
+            }
+        };
+        let logs = interpreter.journal.finish_transaction();
+        *journal = interpreter.journal;
+        Receipt::new(0, status, interpreter.gas.spent(), logs)
+    }
+
+    fn run_raw_tx(&self, raw_tx: &str, journal: &mut JournaledState) -> eyre::Result<Receipt> {
+        let bytes: Bytes = raw_tx
+            .parse()
+            .map_err(|_| anyhow!("invalid raw transaction"))?;
+        let tx = SignedTransaction::decode_envelope(&bytes)?;
+        let sender = tx.recover_sender()?;
+        let mut env = Env::default();
+        env.cfg.spec = self.fork.unwrap_or_default();
+        env.tx = tx.transaction.tx_env(sender);
+        if let Some(chain_id) = env.tx.chain_id {
+            env.cfg.chain_id = chain_id;
+        }
+
+        let result = transaction::transact(journal, env)?;
+        match result.status {
+            ExitStatus::Success => println!("{}", result.output),
+            status => eprintln!("{status:?}"),
+        }
+        receipt::from_results([(tx.transaction.tx_type(), &result)])
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("missing receipt"))
+    }
+}
+
+/// Run ethereum/tests state tests, printing the results as JSON.
+#[derive(Parser, Debug)]
+pub struct StateTest {
+    /// A test file, or a directory to search for test files.
+    path: PathBuf,
+}
+
+impl StateTest {
+    fn run(&self) -> eyre::Result<()> {
+        let results = statetest::run_path(&self.path)?;
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        Ok(())
+    }
+}
+
+/// Run ethereum/tests blockchain tests, printing the results as JSON.
+#[derive(Parser, Debug)]
+pub struct BlockTest {
+    /// A test file, or a directory to search for test files.
+    path: PathBuf,
+}
+
+impl BlockTest {
+    fn run(&self) -> eyre::Result<()> {
+        let results = blocktest::run_path(&self.path)?;
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        Ok(())
+    }
+}
+
+/// Apply transactions to a pre-state, like geth's transition tool.
+///
+/// Inputs and outputs are file names, or `stdin` and `stdout`/`stderr`.
+/// Inputs read from stdin come from a single JSON object with `alloc`,
+/// `env` and `txs` (or `txsRlp`) keys. Outputs to stdout or stderr are
+/// combined into a single JSON object with `result`, `alloc` and `body` keys.
+#[derive(Parser, Debug)]
+pub struct T8n {
+    #[arg(long = "input.alloc", default_value = "alloc.json")]
+    input_alloc: String,
+    #[arg(long = "input.env", default_value = "env.json")]
+    input_env: String,
+    /// A JSON list of transactions, or a `.rlp` file holding the RLP
+    /// encoded list as a JSON string.
+    #[arg(long = "input.txs", default_value = "txs.json")]
+    input_txs: String,
+    /// The directory output files are written to.
+    #[arg(long = "output.basedir")]
+    output_basedir: Option<PathBuf>,
+    #[arg(long = "output.result", default_value = "result.json")]
+    output_result: String,
+    #[arg(long = "output.alloc", default_value = "alloc.json")]
+    output_alloc: String,
+    /// The RLP encoded list of included transactions, not written unless set.
+    #[arg(long = "output.body")]
+    output_body: Option<String>,
+    #[arg(long = "state.fork", default_value = "GrayGlacier")]
+    state_fork: String,
+    #[arg(long = "state.chainid", default_value = "1")]
+    state_chainid: u64,
+    /// The block reward before the merge, or -1 to not reward miners.
+    #[arg(
+        long = "state.reward",
+        default_value = "0",
+        allow_negative_numbers = true
+    )]
+    state_reward: i128,
+}
+
+impl T8n {
+    fn run(&self) -> eyre::Result<()> {
+        let inputs = [&self.input_alloc, &self.input_env, &self.input_txs];
+        let mut stdin = T8nInput::default();
+        if inputs.iter().any(|input| *input == "stdin") {
+            let mut contents = String::new();
+            io::stdin().read_to_string(&mut contents)?;
+            stdin = serde_json::from_str(&contents).map_err(|e| anyhow!("invalid stdin: {e}"))?;
+        }
+
+        let alloc = match self.input_alloc.as_str() {
+            "stdin" => stdin
+                .alloc
+                .ok_or_else(|| anyhow!("missing alloc in stdin"))?,
+            path => read_json(path)?,
+        };
+        let env = match self.input_env.as_str() {
+            "stdin" => stdin.env.ok_or_else(|| anyhow!("missing env in stdin"))?,
+            path => read_json(path)?,
+        };
+        let chain_id = self.state_chainid;
+        let txs = match (self.input_txs.as_str(), stdin.txs, stdin.txs_rlp) {
+            ("stdin", Some(txs), _) => t8n::sign_txs(txs, chain_id),
+            ("stdin", None, Some(rlp)) => t8n::decode_txs(&rlp)?,
+            ("stdin", None, None) => Vec::new(),
+            (path, ..) => t8n::read_txs(path.as_ref(), chain_id)?,
+        };
+
+        let cfg = CfgEnv {
+            chain_id,
+            spec: self.state_fork.parse()?,
+            ..Default::default()
+        };
+        let reward = (self.state_reward >= 0).then(|| U256::from(self.state_reward));
+        let output = t8n::transition(alloc, &env, txs, cfg, reward)?;
+
+        let mut outputs = vec![
+            (&self.output_result, serde_json::to_value(&output.result)?),
+            (&self.output_alloc, serde_json::to_value(&output.alloc)?),
+        ];
+        if let Some(body) = &self.output_body {
+            outputs.push((body, serde_json::to_value(&output.body)?));
         }
+        let keys = ["result", "alloc", "body"];
+        let mut stdout = serde_json::Map::new();
+        let mut stderr = serde_json::Map::new();
+        for (key, (path, value)) in keys.into_iter().zip(outputs) {
+            match path.as_str() {
+                "stdout" => {
+                    stdout.insert(key.to_owned(), value);
+                }
+                "stderr" => {
+                    stderr.insert(key.to_owned(), value);
+                }
+                path => {
+                    let path = self.output_basedir.clone().unwrap_or_default().join(path);
+                    fs::write(&path, serde_json::to_string_pretty(&value)?)
+                        .map_err(|e| anyhow!("failed to write {}: {e}", path.display()))?;
+                }
+            }
+        }
+        if !stdout.is_empty() {
+            println!("{}", serde_json::to_string_pretty(&stdout)?);
+        }
+        if !stderr.is_empty() {
+            eprintln!("{}", serde_json::to_string_pretty(&stderr)?);
+        }
+        Ok(())
     }
 }
 
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> eyre::Result<T> {
+    let contents = fs::read_to_string(path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| anyhow!("invalid {path}: {e}"))
+}
+
 pub fn run() -> eyre::Result<()> {
     let config = Cli::parse();
     match config.command {
         Commands::Assemble(command) => command.run(),
-        Commands::Disassemble(command) => Ok(command.run()),
-        Commands::Run(command) => Ok(command.run()),
+        Commands::Disassemble(command) => {
+            command.run();
+            Ok(())
+        }
+        Commands::Run(command) => command.run(),
+        Commands::StateTest(command) => command.run(),
+        Commands::BlockTest(command) => command.run(),
+        Commands::T8n(command) => command.run(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use crate::instructions::{
+        control,
+        table::{InstructionTable, OpcodeInfo},
+    };
+
+    use super::{assemble, disassemble};
+
+    #[test]
+    fn assembles_custom_opcodes() {
+        let mut table = InstructionTable::standard();
+        let info = OpcodeInfo::new("TRACE", 0, 0, 1).with_immediate_size(1);
+        table.insert(0xB0, info, control::jumpdest);
+
+        let source = "TRACE 0x2a\nPUSH1 0x01\nSTOP";
+        let bytes = assemble(source, &table).expect("should assemble");
+        assert_eq!(vec![0xB0, 0x2A, 0x60, 0x01, 0x00], bytes);
+
+        let instructions = disassemble(&Bytes::from(bytes), &table);
+        assert_eq!(
+            vec![
+                (0, "TRACE 0x2a".to_owned()),
+                (2, "PUSH1 0x01".to_owned()),
+                (4, "STOP".to_owned()),
+            ],
+            instructions
+        );
+
+        let err = assemble(source, &InstructionTable::standard()).unwrap_err();
+        assert_eq!("unknown instruction TRACE", err.to_string());
+    }
+
+    #[test]
+    fn disassembles_eof_code_sections() {
+        // PUSH0
+        // RJUMPV 0x01 0x0000 0x0001
+        // STOP
+        // STOP
+        let bytes = "0xef00010100040200010009ff000000008000015fe201000000010000"
+            .parse()
+            .unwrap();
+        let instructions = disassemble(&bytes, &InstructionTable::standard());
+        assert_eq!(
+            vec![
+                (19, "PUSH0".to_owned()),
+                (20, "RJUMPV 0x0100000001".to_owned()),
+                (26, "STOP".to_owned()),
+                (27, "STOP".to_owned()),
+            ],
+            instructions
+        );
     }
 }
@@ -1,4 +1,4 @@
-use alloy_primitives::{Address, Bytes};
+use alloy_primitives::{Address, Bytes, U256};
 
 use crate::Bytecode;
 
@@ -7,6 +7,8 @@ pub struct Contract {
     pub bytecode: Bytecode,
     pub address: Address,
     pub input: Bytes,
+    pub caller: Address,
+    pub value: U256,
 }
 
 impl Contract {
@@ -16,6 +18,8 @@ impl Contract {
             address,
             bytecode,
             input,
+            caller: Address::ZERO,
+            value: U256::ZERO,
         }
     }
 
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_rlp::{Encodable, RlpDecodable, RlpEncodable};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    block::DEPOSIT_CONTRACT_ADDRESS,
+    envelope::SECP256K1N_HALF,
+    instructions::table::InstructionTable,
+    precompiles::{ecrecover, Precompiles},
+    spec::SpecId,
+};
+
+/// The byte prepended to authorizations before signing them (EIP-7702).
+const AUTHORIZATION_MAGIC: u8 = 0x05;
+
+/// Everything the interpreter needs to know about its surroundings.
+#[derive(Clone, Debug, Default)]
+pub struct Env {
+    pub cfg: CfgEnv,
+    pub block: BlockEnv,
+    pub tx: TxEnv,
+}
+
+#[derive(Clone, Debug)]
+pub struct CfgEnv {
+    pub chain_id: u64,
+    pub spec: SpecId,
+    pub precompiles: Precompiles,
+    pub instructions: InstructionTable,
+    /// Where deposit requests come from (EIP-6110).
+    pub deposit_contract: Address,
+}
+
+impl Default for CfgEnv {
+    fn default() -> Self {
+        Self {
+            chain_id: 1,
+            spec: SpecId::default(),
+            precompiles: Precompiles::standard(),
+            instructions: InstructionTable::standard(),
+            deposit_contract: DEPOSIT_CONTRACT_ADDRESS,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockEnv {
+    pub number: U256,
+    pub coinbase: Address,
+    pub timestamp: U256,
+    pub gas_limit: U256,
+    pub basefee: U256,
+    pub difficulty: U256,
+    /// Replaces `difficulty` after the merge (EIP-4399).
+    pub prevrandao: Option<B256>,
+    /// The blob base fee (EIP-4844), if blobs are enabled.
+    pub blob_basefee: Option<U256>,
+    /// The hashes of the blocks before this one by number, as returned by
+    /// `BLOCKHASH`. Only the last 256 are ever read.
+    pub block_hashes: BTreeMap<u64, B256>,
+}
+
+impl Default for BlockEnv {
+    fn default() -> Self {
+        Self {
+            number: U256::ZERO,
+            coinbase: Address::ZERO,
+            timestamp: U256::from(1),
+            gas_limit: U256::MAX,
+            basefee: U256::ZERO,
+            difficulty: U256::ZERO,
+            prevrandao: Some(B256::ZERO),
+            blob_basefee: Some(U256::from(1)),
+            block_hashes: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, RlpEncodable, RlpDecodable, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address: Address,
+    pub storage_keys: Vec<B256>,
+}
+
+/// An authorization of a set code transaction (EIP-7702), signed by the
+/// account delegating to the code at `address`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct SignedAuthorization {
+    /// Zero if the authorization is valid on any chain.
+    pub chain_id: U256,
+    pub address: Address,
+    pub nonce: u64,
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl SignedAuthorization {
+    /// The hash the authority signs: the magic byte followed by the RLP
+    /// list of the chain id, address and nonce.
+    pub fn signing_hash(&self) -> B256 {
+        let mut fields = Vec::new();
+        self.chain_id.encode(&mut fields);
+        self.address.encode(&mut fields);
+        self.nonce.encode(&mut fields);
+        let mut out = vec![AUTHORIZATION_MAGIC];
+        alloy_rlp::Header {
+            list: true,
+            payload_length: fields.len(),
+        }
+        .encode(&mut out);
+        out.extend_from_slice(&fields);
+        keccak256(out)
+    }
+
+    /// Recovers the account that signed the authorization, or `None` if the
+    /// signature is invalid.
+    pub fn authority(&self) -> Option<Address> {
+        if self.y_parity > 1 || self.r.is_zero() || self.s.is_zero() || self.s > SECP256K1N_HALF {
+            return None;
+        }
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&self.r.to_be_bytes::<32>());
+        signature[32..].copy_from_slice(&self.s.to_be_bytes::<32>());
+        ecrecover::recover_signer(&self.signing_hash(), &signature, self.y_parity)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TxEnv {
+    pub caller: Address,
+    pub gas_limit: u64,
+    /// The gas price for legacy transactions, or the max fee per gas for
+    /// EIP-1559 transactions.
+    pub gas_price: U256,
+    /// The max priority fee per gas, only set for EIP-1559 transactions.
+    pub gas_priority_fee: Option<U256>,
+    /// The callee, or `None` for contract creation.
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Bytes,
+    /// The sender nonce. Not checked when `None`.
+    pub nonce: Option<u64>,
+    /// Not checked when `None`.
+    pub chain_id: Option<u64>,
+    /// Only set for typed transactions (EIP-2930).
+    pub access_list: Option<Vec<AccessListItem>>,
+    pub blob_hashes: Vec<B256>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    /// Only set for set code transactions (EIP-7702).
+    pub authorization_list: Option<Vec<SignedAuthorization>>,
+}
+
+impl Default for TxEnv {
+    fn default() -> Self {
+        Self {
+            caller: Address::ZERO,
+            gas_limit: u64::MAX,
+            gas_price: U256::ZERO,
+            gas_priority_fee: None,
+            to: Some(Address::ZERO),
+            value: U256::ZERO,
+            data: Bytes::new(),
+            nonce: None,
+            chain_id: None,
+            access_list: None,
+            blob_hashes: Vec::new(),
+            max_fee_per_blob_gas: None,
+            authorization_list: None,
+        }
+    }
+}
+
+impl Env {
+    /// The gas price actually paid per unit of gas. After London, this is the
+    /// base fee plus the priority fee, capped by the max fee (EIP-1559).
+    pub fn effective_gas_price(&self) -> U256 {
+        match self.tx.gas_priority_fee {
+            Some(priority_fee) if self.cfg.spec.is_enabled_in(SpecId::London) => self
+                .tx
+                .gas_price
+                .min(self.block.basefee.saturating_add(priority_fee)),
+            _ => self.tx.gas_price,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use pretty_assertions::assert_eq;
+
+    use super::Env;
+
+    #[test]
+    fn computes_effective_gas_price() {
+        let mut env = Env::default();
+        env.block.basefee = U256::from(10);
+        env.tx.gas_price = U256::from(15);
+        assert_eq!(U256::from(15), env.effective_gas_price());
+
+        env.tx.gas_priority_fee = Some(U256::from(2));
+        assert_eq!(U256::from(12), env.effective_gas_price());
+
+        env.tx.gas_priority_fee = Some(U256::from(10));
+        assert_eq!(U256::from(15), env.effective_gas_price());
+    }
+}
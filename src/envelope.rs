@@ -0,0 +1,678 @@
+//! Signed transactions as they appear in blocks: legacy transactions and
+//! the typed envelopes of EIP-2718.
+
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_rlp::{BufMut, Decodable, Encodable, Header, EMPTY_STRING_CODE};
+use eyre::anyhow;
+use k256::ecdsa::SigningKey;
+
+use crate::{
+    env::{AccessListItem, SignedAuthorization, TxEnv},
+    precompiles::ecrecover,
+};
+
+/// Half the order of secp256k1. Signatures with a larger `s` are malleable
+/// and rejected (EIP-2).
+pub(crate) const SECP256K1N_HALF: U256 = U256::from_limbs([
+    0xDFE92F46681B20A0,
+    0x5D576E7357A4501D,
+    0xFFFFFFFFFFFFFFFF,
+    0x7FFFFFFFFFFFFFFF,
+]);
+
+/// The destination of a transaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TxKind {
+    #[default]
+    Create,
+    Call(Address),
+}
+
+impl TxKind {
+    pub fn to(self) -> Option<Address> {
+        match self {
+            Self::Create => None,
+            Self::Call(address) => Some(address),
+        }
+    }
+}
+
+impl Encodable for TxKind {
+    fn encode(&self, out: &mut dyn BufMut) {
+        match self {
+            Self::Create => out.put_u8(EMPTY_STRING_CODE),
+            Self::Call(address) => address.encode(out),
+        }
+    }
+
+    fn length(&self) -> usize {
+        match self {
+            Self::Create => 1,
+            Self::Call(address) => address.length(),
+        }
+    }
+}
+
+impl Decodable for TxKind {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        if buf.first() == Some(&EMPTY_STRING_CODE) {
+            *buf = &buf[1..];
+            return Ok(Self::Create);
+        }
+        Address::decode(buf).map(Self::Call)
+    }
+}
+
+/// Implements the RLP encoding of a transaction's fields, in order, without
+/// the enclosing list header.
+macro_rules! fields {
+    ($tx:ident { $($field:ident),* $(,)? }) => {
+        impl $tx {
+            fn fields_length(&self) -> usize {
+                0 $(+ self.$field.length())*
+            }
+
+            fn encode_fields(&self, out: &mut dyn BufMut) {
+                $(self.$field.encode(out);)*
+            }
+
+            fn decode_fields(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+                Ok(Self {
+                    $($field: Decodable::decode(buf)?,)*
+                })
+            }
+        }
+    };
+}
+
+/// A transaction from before EIP-2718. Replay protection (EIP-155) encodes
+/// the chain id in the signature's `v`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxLegacy {
+    pub nonce: u64,
+    pub gas_price: U256,
+    pub gas_limit: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub input: Bytes,
+    pub chain_id: Option<u64>,
+}
+
+/// An access list transaction (EIP-2930).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxEip2930 {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: U256,
+    pub gas_limit: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub input: Bytes,
+    pub access_list: Vec<AccessListItem>,
+}
+
+fields!(TxEip2930 {
+    chain_id,
+    nonce,
+    gas_price,
+    gas_limit,
+    to,
+    value,
+    input,
+    access_list,
+});
+
+/// A dynamic fee transaction (EIP-1559).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxEip1559 {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub input: Bytes,
+    pub access_list: Vec<AccessListItem>,
+}
+
+fields!(TxEip1559 {
+    chain_id,
+    nonce,
+    max_priority_fee_per_gas,
+    max_fee_per_gas,
+    gas_limit,
+    to,
+    value,
+    input,
+    access_list,
+});
+
+/// A blob transaction (EIP-4844). Blobs can't create contracts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxEip4844 {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: Address,
+    pub value: U256,
+    pub input: Bytes,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: U256,
+    pub blob_versioned_hashes: Vec<B256>,
+}
+
+fields!(TxEip4844 {
+    chain_id,
+    nonce,
+    max_priority_fee_per_gas,
+    max_fee_per_gas,
+    gas_limit,
+    to,
+    value,
+    input,
+    access_list,
+    max_fee_per_blob_gas,
+    blob_versioned_hashes,
+});
+
+/// A set code transaction (EIP-7702), through which accounts delegate to
+/// code. Like blobs, it can't create contracts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxEip7702 {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: u64,
+    pub to: Address,
+    pub value: U256,
+    pub input: Bytes,
+    pub access_list: Vec<AccessListItem>,
+    pub authorization_list: Vec<SignedAuthorization>,
+}
+
+fields!(TxEip7702 {
+    chain_id,
+    nonce,
+    max_priority_fee_per_gas,
+    max_fee_per_gas,
+    gas_limit,
+    to,
+    value,
+    input,
+    access_list,
+    authorization_list,
+});
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transaction {
+    Legacy(TxLegacy),
+    Eip2930(TxEip2930),
+    Eip1559(TxEip1559),
+    Eip4844(TxEip4844),
+    Eip7702(TxEip7702),
+}
+
+impl Transaction {
+    /// The EIP-2718 type byte.
+    pub fn tx_type(&self) -> u8 {
+        match self {
+            Self::Legacy(_) => 0,
+            Self::Eip2930(_) => 1,
+            Self::Eip1559(_) => 2,
+            Self::Eip4844(_) => 3,
+            Self::Eip7702(_) => 4,
+        }
+    }
+
+    /// The hash the sender signs.
+    pub fn signing_hash(&self) -> B256 {
+        let mut out = Vec::new();
+        match self {
+            Self::Legacy(tx) => {
+                let mut fields = Vec::new();
+                tx.nonce.encode(&mut fields);
+                tx.gas_price.encode(&mut fields);
+                tx.gas_limit.encode(&mut fields);
+                tx.to.encode(&mut fields);
+                tx.value.encode(&mut fields);
+                tx.input.encode(&mut fields);
+                if let Some(chain_id) = tx.chain_id {
+                    chain_id.encode(&mut fields);
+                    0u8.encode(&mut fields);
+                    0u8.encode(&mut fields);
+                }
+                list_header(fields.len()).encode(&mut out);
+                out.extend_from_slice(&fields);
+            }
+            Self::Eip2930(tx) => {
+                out.put_u8(self.tx_type());
+                list_header(tx.fields_length()).encode(&mut out);
+                tx.encode_fields(&mut out);
+            }
+            Self::Eip1559(tx) => {
+                out.put_u8(self.tx_type());
+                list_header(tx.fields_length()).encode(&mut out);
+                tx.encode_fields(&mut out);
+            }
+            Self::Eip4844(tx) => {
+                out.put_u8(self.tx_type());
+                list_header(tx.fields_length()).encode(&mut out);
+                tx.encode_fields(&mut out);
+            }
+            Self::Eip7702(tx) => {
+                out.put_u8(self.tx_type());
+                list_header(tx.fields_length()).encode(&mut out);
+                tx.encode_fields(&mut out);
+            }
+        }
+        keccak256(out)
+    }
+
+    /// The execution environment of the transaction, sent by `caller`.
+    pub fn tx_env(&self, caller: Address) -> TxEnv {
+        let mut env = TxEnv {
+            caller,
+            ..Default::default()
+        };
+        match self {
+            Self::Legacy(tx) => {
+                env.chain_id = tx.chain_id;
+                env.nonce = Some(tx.nonce);
+                env.gas_limit = tx.gas_limit;
+                env.gas_price = tx.gas_price;
+                env.to = tx.to.to();
+                env.value = tx.value;
+                env.data = tx.input.clone();
+            }
+            Self::Eip2930(tx) => {
+                env.chain_id = Some(tx.chain_id);
+                env.nonce = Some(tx.nonce);
+                env.gas_limit = tx.gas_limit;
+                env.gas_price = tx.gas_price;
+                env.to = tx.to.to();
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.access_list = Some(tx.access_list.clone());
+            }
+            Self::Eip1559(tx) => {
+                env.chain_id = Some(tx.chain_id);
+                env.nonce = Some(tx.nonce);
+                env.gas_limit = tx.gas_limit;
+                env.gas_price = tx.max_fee_per_gas;
+                env.gas_priority_fee = Some(tx.max_priority_fee_per_gas);
+                env.to = tx.to.to();
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.access_list = Some(tx.access_list.clone());
+            }
+            Self::Eip4844(tx) => {
+                env.chain_id = Some(tx.chain_id);
+                env.nonce = Some(tx.nonce);
+                env.gas_limit = tx.gas_limit;
+                env.gas_price = tx.max_fee_per_gas;
+                env.gas_priority_fee = Some(tx.max_priority_fee_per_gas);
+                env.to = Some(tx.to);
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.access_list = Some(tx.access_list.clone());
+                env.blob_hashes = tx.blob_versioned_hashes.clone();
+                env.max_fee_per_blob_gas = Some(tx.max_fee_per_blob_gas);
+            }
+            Self::Eip7702(tx) => {
+                env.chain_id = Some(tx.chain_id);
+                env.nonce = Some(tx.nonce);
+                env.gas_limit = tx.gas_limit;
+                env.gas_price = tx.max_fee_per_gas;
+                env.gas_priority_fee = Some(tx.max_priority_fee_per_gas);
+                env.to = Some(tx.to);
+                env.value = tx.value;
+                env.data = tx.input.clone();
+                env.access_list = Some(tx.access_list.clone());
+                env.authorization_list = Some(tx.authorization_list.clone());
+            }
+        }
+        env
+    }
+
+    /// Signs the transaction with `secret_key`.
+    pub fn sign(self, secret_key: &B256) -> eyre::Result<SignedTransaction> {
+        let key = SigningKey::from_slice(secret_key.as_slice())
+            .map_err(|_| anyhow!("invalid secret key"))?;
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(self.signing_hash().as_slice())
+            .map_err(|_| anyhow!("failed to sign transaction"))?;
+        let signature = Signature {
+            y_parity: recovery_id.is_y_odd(),
+            r: U256::from_be_slice(&signature.r().to_bytes()),
+            s: U256::from_be_slice(&signature.s().to_bytes()),
+        };
+        Ok(SignedTransaction::new(self, signature))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Signature {
+    /// The parity of the `y` coordinate of the signature's curve point.
+    pub y_parity: bool,
+    pub r: U256,
+    pub s: U256,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedTransaction {
+    pub transaction: Transaction,
+    pub signature: Signature,
+    /// The hash of the encoded envelope, which identifies the transaction.
+    pub hash: B256,
+    /// The encoded envelope: the RLP list of a legacy transaction, or the
+    /// type byte followed by the RLP list of a typed one.
+    pub encoded: Bytes,
+}
+
+impl SignedTransaction {
+    pub fn new(transaction: Transaction, signature: Signature) -> Self {
+        let mut fields = Vec::new();
+        match &transaction {
+            Transaction::Legacy(tx) => {
+                tx.nonce.encode(&mut fields);
+                tx.gas_price.encode(&mut fields);
+                tx.gas_limit.encode(&mut fields);
+                tx.to.encode(&mut fields);
+                tx.value.encode(&mut fields);
+                tx.input.encode(&mut fields);
+                let parity = signature.y_parity as u64;
+                let v = match tx.chain_id {
+                    Some(chain_id) => chain_id * 2 + 35 + parity,
+                    None => 27 + parity,
+                };
+                v.encode(&mut fields);
+            }
+            Transaction::Eip2930(tx) => tx.encode_fields(&mut fields),
+            Transaction::Eip1559(tx) => tx.encode_fields(&mut fields),
+            Transaction::Eip4844(tx) => tx.encode_fields(&mut fields),
+            Transaction::Eip7702(tx) => tx.encode_fields(&mut fields),
+        }
+        if !matches!(transaction, Transaction::Legacy(_)) {
+            signature.y_parity.encode(&mut fields);
+        }
+        signature.r.encode(&mut fields);
+        signature.s.encode(&mut fields);
+
+        let mut encoded = Vec::with_capacity(fields.len() + 4);
+        if !matches!(transaction, Transaction::Legacy(_)) {
+            encoded.push(transaction.tx_type());
+        }
+        list_header(fields.len()).encode(&mut encoded);
+        encoded.extend_from_slice(&fields);
+        Self {
+            transaction,
+            signature,
+            hash: keccak256(&encoded),
+            encoded: encoded.into(),
+        }
+    }
+
+    /// Decodes an encoded envelope, as sent to `eth_sendRawTransaction`.
+    pub fn decode_envelope(bytes: &[u8]) -> eyre::Result<Self> {
+        let buf = &mut &bytes[..];
+        let (transaction, signature) = match bytes.first() {
+            None => return Err(anyhow!("empty transaction")),
+            Some(0xC0..) => decode_legacy(buf)?,
+            Some(&tx_type) => {
+                *buf = &buf[1..];
+                let header = Header::decode(buf)?;
+                if !header.list {
+                    return Err(anyhow!("transaction is not a list"));
+                }
+                let remaining = buf.len();
+                let transaction = match tx_type {
+                    1 => Transaction::Eip2930(TxEip2930::decode_fields(buf)?),
+                    2 => Transaction::Eip1559(TxEip1559::decode_fields(buf)?),
+                    3 => Transaction::Eip4844(TxEip4844::decode_fields(buf)?),
+                    4 => Transaction::Eip7702(TxEip7702::decode_fields(buf)?),
+                    _ => return Err(anyhow!("unsupported transaction type {tx_type}")),
+                };
+                let signature = Signature {
+                    y_parity: bool::decode(buf)?,
+                    r: U256::decode(buf)?,
+                    s: U256::decode(buf)?,
+                };
+                if remaining - buf.len() != header.payload_length {
+                    return Err(anyhow!("transaction length mismatch"));
+                }
+                (transaction, signature)
+            }
+        };
+        if !buf.is_empty() {
+            return Err(anyhow!("trailing bytes after transaction"));
+        }
+
+        Ok(Self {
+            transaction,
+            signature,
+            hash: keccak256(bytes),
+            encoded: Bytes::copy_from_slice(bytes),
+        })
+    }
+
+    /// Recovers the address that signed the transaction.
+    pub fn recover_sender(&self) -> eyre::Result<Address> {
+        let Signature { y_parity, r, s } = self.signature;
+        if r.is_zero() || s.is_zero() || s > SECP256K1N_HALF {
+            return Err(anyhow!("invalid signature"));
+        }
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&r.to_be_bytes::<32>());
+        signature[32..].copy_from_slice(&s.to_be_bytes::<32>());
+        let hash = self.transaction.signing_hash();
+        ecrecover::recover_signer(&hash, &signature, y_parity as u8)
+            .ok_or_else(|| anyhow!("invalid signature"))
+    }
+}
+
+impl Encodable for SignedTransaction {
+    /// Within a block, typed envelopes are wrapped in an RLP string.
+    fn encode(&self, out: &mut dyn BufMut) {
+        match self.transaction {
+            Transaction::Legacy(_) => out.put_slice(&self.encoded),
+            _ => self.encoded.encode(out),
+        }
+    }
+
+    fn length(&self) -> usize {
+        match self.transaction {
+            Transaction::Legacy(_) => self.encoded.len(),
+            _ => self.encoded.length(),
+        }
+    }
+}
+
+impl Decodable for SignedTransaction {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut peek = &buf[..];
+        let header = Header::decode(&mut peek)?;
+        let envelope = if header.list {
+            let len = buf.len() - peek.len() + header.payload_length;
+            let envelope = &buf[..len];
+            *buf = &buf[len..];
+            envelope
+        } else {
+            *buf = &peek[header.payload_length..];
+            &peek[..header.payload_length]
+        };
+        Self::decode_envelope(envelope).map_err(|_| alloy_rlp::Error::Custom("invalid transaction"))
+    }
+}
+
+fn decode_legacy(buf: &mut &[u8]) -> eyre::Result<(Transaction, Signature)> {
+    let header = Header::decode(buf)?;
+    if !header.list {
+        return Err(anyhow!("transaction is not a list"));
+    }
+    let mut tx = TxLegacy {
+        nonce: u64::decode(buf)?,
+        gas_price: U256::decode(buf)?,
+        gas_limit: u64::decode(buf)?,
+        to: TxKind::decode(buf)?,
+        value: U256::decode(buf)?,
+        input: Bytes::decode(buf)?,
+        chain_id: None,
+    };
+    let v = u64::decode(buf)?;
+    let y_parity = match v {
+        27 | 28 => v == 28,
+        // EIP-155: v = chain_id * 2 + 35 + y_parity.
+        35.. => {
+            tx.chain_id = Some((v - 35) / 2);
+            (v - 35) % 2 == 1
+        }
+        _ => return Err(anyhow!("invalid signature v {v}")),
+    };
+    let signature = Signature {
+        y_parity,
+        r: U256::decode(buf)?,
+        s: U256::decode(buf)?,
+    };
+    Ok((Transaction::Legacy(tx), signature))
+}
+
+fn list_header(payload_length: usize) -> Header {
+    Header {
+        list: true,
+        payload_length,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, Bytes, B256, U256};
+    use alloy_rlp::Decodable;
+    use pretty_assertions::assert_eq;
+
+    use crate::env::SignedAuthorization;
+
+    use super::{SignedTransaction, Transaction, TxEip1559, TxEip7702, TxKind, SECP256K1N_HALF};
+
+    // The example transaction of EIP-155.
+    const EIP155: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    #[test]
+    fn decodes_legacy_transactions() {
+        let bytes: Bytes = EIP155.parse().unwrap();
+        let tx = SignedTransaction::decode_envelope(&bytes).expect("should decode");
+        let Transaction::Legacy(legacy) = &tx.transaction else {
+            panic!("should be a legacy transaction");
+        };
+        assert_eq!(Some(1), legacy.chain_id);
+        assert_eq!(9, legacy.nonce);
+        assert_eq!(TxKind::Call(Address::repeat_byte(0x35)), legacy.to);
+        assert_eq!(U256::from(10).pow(U256::from(18)), legacy.value);
+        assert_eq!(
+            "0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+                .parse::<alloy_primitives::B256>()
+                .unwrap(),
+            tx.transaction.signing_hash()
+        );
+        assert_eq!(
+            "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+                .parse::<Address>()
+                .unwrap(),
+            tx.recover_sender().expect("should recover the sender")
+        );
+
+        // The other signature of the same sender, with `s` above half the
+        // curve order, is malleable (EIP-2).
+        let mut malleable = tx.clone();
+        let n = SECP256K1N_HALF * U256::from(2) + U256::from(1);
+        malleable.signature.s = n - tx.signature.s;
+        malleable.signature.y_parity = !tx.signature.y_parity;
+        let err = malleable.recover_sender().unwrap_err();
+        assert_eq!("invalid signature", err.to_string());
+    }
+
+    #[test]
+    fn round_trips_block_encoding() {
+        let bytes: Bytes = EIP155.parse().unwrap();
+        let tx = SignedTransaction::decode_envelope(&bytes).expect("should decode");
+        let encoded = alloy_rlp::encode(&tx);
+        let decoded =
+            SignedTransaction::decode(&mut encoded.as_slice()).expect("should decode from a block");
+        assert_eq!(tx, decoded);
+    }
+
+    #[test]
+    fn signs_transactions() {
+        let key: B256 = "0x4646464646464646464646464646464646464646464646464646464646464646"
+            .parse()
+            .unwrap();
+        let bytes: Bytes = EIP155.parse().unwrap();
+        let tx = SignedTransaction::decode_envelope(&bytes).expect("should decode");
+        let signed = tx.transaction.clone().sign(&key).expect("should sign");
+        assert_eq!(tx, signed);
+
+        let typed = Transaction::Eip1559(TxEip1559 {
+            chain_id: 1,
+            to: TxKind::Create,
+            ..Default::default()
+        });
+        let signed = typed.sign(&key).expect("should sign");
+        let decoded = SignedTransaction::decode_envelope(&signed.encoded).expect("should decode");
+        assert_eq!(signed, decoded);
+        assert_eq!(
+            tx.recover_sender().expect("should recover the sender"),
+            decoded.recover_sender().expect("should recover the sender")
+        );
+    }
+
+    #[test]
+    fn decodes_set_code_transactions() {
+        let key = B256::repeat_byte(0x46);
+        let tx = Transaction::Eip7702(TxEip7702 {
+            chain_id: 1,
+            to: Address::with_last_byte(1),
+            authorization_list: vec![SignedAuthorization {
+                address: Address::with_last_byte(2),
+                nonce: 3,
+                y_parity: 1,
+                r: U256::from(4),
+                s: U256::from(5),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let signed = tx.sign(&key).expect("should sign");
+        assert_eq!(4, signed.encoded[0]);
+        let decoded = SignedTransaction::decode_envelope(&signed.encoded).expect("should decode");
+        assert_eq!(signed, decoded);
+        assert_eq!(
+            Some(Address::with_last_byte(1)),
+            decoded.transaction.tx_env(Address::ZERO).to
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_transactions() {
+        let err = SignedTransaction::decode_envelope(&[0x05, 0xC0]).unwrap_err();
+        assert_eq!("unsupported transaction type 5", err.to_string());
+        assert_eq!(true, SignedTransaction::decode_envelope(&[]).is_err());
+
+        // The fields must fill the list exactly, even when nothing follows.
+        let typed = Transaction::Eip1559(TxEip1559 {
+            chain_id: 1,
+            ..Default::default()
+        });
+        let mut bytes = typed
+            .sign(&B256::repeat_byte(0x46))
+            .expect("should sign")
+            .encoded
+            .to_vec();
+        assert_eq!([0x02, 0xF8], bytes[..2]);
+        bytes[2] -= 1;
+        let err = SignedTransaction::decode_envelope(&bytes).unwrap_err();
+        assert_eq!("transaction length mismatch", err.to_string());
+    }
+}
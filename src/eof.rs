@@ -0,0 +1,846 @@
+//! The EVM Object Format (EOF) v1 container (EIP-3540).
+//!
+//! A container splits code into functions (EIP-4750), each with its own
+//! type, and keeps data and subcontainers (EIP-7620) apart from code. Code is
+//! validated once when it is created (EIP-3670, EIP-4200, EIP-5450), so the
+//! interpreter doesn't have to check jumps or the stack at runtime.
+
+use alloy_primitives::Bytes;
+use bitvec::prelude::{bitvec, BitVec, Lsb0};
+use eyre::anyhow;
+
+use crate::{
+    constants::STACK_SIZE,
+    frame::MAX_INITCODE_SIZE,
+    instructions::opcode::{
+        self, CALLF, DATACOPY, DATALOAD, DATALOADN, DATASIZE, DUPN, EXCHANGE, JUMPF, PUSH1, PUSH32,
+        RETF, RJUMP, RJUMPI, RJUMPV, SWAPN,
+    },
+};
+
+/// The first bytes of every container. 0xEF can't start legacy code since
+/// London (EIP-3541).
+pub const MAGIC: [u8; 2] = [0xEF, 0x00];
+pub const VERSION: u8 = 1;
+/// The outputs of a code section that never returns to its caller.
+pub const NON_RETURNING: u8 = 0x80;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0xFF;
+const TERMINATOR: u8 = 0x00;
+
+const MAX_CODE_SECTIONS: usize = 1024;
+const MAX_CONTAINER_SECTIONS: usize = 256;
+const MAX_INPUTS_OUTPUTS: u8 = 127;
+const MAX_STACK_HEIGHT: u16 = 1023;
+
+// The EOF opcodes the interpreter doesn't run yet.
+const EOFCREATE: u8 = 0xEC;
+const RETURNCONTRACT: u8 = 0xEE;
+const RETURNDATALOAD: u8 = 0xF7;
+const EXTCALL: u8 = 0xF8;
+const EXTDELEGATECALL: u8 = 0xF9;
+const EXTSTATICCALL: u8 = 0xFB;
+
+/// The signature of a code section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TypeSection {
+    pub inputs: u8,
+    /// The number of outputs, or `NON_RETURNING`.
+    pub outputs: u8,
+    /// The most stack items the section uses, including its inputs.
+    pub max_stack_height: u16,
+}
+
+impl TypeSection {
+    pub fn is_returning(&self) -> bool {
+        self.outputs != NON_RETURNING
+    }
+}
+
+/// What a container is validated as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// Deployed code, which can't use `RETURNCONTRACT`.
+    Runtime,
+    /// Code run by `EOFCREATE` or a creation transaction, which deploys a
+    /// subcontainer with `RETURNCONTRACT` and can't use `RETURN` or `STOP`.
+    Initcode,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Eof {
+    pub types: Vec<TypeSection>,
+    pub code_sections: Vec<Bytes>,
+    /// Where each code section starts within `raw`.
+    pub code_offsets: Vec<usize>,
+    pub container_sections: Vec<Bytes>,
+    pub data: Bytes,
+    /// The data size declared by the header. It is larger than `data` for
+    /// containers waiting for `RETURNCONTRACT` to append auxiliary data.
+    pub data_size: u16,
+    /// The whole encoded container.
+    pub raw: Bytes,
+}
+
+impl Eof {
+    /// Whether `bytes` claim to be a container, as opposed to legacy code.
+    pub fn is_eof(bytes: &[u8]) -> bool {
+        bytes.starts_with(&MAGIC)
+    }
+
+    /// Parses the container in `raw`, without validating its code.
+    pub fn decode(raw: Bytes) -> eyre::Result<Self> {
+        let mut reader = Reader {
+            bytes: &raw,
+            position: 0,
+        };
+        if reader.take(2)? != MAGIC {
+            return Err(anyhow!("invalid magic"));
+        }
+        if reader.u8()? != VERSION {
+            return Err(anyhow!("invalid version"));
+        }
+
+        reader.expect(KIND_TYPES, "missing type section header")?;
+        let types_size = reader.u16()? as usize;
+        if types_size == 0 || !types_size.is_multiple_of(4) {
+            return Err(anyhow!("invalid type section size"));
+        }
+        reader.expect(KIND_CODE, "missing code section header")?;
+        let code_sizes = reader.section_sizes(MAX_CODE_SECTIONS, 2, "code")?;
+        if types_size / 4 != code_sizes.len() {
+            return Err(anyhow!("invalid type section size"));
+        }
+        let container_sizes = if reader.peek() == Some(KIND_CONTAINER) {
+            reader.u8()?;
+            reader.section_sizes(MAX_CONTAINER_SECTIONS, 4, "container")?
+        } else {
+            Vec::new()
+        };
+        reader.expect(KIND_DATA, "missing data section header")?;
+        let data_size = reader.u16()?;
+        reader.expect(TERMINATOR, "missing header terminator")?;
+
+        let types = (0..code_sizes.len())
+            .map(|_| {
+                Ok(TypeSection {
+                    inputs: reader.u8()?,
+                    outputs: reader.u8()?,
+                    max_stack_height: reader.u16()?,
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let code_offsets = code_sizes
+            .iter()
+            .scan(reader.position, |offset, size| {
+                *offset += size;
+                Some(*offset - size)
+            })
+            .collect();
+        let mut section = |size| -> eyre::Result<Bytes> {
+            let start = reader.position;
+            reader
+                .take(size)
+                .map_err(|_| anyhow!("truncated section"))?;
+            Ok(raw.slice(start..start + size))
+        };
+        let code_sections = code_sizes
+            .into_iter()
+            .map(&mut section)
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let container_sections = container_sizes
+            .into_iter()
+            .map(&mut section)
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let data = raw.slice(reader.position..);
+        if data.len() > data_size as usize {
+            return Err(anyhow!("trailing bytes after data section"));
+        }
+
+        Ok(Self {
+            types,
+            code_sections,
+            code_offsets,
+            container_sections,
+            data,
+            data_size,
+            raw,
+        })
+    }
+
+    /// Parses and validates the container in `raw` as a `kind` container.
+    pub fn decode_valid(raw: Bytes, kind: ContainerKind) -> eyre::Result<Self> {
+        let eof = Self::decode(raw)?;
+        eof.validate(kind)?;
+        Ok(eof)
+    }
+
+    /// Checks the container and its subcontainers follow every EOF rule.
+    pub fn validate(&self, kind: ContainerKind) -> eyre::Result<()> {
+        self.validate_container(kind, false)
+    }
+
+    fn validate_container(&self, kind: ContainerKind, truncated_data: bool) -> eyre::Result<()> {
+        if self.raw.len() > MAX_INITCODE_SIZE {
+            return Err(anyhow!("container size above limit"));
+        }
+        if !truncated_data && self.data.len() != self.data_size as usize {
+            return Err(anyhow!("truncated data section"));
+        }
+        let first = self.types[0];
+        if first.inputs != 0 || first.is_returning() {
+            return Err(anyhow!("invalid first section type"));
+        }
+        for (i, ty) in self.types.iter().enumerate() {
+            if ty.inputs > MAX_INPUTS_OUTPUTS
+                || (ty.outputs > MAX_INPUTS_OUTPUTS && ty.is_returning())
+            {
+                return Err(anyhow!("invalid section type {i}"));
+            }
+            if ty.max_stack_height > MAX_STACK_HEIGHT {
+                return Err(anyhow!("max stack height above limit in section {i}"));
+            }
+        }
+
+        let mut references = References {
+            code: vec![Vec::new(); self.code_sections.len()],
+            containers: vec![None; self.container_sections.len()],
+        };
+        for i in 0..self.code_sections.len() {
+            self.validate_code(i, kind, &mut references)
+                .map_err(|e| anyhow!("section {i}: {e}"))?;
+            self.validate_stack(i)
+                .map_err(|e| anyhow!("section {i}: {e}"))?;
+        }
+
+        // Every section must be reachable from the first one.
+        let mut reached = bitvec![usize, Lsb0; 0; self.code_sections.len()];
+        let mut queue = vec![0];
+        while let Some(section) = queue.pop() {
+            if reached.replace(section, true) {
+                continue;
+            }
+            queue.extend(references.code[section].iter().copied());
+        }
+        if let Some(section) = reached.first_zero() {
+            return Err(anyhow!("unreachable code section {section}"));
+        }
+
+        for (i, (container, kind)) in self
+            .container_sections
+            .iter()
+            .zip(references.containers)
+            .enumerate()
+        {
+            let kind = kind.ok_or_else(|| anyhow!("unreferenced container {i}"))?;
+            Self::decode(container.clone())
+                .and_then(|eof| eof.validate_container(kind, kind == ContainerKind::Runtime))
+                .map_err(|e| anyhow!("container {i}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Checks the instructions and their immediates in code section `index`,
+    /// recording the sections and containers it references.
+    fn validate_code(
+        &self,
+        index: usize,
+        kind: ContainerKind,
+        references: &mut References,
+    ) -> eyre::Result<()> {
+        let code = &self.code_sections[index];
+        let mut starts: BitVec<usize, Lsb0> = bitvec![usize, Lsb0; 0; code.len()];
+        let mut jump_targets = Vec::new();
+        let mut returns = false;
+        let mut i = 0;
+        while i < code.len() {
+            let op = code[i];
+            starts.set(i, true);
+            if !is_valid(op) {
+                return Err(anyhow!("undefined instruction {op:#04x} at {i}"));
+            }
+            let size = immediate_size(code, i);
+            if i + size >= code.len() && (size > 0 || op == RJUMPV) {
+                return Err(anyhow!("truncated instruction at {i}"));
+            }
+            let immediate = &code[i + 1..i + 1 + size];
+            let next = i + 1 + size;
+
+            match op {
+                RJUMP | RJUMPI => jump_targets.push(relative_target(next, immediate)),
+                RJUMPV => jump_targets.extend(
+                    immediate[1..]
+                        .chunks(2)
+                        .map(|offset| relative_target(next, offset)),
+                ),
+                CALLF | JUMPF => {
+                    let target = u16::from_be_bytes([immediate[0], immediate[1]]) as usize;
+                    let ty = self
+                        .types
+                        .get(target)
+                        .ok_or_else(|| anyhow!("invalid code section index at {i}"))?;
+                    if op == CALLF && !ty.is_returning() {
+                        return Err(anyhow!("CALLF to non-returning section at {i}"));
+                    }
+                    if op == JUMPF && ty.is_returning() {
+                        returns = true;
+                    }
+                    references.code[index].push(target);
+                }
+                RETF => returns = true,
+                DATALOADN => {
+                    let offset = u16::from_be_bytes([immediate[0], immediate[1]]) as usize;
+                    if offset + 32 > self.data_size as usize {
+                        return Err(anyhow!("invalid DATALOADN index at {i}"));
+                    }
+                }
+                EOFCREATE | RETURNCONTRACT => {
+                    let target = immediate[0] as usize;
+                    let referenced_as = if op == EOFCREATE {
+                        ContainerKind::Initcode
+                    } else {
+                        ContainerKind::Runtime
+                    };
+                    let reference = references
+                        .containers
+                        .get_mut(target)
+                        .ok_or_else(|| anyhow!("invalid container section index at {i}"))?;
+                    if reference.is_some_and(|kind| kind != referenced_as) {
+                        return Err(anyhow!(
+                            "container {target} referenced by both EOFCREATE and RETURNCONTRACT"
+                        ));
+                    }
+                    *reference = Some(referenced_as);
+                    if op == RETURNCONTRACT && kind == ContainerKind::Runtime {
+                        return Err(anyhow!("RETURNCONTRACT in runtime container at {i}"));
+                    }
+                }
+                opcode::STOP | opcode::RETURN if kind == ContainerKind::Initcode => {
+                    return Err(anyhow!("{} in initcode container at {i}", name(op)));
+                }
+                _ => {}
+            }
+            i = next;
+        }
+
+        for target in jump_targets {
+            if !usize::try_from(target).is_ok_and(|target| target < code.len() && starts[target]) {
+                return Err(anyhow!("invalid relative jump target {target}"));
+            }
+        }
+        if returns != self.types[index].is_returning() {
+            return Err(anyhow!("invalid non-returning flag"));
+        }
+        Ok(())
+    }
+
+    /// Checks no instruction can underflow or overflow the stack, and that
+    /// the declared max stack height is the one actually reached (EIP-5450).
+    fn validate_stack(&self, index: usize) -> eyre::Result<()> {
+        let code = &self.code_sections[index];
+        let ty = self.types[index];
+        // The range of stack heights each instruction can start with.
+        let mut heights: Vec<Option<(u16, u16)>> = vec![None; code.len()];
+        heights[0] = Some((ty.inputs as u16, ty.inputs as u16));
+        let mut max_height = ty.inputs as u16;
+
+        let mut i = 0;
+        while i < code.len() {
+            let (min, max) = heights[i].ok_or_else(|| anyhow!("unreachable instruction at {i}"))?;
+            let op = code[i];
+            let size = immediate_size(code, i);
+            let immediate = &code[i + 1..i + 1 + size];
+            let next = i + 1 + size;
+
+            let (inputs, outputs) = match op {
+                CALLF | JUMPF => {
+                    let target = u16::from_be_bytes([immediate[0], immediate[1]]) as usize;
+                    let target = self.types[target];
+                    let growth = target.max_stack_height - target.inputs as u16;
+                    if max as usize + growth as usize > STACK_SIZE {
+                        return Err(anyhow!("stack overflow at {i}"));
+                    }
+                    if op == JUMPF && target.is_returning() {
+                        let expected =
+                            ty.outputs as u16 + target.inputs as u16 - target.outputs as u16;
+                        if min != max || min != expected {
+                            return Err(anyhow!("invalid stack height for JUMPF at {i}"));
+                        }
+                    }
+                    let outputs = if target.is_returning() {
+                        target.outputs
+                    } else {
+                        0
+                    };
+                    (target.inputs, outputs)
+                }
+                RETF => {
+                    if min != max || min != ty.outputs as u16 {
+                        return Err(anyhow!("invalid stack height for RETF at {i}"));
+                    }
+                    (ty.outputs, 0)
+                }
+                _ => stack_io(op, immediate),
+            };
+            if min < inputs as u16 {
+                return Err(anyhow!("stack underflow at {i}"));
+            }
+            let after = (
+                min - inputs as u16 + outputs as u16,
+                max - inputs as u16 + outputs as u16,
+            );
+            max_height = max_height.max(after.1);
+
+            let mut successors = Vec::new();
+            if !is_terminating(op) {
+                successors.push(next);
+            }
+            match op {
+                RJUMP | RJUMPI => successors.push(relative_target(next, immediate) as usize),
+                RJUMPV => successors.extend(
+                    immediate[1..]
+                        .chunks(2)
+                        .map(|offset| relative_target(next, offset) as usize),
+                ),
+                _ => {}
+            }
+            for successor in successors {
+                if successor >= code.len() {
+                    return Err(anyhow!("no terminating instruction"));
+                }
+                if successor > i {
+                    heights[successor] = Some(match heights[successor] {
+                        Some((min, max)) => (min.min(after.0), max.max(after.1)),
+                        None => after,
+                    });
+                } else if heights[successor] != Some(after) {
+                    return Err(anyhow!("invalid stack height at backward jump from {i}"));
+                }
+            }
+            i = next;
+        }
+
+        if max_height > MAX_STACK_HEIGHT {
+            return Err(anyhow!("max stack height above limit"));
+        }
+        if max_height != ty.max_stack_height {
+            return Err(anyhow!(
+                "invalid max stack height: have {}, want {max_height}",
+                ty.max_stack_height
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The sections and containers referenced by each code section.
+struct References {
+    code: Vec<Vec<usize>>,
+    containers: Vec<Option<ContainerKind>>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn take(&mut self, len: usize) -> eyre::Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or_else(|| anyhow!("truncated header"))?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> eyre::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> eyre::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn expect(&mut self, byte: u8, error: &str) -> eyre::Result<()> {
+        if self.u8()? != byte {
+            return Err(anyhow!("{error}"));
+        }
+        Ok(())
+    }
+
+    /// Reads the number of sections of a kind, then the size of each, in
+    /// `width` bytes.
+    fn section_sizes(
+        &mut self,
+        limit: usize,
+        width: usize,
+        name: &str,
+    ) -> eyre::Result<Vec<usize>> {
+        let count = self.u16()? as usize;
+        if count == 0 {
+            return Err(anyhow!("no {name} sections"));
+        }
+        if count > limit {
+            return Err(anyhow!("too many {name} sections"));
+        }
+        (0..count)
+            .map(|_| {
+                let size = self
+                    .take(width)?
+                    .iter()
+                    .fold(0, |size, byte| size << 8 | *byte as usize);
+                if size == 0 {
+                    return Err(anyhow!("empty {name} section"));
+                }
+                Ok(size)
+            })
+            .collect()
+    }
+}
+
+/// The target of a relative jump, given the position after the jump and its
+/// 16 bit signed offset.
+fn relative_target(next: usize, offset: &[u8]) -> isize {
+    next as isize + i16::from_be_bytes([offset[0], offset[1]]) as isize
+}
+
+/// Whether `op` may appear in EOF code. Instructions that inspect or jump
+/// around code, observe gas or use legacy calls and creation are removed.
+pub const fn is_valid(op: u8) -> bool {
+    matches!(
+        op,
+        0x00..=0x0B
+            | 0x10..=0x1D
+            | 0x20
+            | 0x30..=0x37
+            | 0x3A
+            | 0x3D
+            | 0x3E
+            | 0x40..=0x4A
+            | 0x50..=0x55
+            | 0x59
+            | 0x5B..=0x9F
+            | 0xA0..=0xA4
+            | DATALOAD..=DATACOPY
+            | RJUMP..=EXCHANGE
+            | EOFCREATE
+            | RETURNCONTRACT
+            | opcode::RETURN
+            | RETURNDATALOAD
+            | EXTCALL
+            | EXTDELEGATECALL
+            | EXTSTATICCALL
+            | opcode::REVERT
+            | opcode::INVALID
+    )
+}
+
+/// Whether `op` is only defined in EOF code.
+pub const fn is_eof_only(op: u8) -> bool {
+    matches!(
+        op,
+        DATALOAD..=DATACOPY
+            | RJUMP..=EXCHANGE
+            | EOFCREATE
+            | RETURNCONTRACT
+            | RETURNDATALOAD
+            | EXTCALL
+            | EXTDELEGATECALL
+            | EXTSTATICCALL
+    )
+}
+
+/// The number of immediate bytes following the instruction at `i`.
+pub fn immediate_size(code: &[u8], i: usize) -> usize {
+    match code[i] {
+        op @ PUSH1..=PUSH32 => (op - PUSH1 + 1) as usize,
+        RJUMP | RJUMPI | CALLF | JUMPF | DATALOADN => 2,
+        DUPN | SWAPN | EXCHANGE | EOFCREATE | RETURNCONTRACT => 1,
+        RJUMPV => code
+            .get(i + 1)
+            .map_or(0, |max_index| 1 + 2 * (*max_index as usize + 1)),
+        _ => 0,
+    }
+}
+
+fn stack_io(op: u8, immediate: &[u8]) -> (u8, u8) {
+    match op {
+        DATALOAD | RETURNDATALOAD => (1, 1),
+        DATALOADN | DATASIZE => (0, 1),
+        DATACOPY => (3, 0),
+        RJUMPI | RJUMPV => (1, 0),
+        DUPN => (immediate[0] + 1, immediate[0] + 2),
+        SWAPN => (immediate[0] + 2, immediate[0] + 2),
+        EXCHANGE => {
+            let n = (immediate[0] >> 4) + 1;
+            let m = (immediate[0] & 0x0F) + 1;
+            (n + m + 1, n + m + 1)
+        }
+        EOFCREATE | EXTCALL => (4, 1),
+        RETURNCONTRACT => (2, 0),
+        EXTDELEGATECALL | EXTSTATICCALL => (3, 1),
+        _ => opcode::stack_io(op),
+    }
+}
+
+fn is_terminating(op: u8) -> bool {
+    matches!(
+        op,
+        opcode::STOP
+            | opcode::RETURN
+            | opcode::REVERT
+            | opcode::INVALID
+            | RJUMP
+            | RETF
+            | JUMPF
+            | RETURNCONTRACT
+    )
+}
+
+fn name(op: u8) -> &'static str {
+    opcode::OPCODE_NAMES[op as usize].unwrap_or("UNKNOWN")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::{ContainerKind, Eof, TypeSection, NON_RETURNING};
+
+    /// Encodes a container with `sections` of `(type, code)`, `containers`
+    /// and `data`.
+    fn container(sections: &[((u8, u8, u16), &str)], containers: &[&[u8]], data: &str) -> Bytes {
+        let code: Vec<Vec<u8>> = sections
+            .iter()
+            .map(|(_, code)| code.parse::<Bytes>().unwrap().to_vec())
+            .collect();
+        let data = data.parse::<Bytes>().unwrap();
+        let mut out = vec![0xEF, 0x00, 0x01, 0x01];
+        out.extend_from_slice(&(4 * sections.len() as u16).to_be_bytes());
+        out.push(0x02);
+        out.extend_from_slice(&(sections.len() as u16).to_be_bytes());
+        for code in &code {
+            out.extend_from_slice(&(code.len() as u16).to_be_bytes());
+        }
+        if !containers.is_empty() {
+            out.push(0x03);
+            out.extend_from_slice(&(containers.len() as u16).to_be_bytes());
+            for container in containers {
+                out.extend_from_slice(&(container.len() as u32).to_be_bytes());
+            }
+        }
+        out.push(0xFF);
+        out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        out.push(0x00);
+        for ((inputs, outputs, max_stack_height), _) in sections {
+            out.extend_from_slice(&[*inputs, *outputs]);
+            out.extend_from_slice(&max_stack_height.to_be_bytes());
+        }
+        for code in code {
+            out.extend_from_slice(&code);
+        }
+        for container in containers {
+            out.extend_from_slice(container);
+        }
+        out.extend_from_slice(&data);
+        out.into()
+    }
+
+    fn validate(sections: &[((u8, u8, u16), &str)], kind: ContainerKind) -> Result<(), String> {
+        let raw = container(sections, &[], "");
+        Eof::decode(raw)
+            .and_then(|eof| eof.validate(kind))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn decodes_containers() {
+        let raw = container(&[((0, 0x80, 1), "0x600000")], &[&[0xAA]], "0xaaaa");
+        let eof = Eof::decode(raw.clone()).expect("should decode");
+        assert_eq!(
+            vec![TypeSection {
+                inputs: 0,
+                outputs: NON_RETURNING,
+                max_stack_height: 1,
+            }],
+            eof.types
+        );
+        assert_eq!(
+            vec![Bytes::from_static(&[0x60, 0x00, 0x00])],
+            eof.code_sections
+        );
+        assert_eq!(vec![Bytes::from_static(&[0xAA])], eof.container_sections);
+        assert_eq!(Bytes::from_static(&[0xAA, 0xAA]), eof.data);
+        assert_eq!(raw, eof.raw);
+
+        let err = |hex: &str| Eof::decode(hex.parse().unwrap()).unwrap_err().to_string();
+        assert_eq!("invalid magic", err("0xef0101"));
+        assert_eq!("invalid version", err("0xef0002"));
+        assert_eq!("truncated header", err("0xef0001010004"));
+        assert_eq!(
+            "invalid type section size",
+            err("0xef0001010008020001000100")
+        );
+        assert_eq!("no code sections", err("0xef000101000402000000"));
+        assert_eq!("empty code section", err("0xef00010100040200010000"));
+        assert_eq!(
+            "missing header terminator",
+            err("0xef00010100040200010001ff000001")
+        );
+        assert_eq!(
+            "truncated section",
+            err("0xef0001010004020001000aff00000000800000")
+        );
+        assert_eq!(
+            "trailing bytes after data section",
+            err("0xef00010100040200010001ff0000000080000000aa")
+        );
+    }
+
+    #[test]
+    fn validates_instructions() {
+        let runtime = ContainerKind::Runtime;
+        // PUSH1 0x00, STOP
+        assert_eq!(Ok(()), validate(&[((0, 0x80, 1), "0x600000")], runtime));
+        assert_eq!(
+            Err("invalid first section type".to_owned()),
+            validate(&[((0, 0, 1), "0x600000")], runtime)
+        );
+        // PC
+        assert_eq!(
+            Err("section 0: undefined instruction 0x58 at 0".to_owned()),
+            validate(&[((0, 0x80, 1), "0x5800")], runtime)
+        );
+        // PUSH2 0x00
+        assert_eq!(
+            Err("section 0: truncated instruction at 0".to_owned()),
+            validate(&[((0, 0x80, 1), "0x6100")], runtime)
+        );
+        // RJUMP into the immediate of PUSH1.
+        assert_eq!(
+            Err("section 0: invalid relative jump target 4".to_owned()),
+            validate(&[((0, 0x80, 1), "0xe00001600000")], runtime)
+        );
+        // STOP in initcode.
+        assert_eq!(
+            Err("section 0: STOP in initcode container at 0".to_owned()),
+            validate(&[((0, 0x80, 0), "0x00")], ContainerKind::Initcode)
+        );
+        // CALLF to a section that never runs.
+        assert_eq!(
+            Err("unreachable code section 1".to_owned()),
+            validate(&[((0, 0x80, 0), "0x00"), ((0, 0, 0), "0xe4")], runtime)
+        );
+        // CALLF 0x0001, STOP and a section returning one item.
+        assert_eq!(
+            Ok(()),
+            validate(
+                &[((0, 0x80, 1), "0xe3000100"), ((0, 1, 1), "0x6001e4")],
+                runtime
+            )
+        );
+        // A returning section without RETF.
+        assert_eq!(
+            Err("section 1: invalid non-returning flag".to_owned()),
+            validate(
+                &[((0, 0x80, 1), "0xe3000100"), ((0, 1, 1), "0x600100")],
+                runtime
+            )
+        );
+        // JUMPF from a non-returning section to a returning one.
+        assert_eq!(
+            Err("section 0: invalid non-returning flag".to_owned()),
+            validate(&[((0, 0x80, 0), "0xe50001"), ((0, 0, 0), "0xe4")], runtime)
+        );
+    }
+
+    #[test]
+    fn validates_stack_heights() {
+        let runtime = ContainerKind::Runtime;
+        // ADD
+        assert_eq!(
+            Err("section 0: stack underflow at 0".to_owned()),
+            validate(&[((0, 0x80, 0), "0x0100")], runtime)
+        );
+        assert_eq!(
+            Err("section 0: invalid max stack height: have 2, want 1".to_owned()),
+            validate(&[((0, 0x80, 2), "0x600000")], runtime)
+        );
+        // PUSH1 0x01, without a terminating instruction.
+        assert_eq!(
+            Err("section 0: no terminating instruction".to_owned()),
+            validate(&[((0, 0x80, 1), "0x6001")], runtime)
+        );
+        // PUSH1 0x01, RJUMPI +1, PUSH0, STOP: the branches meet with
+        // different heights, which is fine going forward.
+        assert_eq!(
+            Ok(()),
+            validate(&[((0, 0x80, 1), "0x6001e100015f00")], runtime)
+        );
+        // PUSH0, RJUMP -4: a loop growing the stack.
+        assert_eq!(
+            Err("section 0: invalid stack height at backward jump from 1".to_owned()),
+            validate(&[((0, 0x80, 1), "0x5fe0fffc")], runtime)
+        );
+        // PUSH0, POP, RJUMP -5: a loop keeping it constant.
+        assert_eq!(Ok(()), validate(&[((0, 0x80, 1), "0x5f50e0fffb")], runtime));
+        // PUSH0, STOP, PUSH0: unreachable code.
+        assert_eq!(
+            Err("section 0: unreachable instruction at 2".to_owned()),
+            validate(&[((0, 0x80, 1), "0x5f005f00")], runtime)
+        );
+    }
+
+    #[test]
+    fn validates_subcontainers() {
+        let runtime = container(&[((0, 0x80, 0), "0x00")], &[], "0xaabb");
+        // PUSH0, PUSH0, RETURNCONTRACT 0 deploys the runtime container.
+        let initcode = container(&[((0, 0x80, 2), "0x5f5fee00")], &[&runtime], "");
+        let eof = Eof::decode(initcode.clone()).expect("should decode");
+        assert_eq!(
+            Ok(()),
+            eof.validate(ContainerKind::Initcode)
+                .map_err(|e| e.to_string())
+        );
+        assert_eq!(
+            Err("section 0: RETURNCONTRACT in runtime container at 2".to_owned()),
+            eof.validate(ContainerKind::Runtime)
+                .map_err(|e| e.to_string())
+        );
+
+        // The deployed container may have its data truncated.
+        let truncated = &runtime[..runtime.len() - 1];
+        let initcode = container(&[((0, 0x80, 2), "0x5f5fee00")], &[truncated], "");
+        let eof = Eof::decode(initcode).expect("should decode");
+        assert_eq!(
+            Ok(()),
+            eof.validate(ContainerKind::Initcode)
+                .map_err(|e| e.to_string())
+        );
+        let eof = Eof::decode(Bytes::copy_from_slice(truncated)).expect("should decode");
+        assert_eq!(
+            Err("truncated data section".to_owned()),
+            eof.validate(ContainerKind::Runtime)
+                .map_err(|e| e.to_string())
+        );
+
+        // PUSH0, STOP never uses its container.
+        let unused = container(&[((0, 0x80, 1), "0x5f00")], &[&runtime], "");
+        let eof = Eof::decode(unused).expect("should decode");
+        assert_eq!(
+            Err("unreferenced container 0".to_owned()),
+            eof.validate(ContainerKind::Runtime)
+                .map_err(|e| e.to_string())
+        );
+    }
+}
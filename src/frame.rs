@@ -0,0 +1,289 @@
+use std::rc::Rc;
+
+use alloy_primitives::{Address, Bytes};
+
+use crate::{
+    bytecode,
+    env::Env,
+    gas::Gas,
+    journal::JournaledState,
+    precompiles::{PrecompileContext, PrecompileOutput, PrecompileResult},
+    spec::SpecId,
+    Bytecode, Contract, Interpreter,
+};
+
+/// The largest contract code that can be deployed (EIP-170).
+pub const MAX_CODE_SIZE: usize = 0x6000;
+/// The largest initcode that can be run (EIP-3860).
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+/// The deepest a call can be nested.
+pub const CALL_STACK_LIMIT: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+}
+
+impl CallKind {
+    /// Whether the call moves `value` from the caller to the callee.
+    pub fn transfers_value(self) -> bool {
+        matches!(self, Self::Call | Self::CallCode)
+    }
+}
+
+#[derive(Debug)]
+pub struct CallInputs {
+    /// The context the code runs in. Its bytecode is loaded from
+    /// `code_address` when the call starts.
+    pub contract: Contract,
+    pub code_address: Address,
+    pub gas_limit: u64,
+    pub kind: CallKind,
+    pub is_static: bool,
+    pub depth: usize,
+}
+
+/// How a frame halted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Halted with `STOP` or `RETURN`.
+    Success,
+    /// Halted with `REVERT`. Unused gas is returned to the caller.
+    Revert,
+    /// Halted exceptionally, consuming all of its gas.
+    Halt,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameResult {
+    pub status: ExitStatus,
+    pub output: Bytes,
+    pub gas: Gas,
+    /// The address of the created contract, for successful create frames.
+    pub address: Option<Address>,
+}
+
+impl FrameResult {
+    fn halt(gas_limit: u64) -> Self {
+        let mut gas = Gas::new(gas_limit);
+        gas.remaining = 0;
+        Self {
+            status: ExitStatus::Halt,
+            output: Bytes::new(),
+            gas,
+            address: None,
+        }
+    }
+
+    fn revert(gas_limit: u64) -> Self {
+        Self {
+            status: ExitStatus::Revert,
+            output: Bytes::new(),
+            gas: Gas::new(gas_limit),
+            address: None,
+        }
+    }
+}
+
+/// Runs a message call in a new frame, after transferring its value. The
+/// precompile registry, custom precompiles first, is consulted before
+/// loading any code. All of the frame's state changes, including the
+/// transfer, are undone unless it succeeds.
+pub fn call(journal: &mut JournaledState, env: Rc<Env>, inputs: CallInputs) -> FrameResult {
+    let CallInputs {
+        mut contract,
+        code_address,
+        gas_limit,
+        kind,
+        is_static,
+        depth,
+    } = inputs;
+    if depth > CALL_STACK_LIMIT {
+        return FrameResult::revert(gas_limit);
+    }
+
+    let checkpoint = journal.checkpoint();
+    if matches!(kind, CallKind::Call | CallKind::StaticCall) {
+        // Before EIP-161 every call created its target. Since then, calls
+        // only touch it, deleting it at the end of the transaction if it
+        // is empty.
+        if env.cfg.spec.is_enabled_in(SpecId::SpuriousDragon) {
+            journal.touch(contract.address);
+        } else {
+            journal.load_account(contract.address);
+        }
+    }
+    if kind.transfers_value()
+        && !contract.value.is_zero()
+        && journal
+            .transfer(contract.caller, contract.address, contract.value)
+            .is_err()
+    {
+        journal.revert(checkpoint);
+        return FrameResult::revert(gas_limit);
+    }
+
+    let precompiles = &env.cfg.precompiles;
+    let result = if let Some(precompile) = precompiles.get_custom(&code_address) {
+        let cost = precompile.gas(&contract.input);
+        if cost > gas_limit {
+            FrameResult::halt(gas_limit)
+        } else {
+            let mut context = PrecompileContext {
+                journal,
+                env: &env,
+                caller: contract.caller,
+                address: contract.address,
+                value: contract.value,
+                is_static,
+                depth,
+            };
+            let output = precompile
+                .run(&mut context, &contract.input)
+                .map(|bytes| PrecompileOutput::new(cost, bytes));
+            precompile_result(output, gas_limit)
+        }
+    } else if let Some(precompile) = precompiles.get(&code_address, env.cfg.spec) {
+        precompile_result(precompile(&contract.input, gas_limit), gas_limit)
+    } else {
+        let code = journal.code(&code_address);
+        // Delegated accounts run the code they delegate to (EIP-7702).
+        let code = match bytecode::delegation(&code) {
+            Some(target) if env.cfg.spec.is_enabled_in(SpecId::Prague) => journal.code(&target),
+            _ => code,
+        };
+        contract.bytecode = Bytecode::new(code);
+        run(journal, env, contract, gas_limit, depth, is_static)
+    };
+
+    if result.status == ExitStatus::Success {
+        journal.commit(checkpoint);
+    } else {
+        journal.revert(checkpoint);
+    }
+    result
+}
+
+/// Runs the initcode in `contract.bytecode` in a new frame that deploys its
+/// output as the code of `contract.address`.
+pub fn create(
+    journal: &mut JournaledState,
+    env: Rc<Env>,
+    contract: Contract,
+    gas_limit: u64,
+    depth: usize,
+) -> FrameResult {
+    let spec = env.cfg.spec;
+    let address = contract.address;
+    if depth > CALL_STACK_LIMIT {
+        return FrameResult::revert(gas_limit);
+    }
+
+    // Deploying to an address with code, a nonce or storage is a collision
+    // (EIP-7610).
+    let collides = journal.account(&address).is_some_and(|a| {
+        a.nonce != 0 || !a.code.is_empty() || a.storage.values().any(|v| !v.is_zero())
+    });
+    if collides {
+        return FrameResult::halt(gas_limit);
+    }
+
+    let checkpoint = journal.checkpoint();
+    journal.load_account(address);
+    journal.mark_created(address);
+    if spec.is_enabled_in(SpecId::SpuriousDragon) && journal.increment_nonce(address).is_err() {
+        journal.revert(checkpoint);
+        return FrameResult::halt(gas_limit);
+    }
+    if journal
+        .transfer(contract.caller, address, contract.value)
+        .is_err()
+    {
+        journal.revert(checkpoint);
+        return FrameResult::revert(gas_limit);
+    }
+
+    let mut result = run(journal, env, contract, gas_limit, depth, false);
+    if result.status != ExitStatus::Success {
+        journal.revert(checkpoint);
+        return result;
+    }
+
+    let mut code = std::mem::take(&mut result.output);
+    let oversized = spec.is_enabled_in(SpecId::SpuriousDragon) && code.len() > MAX_CODE_SIZE;
+    // Code starting with 0xEF is reserved for EOF (EIP-3541).
+    let reserved = spec.is_enabled_in(SpecId::London) && code.first() == Some(&0xEF);
+    let deposit_cost = crate::gas::CODEDEPOSIT * code.len() as u64;
+    if oversized || reserved {
+        journal.revert(checkpoint);
+        return FrameResult::halt(gas_limit);
+    }
+    if result.gas.record_cost(deposit_cost).is_err() {
+        // Frontier deployed no code instead of failing (EIP-2).
+        if spec.is_enabled_in(SpecId::Homestead) {
+            journal.revert(checkpoint);
+            return FrameResult::halt(gas_limit);
+        }
+        code = Bytes::new();
+    }
+
+    journal.set_code(address, code);
+    journal.commit(checkpoint);
+    result.address = Some(address);
+    result
+}
+
+fn precompile_result(output: PrecompileResult, gas_limit: u64) -> FrameResult {
+    match output {
+        Ok(output) if output.gas_used <= gas_limit => {
+            let mut gas = Gas::new(gas_limit);
+            gas.remaining -= output.gas_used;
+            FrameResult {
+                status: ExitStatus::Success,
+                output: output.bytes,
+                gas,
+                address: None,
+            }
+        }
+        _ => FrameResult::halt(gas_limit),
+    }
+}
+
+fn run(
+    journal: &mut JournaledState,
+    env: Rc<Env>,
+    contract: Contract,
+    gas_limit: u64,
+    depth: usize,
+    is_static: bool,
+) -> FrameResult {
+    let mut interpreter = Interpreter::with_journal(Box::new(contract), std::mem::take(journal));
+    interpreter.gas = Gas::new(gas_limit);
+    interpreter.env = env;
+    interpreter.depth = depth;
+    interpreter.is_static = is_static;
+    let result = interpreter.execute();
+    *journal = std::mem::take(&mut interpreter.journal);
+
+    match result {
+        Ok(output) if interpreter.reverted.get() => FrameResult {
+            status: ExitStatus::Revert,
+            output,
+            gas: Gas {
+                refunded: 0,
+                ..interpreter.gas
+            },
+            address: None,
+        },
+        Ok(output) => FrameResult {
+            status: ExitStatus::Success,
+            output,
+            gas: interpreter.gas,
+            address: None,
+        },
+        Err(_) => FrameResult::halt(gas_limit),
+    }
+}
@@ -0,0 +1,499 @@
+use alloy_primitives::U256;
+use eyre::anyhow;
+
+use crate::{env::TxEnv, instructions::opcode, spec::SpecId};
+
+pub const ZERO: u64 = 0;
+pub const BASE: u64 = 2;
+pub const VERYLOW: u64 = 3;
+pub const LOW: u64 = 5;
+pub const MID: u64 = 8;
+pub const HIGH: u64 = 10;
+pub const JUMPDEST: u64 = 1;
+pub const EXPBYTE_FRONTIER: u64 = 10;
+/// The cost of each byte of the exponent of `EXP` since EIP-160.
+pub const EXPBYTE: u64 = 50;
+pub const KECCAK256: u64 = 30;
+pub const KECCAK256WORD: u64 = 6;
+pub const BLOCKHASH: u64 = 20;
+pub const WARM_STORAGE_READ: u64 = 100;
+pub const COLD_SLOAD: u64 = 2100;
+pub const COLD_ACCOUNT_ACCESS: u64 = 2600;
+pub const SLOAD_FRONTIER: u64 = 50;
+/// The cost of `SLOAD` since EIP-150.
+pub const SLOAD_TANGERINE: u64 = 200;
+/// The cost of `SLOAD` since EIP-1884.
+pub const SLOAD_ISTANBUL: u64 = 800;
+pub const SSTORE_SET: u64 = 20000;
+pub const SSTORE_RESET_FRONTIER: u64 = 5000;
+pub const SSTORE_RESET: u64 = SSTORE_RESET_FRONTIER - COLD_SLOAD;
+pub const SSTORE_CLEARS_SCHEDULE_FRONTIER: i64 = 15000;
+pub const SSTORE_CLEARS_SCHEDULE: i64 = 4800;
+/// `SSTORE` fails if less than this is left (EIP-2200).
+pub const SSTORE_SENTRY: u64 = 2300;
+pub const LOG: u64 = 375;
+pub const LOGDATA: u64 = 8;
+pub const LOGTOPIC: u64 = 375;
+pub const COPY: u64 = 3;
+pub const MEMORY: u64 = 3;
+pub const CODEDEPOSIT: u64 = 200;
+pub const CREATE: u64 = 32000;
+/// The cost of `SELFDESTRUCT` since EIP-150. It was free before.
+pub const SELFDESTRUCT: u64 = 5000;
+/// Refunded for each destroyed account before EIP-3529.
+pub const SELFDESTRUCT_REFUND: i64 = 24000;
+pub const CALL_FRONTIER: u64 = 40;
+/// The cost of the `CALL`s and `EXTCODE*`s since EIP-150.
+pub const CALL_TANGERINE: u64 = 700;
+pub const EXTCODE_FRONTIER: u64 = 20;
+pub const BALANCE_FRONTIER: u64 = 20;
+/// The cost of `BALANCE` since EIP-150.
+pub const BALANCE_TANGERINE: u64 = 400;
+/// The cost of `BALANCE` and `EXTCODEHASH` since EIP-1884.
+pub const BALANCE_ISTANBUL: u64 = 700;
+/// The cost of `EXTCODEHASH` when EIP-1052 introduced it.
+pub const EXTCODEHASH_CONSTANTINOPLE: u64 = 400;
+pub const CALLVALUE: u64 = 9000;
+pub const NEWACCOUNT: u64 = 25000;
+/// Added to the gas of calls that transfer value.
+pub const CALL_STIPEND: u64 = 2300;
+
+pub const TX: u64 = 21000;
+pub const TX_CREATE: u64 = 32000;
+pub const TX_DATA_ZERO: u64 = 4;
+pub const TX_DATA_NON_ZERO_FRONTIER: u64 = 68;
+pub const TX_DATA_NON_ZERO_ISTANBUL: u64 = 16;
+pub const TX_ACCESS_LIST_ADDRESS: u64 = 2400;
+pub const TX_ACCESS_LIST_STORAGE_KEY: u64 = 1900;
+pub const INITCODE_WORD: u64 = 2;
+/// The cost of a calldata token for the calldata floor (EIP-7623).
+pub const TX_TOTAL_COST_FLOOR_PER_TOKEN: u64 = 10;
+/// The intrinsic cost of each authorization (EIP-7702), assuming it
+/// creates the authority's account.
+pub const PER_EMPTY_ACCOUNT_COST: u64 = 25000;
+/// What an authorization costs when the authority's account already exists.
+pub const PER_AUTH_BASE_COST: u64 = 12500;
+
+/// Tracks the gas of a single frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Gas {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Can go negative within a frame, e.g. when a slot cleared by a parent
+    /// frame gets written again.
+    pub refunded: i64,
+}
+
+impl Gas {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            remaining: limit,
+            refunded: 0,
+        }
+    }
+
+    pub fn spent(&self) -> u64 {
+        self.limit - self.remaining
+    }
+
+    pub fn record_cost(&mut self, cost: u64) -> eyre::Result<()> {
+        self.remaining = self
+            .remaining
+            .checked_sub(cost)
+            .ok_or_else(|| anyhow!("out of gas"))?;
+        Ok(())
+    }
+
+    pub fn record_refund(&mut self, refund: i64) {
+        self.refunded += refund;
+    }
+}
+
+/// The constant part of the gas cost of each opcode. Opcodes whose whole cost
+/// depends on their operands are priced at zero here and charged by their
+/// instruction.
+pub const fn static_gas(op: u8) -> u64 {
+    match op {
+        opcode::STOP | opcode::RETURN | opcode::REVERT => ZERO,
+        opcode::JUMPDEST => JUMPDEST,
+        opcode::ADDRESS
+        | opcode::CALLDATASIZE
+        | opcode::ORIGIN
+        | opcode::CALLER
+        | opcode::CALLVALUE
+        | opcode::CODESIZE
+        | opcode::GASPRICE
+        | opcode::COINBASE
+        | opcode::TIMESTAMP
+        | opcode::NUMBER
+        | opcode::DIFFICULTY
+        | opcode::GASLIMIT
+        | opcode::CHAINID
+        | opcode::BASEFEE
+        | opcode::BLOBBASEFEE
+        | opcode::RETURNDATASIZE
+        | opcode::POP
+        | opcode::PC
+        | opcode::MSIZE
+        | opcode::GAS
+        | opcode::PUSH0 => BASE,
+        opcode::ADD
+        | opcode::SUB
+        | opcode::LT
+        | opcode::GT
+        | opcode::SLT
+        | opcode::SGT
+        | opcode::EQ
+        | opcode::ISZERO
+        | opcode::AND
+        | opcode::OR
+        | opcode::XOR
+        | opcode::NOT
+        | opcode::BYTE
+        | opcode::SHL
+        | opcode::SHR
+        | opcode::SAR
+        | opcode::CALLDATALOAD
+        | opcode::CALLDATACOPY
+        | opcode::CODECOPY
+        | opcode::RETURNDATACOPY
+        | opcode::BLOBHASH
+        | opcode::MLOAD
+        | opcode::MSTORE
+        | opcode::MSTORE8
+        | opcode::MCOPY => VERYLOW,
+        opcode::PUSH1..=opcode::SWAP16 => VERYLOW,
+        opcode::MUL
+        | opcode::DIV
+        | opcode::SDIV
+        | opcode::MOD
+        | opcode::SMOD
+        | opcode::SIGNEXTEND
+        | opcode::SELFBALANCE => LOW,
+        opcode::ADDMOD | opcode::MULMOD | opcode::JUMP => MID,
+        opcode::EXP | opcode::JUMPI => HIGH,
+        opcode::KECCAK256 => KECCAK256,
+        opcode::BLOCKHASH => BLOCKHASH,
+        opcode::CREATE | opcode::CREATE2 => CREATE,
+        opcode::RJUMP | opcode::DATASIZE => BASE,
+        opcode::RETF
+        | opcode::DATALOADN
+        | opcode::DATACOPY
+        | opcode::DUPN
+        | opcode::SWAPN
+        | opcode::EXCHANGE => VERYLOW,
+        opcode::RJUMPI | opcode::RJUMPV | opcode::DATALOAD => 4,
+        opcode::CALLF | opcode::JUMPF => LOW,
+        opcode::TLOAD | opcode::TSTORE => WARM_STORAGE_READ,
+        opcode::LOG0..=opcode::LOG4 => LOG + (op - opcode::LOG0) as u64 * LOGTOPIC,
+        _ => ZERO,
+    }
+}
+
+/// Returns the number of 32 byte words needed to hold `len` bytes.
+pub const fn num_words(len: u64) -> u64 {
+    len.saturating_add(31) / 32
+}
+
+/// The total cost of a memory of `num_words` words.
+pub const fn memory_cost(num_words: u64) -> u64 {
+    MEMORY
+        .saturating_mul(num_words)
+        .saturating_add(num_words.saturating_mul(num_words) / 512)
+}
+
+/// The cost of copying `len` bytes, as done by the `*COPY` opcodes.
+pub const fn copy_cost(len: u64) -> u64 {
+    COPY.saturating_mul(num_words(len))
+}
+
+/// The dynamic cost of `EXP`, which grows with the size of the exponent.
+pub fn exp_cost(exponent: U256, spec: SpecId) -> u64 {
+    let byte_cost = if spec.is_enabled_in(SpecId::SpuriousDragon) {
+        EXPBYTE
+    } else {
+        EXPBYTE_FRONTIER
+    };
+    byte_cost * exponent.byte_len() as u64
+}
+
+/// The cost of hashing `len` bytes with `KECCAK256`, or of hashing the
+/// initcode of `CREATE2`, on top of any static cost.
+pub const fn keccak256_cost(len: u64) -> u64 {
+    KECCAK256WORD.saturating_mul(num_words(len))
+}
+
+/// The cost of the opcodes that access another account, before EIP-2929
+/// made it depend on whether the account was accessed before.
+pub fn account_access_cost(op: u8, spec: SpecId) -> u64 {
+    match op {
+        opcode::BALANCE if spec.is_enabled_in(SpecId::Istanbul) => BALANCE_ISTANBUL,
+        opcode::BALANCE if spec.is_enabled_in(SpecId::Tangerine) => BALANCE_TANGERINE,
+        opcode::BALANCE => BALANCE_FRONTIER,
+        opcode::EXTCODEHASH if spec.is_enabled_in(SpecId::Istanbul) => BALANCE_ISTANBUL,
+        opcode::EXTCODEHASH => EXTCODEHASH_CONSTANTINOPLE,
+        opcode::EXTCODESIZE | opcode::EXTCODECOPY if spec.is_enabled_in(SpecId::Tangerine) => {
+            CALL_TANGERINE
+        }
+        opcode::EXTCODESIZE | opcode::EXTCODECOPY => EXTCODE_FRONTIER,
+        _ if spec.is_enabled_in(SpecId::Tangerine) => CALL_TANGERINE,
+        _ => CALL_FRONTIER,
+    }
+}
+
+/// The cost of `SLOAD` before EIP-2929.
+pub fn sload_cost(spec: SpecId) -> u64 {
+    if spec.is_enabled_in(SpecId::Istanbul) {
+        SLOAD_ISTANBUL
+    } else if spec.is_enabled_in(SpecId::Tangerine) {
+        SLOAD_TANGERINE
+    } else {
+        SLOAD_FRONTIER
+    }
+}
+
+/// Whether `SSTORE` is priced by how the slot changed over the transaction
+/// (EIP-1283, reverted in Petersburg and reintroduced by EIP-2200), rather
+/// than by its current value alone.
+fn is_net_metered(spec: SpecId) -> bool {
+    spec == SpecId::Constantinople || spec.is_enabled_in(SpecId::Istanbul)
+}
+
+/// The cost of `SSTORE` when a slot is left dirty, which is that of reading
+/// it.
+fn sstore_noop_cost(spec: SpecId) -> u64 {
+    if spec.is_enabled_in(SpecId::Berlin) {
+        WARM_STORAGE_READ
+    } else {
+        sload_cost(spec)
+    }
+}
+
+fn sstore_reset_cost(spec: SpecId) -> u64 {
+    if spec.is_enabled_in(SpecId::Berlin) {
+        SSTORE_RESET
+    } else {
+        SSTORE_RESET_FRONTIER
+    }
+}
+
+fn sstore_clears_schedule(spec: SpecId) -> i64 {
+    if spec.is_enabled_in(SpecId::London) {
+        SSTORE_CLEARS_SCHEDULE
+    } else {
+        SSTORE_CLEARS_SCHEDULE_FRONTIER
+    }
+}
+
+/// The dynamic cost of `SSTORE`, not counting the cold slot surcharge of
+/// EIP-2929.
+pub fn sstore_cost(original: U256, current: U256, new: U256, spec: SpecId) -> u64 {
+    if !is_net_metered(spec) {
+        return if current.is_zero() && !new.is_zero() {
+            SSTORE_SET
+        } else {
+            SSTORE_RESET_FRONTIER
+        };
+    }
+
+    if current == new || original != current {
+        sstore_noop_cost(spec)
+    } else if original.is_zero() {
+        SSTORE_SET
+    } else {
+        sstore_reset_cost(spec)
+    }
+}
+
+/// The refund granted (or taken back) by `SSTORE`.
+pub fn sstore_refund(original: U256, current: U256, new: U256, spec: SpecId) -> i64 {
+    let clears_schedule = sstore_clears_schedule(spec);
+    if !is_net_metered(spec) {
+        return if !current.is_zero() && new.is_zero() {
+            clears_schedule
+        } else {
+            0
+        };
+    }
+
+    if current == new {
+        return 0;
+    }
+
+    if original == current {
+        return if !original.is_zero() && new.is_zero() {
+            clears_schedule
+        } else {
+            0
+        };
+    }
+
+    let mut refund = 0;
+    if !original.is_zero() {
+        if current.is_zero() {
+            refund -= clears_schedule;
+        } else if new.is_zero() {
+            refund += clears_schedule;
+        }
+    }
+
+    if original == new {
+        let noop_cost = sstore_noop_cost(spec);
+        refund += if original.is_zero() {
+            (SSTORE_SET - noop_cost) as i64
+        } else {
+            (sstore_reset_cost(spec) - noop_cost) as i64
+        };
+    }
+
+    refund
+}
+
+/// The gas charged before execution starts: the base cost, calldata, access
+/// list and contract creation costs.
+pub fn intrinsic_gas(tx: &TxEnv, spec: SpecId) -> u64 {
+    let zeros = tx.data.iter().filter(|b| **b == 0).count() as u64;
+    let non_zeros = tx.data.len() as u64 - zeros;
+    let non_zero_cost = if spec.is_enabled_in(SpecId::Istanbul) {
+        TX_DATA_NON_ZERO_ISTANBUL
+    } else {
+        TX_DATA_NON_ZERO_FRONTIER
+    };
+
+    let mut gas = TX + zeros * TX_DATA_ZERO + non_zeros * non_zero_cost;
+
+    if tx.to.is_none() {
+        if spec.is_enabled_in(SpecId::Homestead) {
+            gas += TX_CREATE;
+        }
+        if spec.is_enabled_in(SpecId::Shanghai) {
+            gas += INITCODE_WORD * num_words(tx.data.len() as u64);
+        }
+    }
+
+    if spec.is_enabled_in(SpecId::Berlin) {
+        for item in tx.access_list.iter().flatten() {
+            gas += TX_ACCESS_LIST_ADDRESS;
+            gas += TX_ACCESS_LIST_STORAGE_KEY * item.storage_keys.len() as u64;
+        }
+    }
+
+    if let Some(authorizations) = &tx.authorization_list {
+        gas += PER_EMPTY_ACCOUNT_COST * authorizations.len() as u64;
+    }
+
+    gas
+}
+
+/// The minimum gas a transaction pays for its calldata (EIP-7623).
+pub fn calldata_floor_gas(tx: &TxEnv, spec: SpecId) -> u64 {
+    if !spec.is_enabled_in(SpecId::Prague) {
+        return 0;
+    }
+
+    let zeros = tx.data.iter().filter(|b| **b == 0).count() as u64;
+    let tokens = zeros + (tx.data.len() as u64 - zeros) * 4;
+    TX + tokens * TX_TOTAL_COST_FLOOR_PER_TOKEN
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, Bytes, B256, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        env::{AccessListItem, TxEnv},
+        instructions::opcode,
+        spec::SpecId,
+    };
+
+    use super::{
+        account_access_cost, intrinsic_gas, memory_cost, sload_cost, sstore_cost, sstore_refund,
+    };
+
+    fn u(value: u64) -> U256 {
+        U256::from(value)
+    }
+
+    #[test]
+    fn prices_memory() {
+        assert_eq!(0, memory_cost(0));
+        assert_eq!(3, memory_cost(1));
+        assert_eq!(98, memory_cost(32));
+    }
+
+    #[test]
+    fn prices_intrinsic_gas() {
+        let mut tx = TxEnv {
+            data: Bytes::from_static(&[0, 1, 0, 2]),
+            ..Default::default()
+        };
+        assert_eq!(21000 + 8 + 32, intrinsic_gas(&tx, SpecId::Cancun));
+        assert_eq!(21000 + 8 + 136, intrinsic_gas(&tx, SpecId::Byzantium));
+
+        tx.access_list = Some(vec![AccessListItem {
+            address: Address::ZERO,
+            storage_keys: vec![B256::ZERO; 2],
+        }]);
+        assert_eq!(21000 + 40 + 2400 + 3800, intrinsic_gas(&tx, SpecId::Cancun));
+
+        tx.access_list = None;
+        tx.to = None;
+        assert_eq!(21000 + 40 + 32000 + 2, intrinsic_gas(&tx, SpecId::Cancun));
+        assert_eq!(21000 + 40 + 32000, intrinsic_gas(&tx, SpecId::London));
+    }
+
+    #[test]
+    fn prices_sstore() {
+        let spec = SpecId::Cancun;
+        assert_eq!(20000, sstore_cost(u(0), u(0), u(1), spec));
+        assert_eq!(2900, sstore_cost(u(1), u(1), u(2), spec));
+        assert_eq!(100, sstore_cost(u(1), u(2), u(3), spec));
+        assert_eq!(100, sstore_cost(u(1), u(1), u(1), spec));
+
+        assert_eq!(4800, sstore_refund(u(1), u(1), u(0), spec));
+        assert_eq!(19900, sstore_refund(u(0), u(1), u(0), spec));
+        assert_eq!(2800, sstore_refund(u(1), u(2), u(1), spec));
+        assert_eq!(-4800, sstore_refund(u(1), u(0), u(2), spec));
+    }
+
+    #[test]
+    fn prices_sstore_before_berlin() {
+        let spec = SpecId::Istanbul;
+        assert_eq!(5000, sstore_cost(u(1), u(1), u(2), spec));
+        assert_eq!(800, sstore_cost(u(1), u(2), u(3), spec));
+        assert_eq!(15000, sstore_refund(u(1), u(1), u(0), spec));
+        assert_eq!(4200, sstore_refund(u(1), u(2), u(1), spec));
+
+        // Petersburg only looks at the current value.
+        let spec = SpecId::Petersburg;
+        assert_eq!(20000, sstore_cost(u(0), u(0), u(1), spec));
+        assert_eq!(5000, sstore_cost(u(1), u(2), u(3), spec));
+        assert_eq!(5000, sstore_cost(u(1), u(1), u(1), spec));
+        assert_eq!(15000, sstore_refund(u(0), u(1), u(0), spec));
+        assert_eq!(0, sstore_refund(u(1), u(2), u(1), spec));
+    }
+
+    #[test]
+    fn prices_account_access_before_berlin() {
+        assert_eq!(20, account_access_cost(opcode::BALANCE, SpecId::Frontier));
+        assert_eq!(400, account_access_cost(opcode::BALANCE, SpecId::Byzantium));
+        assert_eq!(700, account_access_cost(opcode::BALANCE, SpecId::Istanbul));
+        assert_eq!(
+            400,
+            account_access_cost(opcode::EXTCODEHASH, SpecId::Constantinople)
+        );
+        assert_eq!(
+            20,
+            account_access_cost(opcode::EXTCODESIZE, SpecId::Homestead)
+        );
+        assert_eq!(40, account_access_cost(opcode::CALL, SpecId::Homestead));
+        assert_eq!(
+            700,
+            account_access_cost(opcode::DELEGATECALL, SpecId::Tangerine)
+        );
+        assert_eq!(50, sload_cost(SpecId::Homestead));
+        assert_eq!(800, sload_cost(SpecId::Istanbul));
+    }
+}
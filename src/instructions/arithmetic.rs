@@ -1,8 +1,11 @@
 use alloy_primitives::U256;
 
-use crate::Interpreter;
+use crate::{gas, Interpreter};
 
-use super::InstructionResult;
+use super::{
+    i256::{i256_div, i256_mod},
+    InstructionResult,
+};
 
 pub fn add(interpreter: &mut Interpreter) -> InstructionResult {
     let a = interpreter.stack.pop()?;
@@ -40,3 +43,121 @@ pub fn div(interpreter: &mut Interpreter) -> InstructionResult {
 
     Ok(1)
 }
+
+pub fn sdiv(interpreter: &mut Interpreter) -> InstructionResult {
+    let a = interpreter.stack.pop()?;
+    let b = interpreter.stack.pop()?;
+    interpreter.stack.push(i256_div(a, b))?;
+
+    Ok(1)
+}
+
+pub fn rem(interpreter: &mut Interpreter) -> InstructionResult {
+    let a = interpreter.stack.pop()?;
+    let b = interpreter.stack.pop()?;
+    let r = if b.is_zero() { U256::ZERO } else { a % b };
+    interpreter.stack.push(r)?;
+
+    Ok(1)
+}
+
+pub fn smod(interpreter: &mut Interpreter) -> InstructionResult {
+    let a = interpreter.stack.pop()?;
+    let b = interpreter.stack.pop()?;
+    interpreter.stack.push(i256_mod(a, b))?;
+
+    Ok(1)
+}
+
+/// The sum is taken modulo `n` without overflowing 256 bits.
+pub fn addmod(interpreter: &mut Interpreter) -> InstructionResult {
+    let a = interpreter.stack.pop()?;
+    let b = interpreter.stack.pop()?;
+    let n = interpreter.stack.pop()?;
+    interpreter.stack.push(a.add_mod(b, n))?;
+
+    Ok(1)
+}
+
+/// The product is taken modulo `n` without overflowing 256 bits.
+pub fn mulmod(interpreter: &mut Interpreter) -> InstructionResult {
+    let a = interpreter.stack.pop()?;
+    let b = interpreter.stack.pop()?;
+    let n = interpreter.stack.pop()?;
+    interpreter.stack.push(a.mul_mod(b, n))?;
+
+    Ok(1)
+}
+
+pub fn exp(interpreter: &mut Interpreter) -> InstructionResult {
+    let base = interpreter.stack.pop()?;
+    let exponent = interpreter.stack.pop()?;
+    let cost = gas::exp_cost(exponent, interpreter.env.cfg.spec);
+    interpreter.gas.record_cost(cost)?;
+    interpreter.stack.push(base.wrapping_pow(exponent))?;
+
+    Ok(1)
+}
+
+/// Extends the sign bit of the byte at index `b`, counting from the least
+/// significant byte, over the higher bytes of `x`.
+pub fn signextend(interpreter: &mut Interpreter) -> InstructionResult {
+    let b = interpreter.stack.pop()?;
+    let x = interpreter.stack.pop()?;
+    let r = if b < U256::from(31) {
+        let bit = b.as_limbs()[0] as usize * 8 + 7;
+        let mask = (U256::from(1) << bit) - U256::from(1);
+        if x.bit(bit) {
+            x | !mask
+        } else {
+            x & mask
+        }
+    } else {
+        x
+    };
+    interpreter.stack.push(r)?;
+
+    Ok(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use pretty_assertions::assert_eq;
+
+    use crate::{Contract, Interpreter};
+
+    fn run(bytecode: &str) -> U256 {
+        let bytes = bytecode.parse().unwrap();
+        let contract = Box::new(Contract::new(Default::default(), bytes, Default::default()));
+        let mut interpreter = Interpreter::new(contract);
+        interpreter.execute().expect("should finish execution");
+        interpreter.stack.pop().expect("should leave a result")
+    }
+
+    fn minus(value: u64) -> U256 {
+        U256::from(value).wrapping_neg()
+    }
+
+    #[test]
+    fn computes_signed_and_modular_arithmetic() {
+        let minus_eight = format!("7f{}f8", "ff".repeat(31));
+        let max = format!("7f{}", "ff".repeat(32));
+        // PUSH1 0x03, PUSH32 -8, SDIV
+        assert_eq!(minus(2), run(&format!("6003{minus_eight}05")));
+        // PUSH1 0x03, PUSH32 -8, SMOD
+        assert_eq!(minus(2), run(&format!("6003{minus_eight}07")));
+        // PUSH1 0x03, PUSH1 0x08, MOD
+        assert_eq!(U256::from(2), run("6003600806"));
+        // PUSH1 0x03, PUSH1 0x02, PUSH32 MAX, ADDMOD
+        assert_eq!(U256::from(2), run(&format!("60036002{max}08")));
+        // PUSH1 0x0c, PUSH32 MAX, PUSH32 MAX, MULMOD
+        assert_eq!(U256::from(9), run(&format!("600c{max}{max}09")));
+        // PUSH1 0x0a, PUSH1 0x02, EXP
+        assert_eq!(U256::from(1024), run("600a60020a"));
+        // PUSH1 0xff, PUSH1 0x00, SIGNEXTEND
+        assert_eq!(U256::MAX, run("60ff60000b"));
+        // PUSH1 0x7f, PUSH1 0x00, SIGNEXTEND
+        assert_eq!(U256::from(0x7f), run("607f60000b"));
+    }
+}
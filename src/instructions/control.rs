@@ -1,4 +1,7 @@
-use crate::{utils::ToUsize, Interpreter};
+use alloy_primitives::U256;
+use eyre::anyhow;
+
+use crate::{constants::STACK_SIZE, utils::ToUsize, Interpreter};
 
 use super::InstructionResult;
 
@@ -9,11 +12,15 @@ pub fn stop(interpreter: &mut Interpreter) -> InstructionResult {
 
 pub fn jump(interpreter: &mut Interpreter) -> InstructionResult {
     let addr = interpreter.stack.pop()?;
-    if interpreter.contract.is_valid_jump(addr.as_usize()?) {
-        // We set `ip` here for the sake of explicitness. The alternative is
-        // to return the difference between `ip` and `addr`.
-        interpreter.instruction_pointer.set(addr);
+    if !interpreter
+        .contract
+        .is_valid_jump(addr.as_usize_saturated())
+    {
+        return Err(anyhow!("invalid jump"));
     }
+    // We set `ip` here for the sake of explicitness. The alternative is
+    // to return the difference between `ip` and `addr`.
+    interpreter.instruction_pointer = addr.as_usize_saturated();
     Ok(0)
 }
 
@@ -21,12 +28,15 @@ pub fn jumpi(interpreter: &mut Interpreter) -> InstructionResult {
     let addr = interpreter.stack.pop()?;
     let value = interpreter.stack.pop()?;
     if !value.is_zero() {
-        let addr_usize = addr.as_usize()?;
-        if interpreter.contract.is_valid_jump(addr_usize) {
-            // We set `ip` here for the sake of explicitness. The alternative is
-            // to return the difference between `ip` and `addr`.
-            interpreter.instruction_pointer.set(addr);
+        if !interpreter
+            .contract
+            .is_valid_jump(addr.as_usize_saturated())
+        {
+            return Err(anyhow!("invalid jump"));
         }
+        // We set `ip` here for the sake of explicitness. The alternative is
+        // to return the difference between `ip` and `addr`.
+        interpreter.instruction_pointer = addr.as_usize_saturated();
         Ok(0)
     } else {
         Ok(1)
@@ -36,7 +46,7 @@ pub fn jumpi(interpreter: &mut Interpreter) -> InstructionResult {
 pub fn pc(interpreter: &mut Interpreter) -> InstructionResult {
     interpreter
         .stack
-        .push(interpreter.instruction_pointer.get())?;
+        .push(U256::from(interpreter.instruction_pointer))?;
     Ok(1)
 }
 
@@ -45,13 +55,10 @@ pub fn jumpdest(_: &mut Interpreter) -> InstructionResult {
 }
 
 pub fn ret(interpreter: &mut Interpreter) -> InstructionResult {
-    let addr = interpreter.stack.pop()?.as_usize()?;
-    let length = interpreter.stack.pop()?.as_usize()?;
+    let addr = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let (addr, length) = interpreter.resize_memory_range(addr, length)?;
     if length != 0 {
-        let size = addr + length;
-        if size > interpreter.memory.len() {
-            interpreter.memory.resize(size);
-        }
         let bytes = interpreter.memory.slice(addr, length).to_vec();
         *interpreter.return_data_buffer.borrow_mut() = bytes.into();
     }
@@ -59,12 +66,122 @@ pub fn ret(interpreter: &mut Interpreter) -> InstructionResult {
     Ok(0)
 }
 
-pub fn invalid(interpreter: &mut Interpreter) -> InstructionResult {
-    interpreter.stop();
+pub fn revert(interpreter: &mut Interpreter) -> InstructionResult {
+    let addr = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let (addr, length) = interpreter.resize_memory_range(addr, length)?;
+    if length != 0 {
+        let bytes = interpreter.memory.slice(addr, length).to_vec();
+        *interpreter.return_data_buffer.borrow_mut() = bytes.into();
+    }
+    interpreter.revert();
     Ok(0)
 }
 
-pub fn unknown(interpreter: &mut Interpreter) -> InstructionResult {
-    interpreter.stop();
+pub fn invalid(_: &mut Interpreter) -> InstructionResult {
+    Err(anyhow!("invalid opcode"))
+}
+
+pub fn unknown(_: &mut Interpreter) -> InstructionResult {
+    Err(anyhow!("unknown opcode"))
+}
+
+/// Jumps by the signed offset in its immediate, relative to the next
+/// instruction (EIP-4200).
+pub fn rjump(interpreter: &mut Interpreter) -> InstructionResult {
+    let ip = interpreter.instruction_pointer;
+    let offset = read_i16(interpreter, ip + 1);
+    relative_jump(interpreter, ip + 3, offset)
+}
+
+pub fn rjumpi(interpreter: &mut Interpreter) -> InstructionResult {
+    let condition = interpreter.stack.pop()?;
+    if condition.is_zero() {
+        return Ok(3);
+    }
+    rjump(interpreter)
+}
+
+/// Jumps by the offset at index `case` of its jump table, or falls through
+/// if `case` is out of bounds.
+pub fn rjumpv(interpreter: &mut Interpreter) -> InstructionResult {
+    let case = interpreter.stack.pop()?;
+    let ip = interpreter.instruction_pointer;
+    let max_index = interpreter.contract.bytecode.bytes[ip + 1] as usize;
+    let next = ip + 2 + 2 * (max_index + 1);
+    let case = case.as_usize_saturated();
+    if case > max_index {
+        return Ok(next - ip);
+    }
+    let offset = read_i16(interpreter, ip + 2 + 2 * case);
+    relative_jump(interpreter, next, offset)
+}
+
+/// Calls the code section in its immediate, which returns with `RETF`
+/// (EIP-4750).
+pub fn callf(interpreter: &mut Interpreter) -> InstructionResult {
+    let ip = interpreter.instruction_pointer;
+    if interpreter.return_stack.len() >= RETURN_STACK_LIMIT {
+        return Err(anyhow!("return stack overflow"));
+    }
+    interpreter
+        .return_stack
+        .push((interpreter.code_section, ip + 3));
+    enter_section(interpreter, ip)
+}
+
+pub fn retf(interpreter: &mut Interpreter) -> InstructionResult {
+    let (section, ip) = interpreter
+        .return_stack
+        .pop()
+        .ok_or_else(|| anyhow!("return stack underflow"))?;
+    interpreter.code_section = section;
+    interpreter.instruction_pointer = ip;
+    Ok(0)
+}
+
+/// Jumps to the code section in its immediate, which returns to the caller
+/// of the current one (EIP-6206).
+pub fn jumpf(interpreter: &mut Interpreter) -> InstructionResult {
+    let ip = interpreter.instruction_pointer;
+    enter_section(interpreter, ip)
+}
+
+/// The most `CALLF`s that can be in progress.
+const RETURN_STACK_LIMIT: usize = 1024;
+
+/// Moves to the start of the code section in the immediate of the
+/// instruction at `ip`, if the stack has room for it.
+fn enter_section(interpreter: &mut Interpreter, ip: usize) -> InstructionResult {
+    let section = read_u16(interpreter, ip + 1) as usize;
+    let eof = interpreter
+        .contract
+        .bytecode
+        .eof
+        .as_ref()
+        .ok_or_else(|| anyhow!("unknown opcode"))?;
+    let ty = eof.types[section];
+    let offset = eof.code_offsets[section];
+    let growth = (ty.max_stack_height - ty.inputs as u16) as usize;
+    if interpreter.stack.len() + growth > STACK_SIZE {
+        return Err(anyhow!("stack overflow"));
+    }
+    interpreter.code_section = section;
+    interpreter.instruction_pointer = offset;
     Ok(0)
 }
+
+fn relative_jump(interpreter: &mut Interpreter, from: usize, offset: i16) -> InstructionResult {
+    let target = from.wrapping_add_signed(offset as isize);
+    interpreter.instruction_pointer = target;
+    Ok(0)
+}
+
+fn read_u16(interpreter: &Interpreter, at: usize) -> u16 {
+    let bytes = &interpreter.contract.bytecode.bytes;
+    u16::from_be_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_i16(interpreter: &Interpreter, at: usize) -> i16 {
+    read_u16(interpreter, at) as i16
+}
@@ -0,0 +1,63 @@
+//! Reading the data section of EOF containers (EIP-7480).
+
+use alloy_primitives::{Bytes, U256};
+use eyre::anyhow;
+
+use crate::{utils::ToUsize, Interpreter};
+
+use super::{system::copy_to_memory, InstructionResult};
+
+pub fn dataload(interpreter: &mut Interpreter) -> InstructionResult {
+    let offset = interpreter.stack.pop()?;
+    let data = data(interpreter)?;
+    interpreter
+        .stack
+        .push(load_word(&data, offset.as_usize_saturated()))?;
+    Ok(1)
+}
+
+/// Loads the word at the offset in its immediate, which validation ensures
+/// is within the data section.
+pub fn dataloadn(interpreter: &mut Interpreter) -> InstructionResult {
+    let ip = interpreter.instruction_pointer;
+    let bytes = &interpreter.contract.bytecode.bytes;
+    let offset = u16::from_be_bytes([bytes[ip + 1], bytes[ip + 2]]) as usize;
+    let data = data(interpreter)?;
+    interpreter.stack.push(load_word(&data, offset))?;
+    Ok(3)
+}
+
+pub fn datasize(interpreter: &mut Interpreter) -> InstructionResult {
+    let len = data(interpreter)?.len();
+    interpreter.stack.push(U256::from(len))?;
+    Ok(1)
+}
+
+pub fn datacopy(interpreter: &mut Interpreter) -> InstructionResult {
+    let mem_offset = interpreter.stack.pop()?;
+    let data_offset = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let data = data(interpreter)?;
+    copy_to_memory(interpreter, mem_offset, &data, data_offset, length)?;
+    Ok(1)
+}
+
+fn data(interpreter: &Interpreter) -> eyre::Result<Bytes> {
+    interpreter
+        .contract
+        .bytecode
+        .eof
+        .as_ref()
+        .map(|eof| eof.data.clone())
+        .ok_or_else(|| anyhow!("unknown opcode"))
+}
+
+/// The word at `offset`, padded with zeros past the end of `data`.
+fn load_word(data: &[u8], offset: usize) -> U256 {
+    let mut word = [0u8; 32];
+    if offset < data.len() {
+        let len = 32.min(data.len() - offset);
+        word[..len].copy_from_slice(&data[offset..offset + len]);
+    }
+    U256::from_be_bytes(word)
+}
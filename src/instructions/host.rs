@@ -0,0 +1,816 @@
+use alloy_primitives::{Address, Bytes, B256, U256};
+use eyre::anyhow;
+
+use crate::{
+    bytecode,
+    frame::{self, CallInputs, CallKind, ExitStatus, CALL_STACK_LIMIT, MAX_INITCODE_SIZE},
+    gas,
+    spec::SpecId,
+    state::Log,
+    Contract, Interpreter,
+};
+
+use super::{system, InstructionResult};
+
+/// Charges for accessing `address`, warming it up (EIP-2929). Before Berlin,
+/// the cost only depends on the current opcode.
+pub(crate) fn charge_account_access(
+    interpreter: &mut Interpreter,
+    address: Address,
+) -> eyre::Result<()> {
+    let spec = interpreter.env.cfg.spec;
+    let cost = if !spec.is_enabled_in(SpecId::Berlin) {
+        gas::account_access_cost(interpreter.opcode(), spec)
+    } else if interpreter.journal.warm_address(address) {
+        gas::COLD_ACCOUNT_ACCESS
+    } else {
+        gas::WARM_STORAGE_READ
+    };
+    interpreter.gas.record_cost(cost)
+}
+
+fn check_not_static(interpreter: &Interpreter) -> eyre::Result<()> {
+    if interpreter.is_static {
+        return Err(anyhow!("state change during static call"));
+    }
+    Ok(())
+}
+
+pub fn balance(interpreter: &mut Interpreter) -> InstructionResult {
+    let address = Address::from_word(interpreter.stack.pop()?.into());
+    charge_account_access(interpreter, address)?;
+    let balance = interpreter.journal.balance(&address);
+    interpreter.stack.push(balance)?;
+    Ok(1)
+}
+
+/// Delegated accounts (EIP-7702) report the size of their designator, not
+/// of the code they delegate to. The same goes for the other `EXTCODE*`s.
+pub fn extcodesize(interpreter: &mut Interpreter) -> InstructionResult {
+    let address = Address::from_word(interpreter.stack.pop()?.into());
+    charge_account_access(interpreter, address)?;
+    let size = interpreter.journal.code(&address).len();
+    interpreter.stack.push(U256::from(size))?;
+    Ok(1)
+}
+
+pub fn extcodecopy(interpreter: &mut Interpreter) -> InstructionResult {
+    let address = Address::from_word(interpreter.stack.pop()?.into());
+    let mem_offset = interpreter.stack.pop()?;
+    let code_offset = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    charge_account_access(interpreter, address)?;
+    let code = interpreter.journal.code(&address);
+    system::copy_to_memory(interpreter, mem_offset, &code, code_offset, length)?;
+    Ok(1)
+}
+
+/// Empty accounts hash to zero rather than to the hash of empty code.
+pub fn extcodehash(interpreter: &mut Interpreter) -> InstructionResult {
+    let address = Address::from_word(interpreter.stack.pop()?.into());
+    charge_account_access(interpreter, address)?;
+    let hash = match interpreter.journal.account(&address) {
+        Some(account) if !account.is_empty() => account.code_hash(),
+        _ => B256::ZERO,
+    };
+    interpreter.stack.push(hash.into())?;
+    Ok(1)
+}
+
+pub fn selfbalance(interpreter: &mut Interpreter) -> InstructionResult {
+    let balance = interpreter.journal.balance(&interpreter.contract.address);
+    interpreter.stack.push(balance)?;
+    Ok(1)
+}
+
+pub fn sload(interpreter: &mut Interpreter) -> InstructionResult {
+    let key = interpreter.stack.pop()?;
+    let address = interpreter.contract.address;
+    let spec = interpreter.env.cfg.spec;
+    let cost = if !spec.is_enabled_in(SpecId::Berlin) {
+        gas::sload_cost(spec)
+    } else if interpreter.journal.warm_slot(address, key) {
+        gas::COLD_SLOAD
+    } else {
+        gas::WARM_STORAGE_READ
+    };
+    interpreter.gas.record_cost(cost)?;
+    let value = interpreter
+        .journal
+        .sload(&interpreter.contract.address, key);
+    interpreter.stack.push(value)?;
+    Ok(1)
+}
+
+pub fn sstore(interpreter: &mut Interpreter) -> InstructionResult {
+    check_not_static(interpreter)?;
+    let key = interpreter.stack.pop()?;
+    let value = interpreter.stack.pop()?;
+    let spec = interpreter.env.cfg.spec;
+    if spec.is_enabled_in(SpecId::Istanbul) && interpreter.gas.remaining <= gas::SSTORE_SENTRY {
+        return Err(anyhow!("out of gas"));
+    }
+
+    let address = interpreter.contract.address;
+    let original = interpreter.journal.original_storage(&address, key);
+    let current = interpreter.journal.sload(&address, key);
+    let mut cost = gas::sstore_cost(original, current, value, spec);
+    if spec.is_enabled_in(SpecId::Berlin) && interpreter.journal.warm_slot(address, key) {
+        cost += gas::COLD_SLOAD;
+    }
+    interpreter.gas.record_cost(cost)?;
+    interpreter
+        .gas
+        .record_refund(gas::sstore_refund(original, current, value, spec));
+    interpreter.journal.sstore(address, key, value);
+    Ok(1)
+}
+
+pub fn tload(interpreter: &mut Interpreter) -> InstructionResult {
+    let key = interpreter.stack.pop()?;
+    let value = interpreter
+        .journal
+        .tload(&interpreter.contract.address, key);
+    interpreter.stack.push(value)?;
+    Ok(1)
+}
+
+pub fn tstore(interpreter: &mut Interpreter) -> InstructionResult {
+    check_not_static(interpreter)?;
+    let key = interpreter.stack.pop()?;
+    let value = interpreter.stack.pop()?;
+    interpreter
+        .journal
+        .tstore(interpreter.contract.address, key, value);
+    Ok(1)
+}
+
+pub fn log<const N: usize>(interpreter: &mut Interpreter) -> InstructionResult {
+    check_not_static(interpreter)?;
+    let addr = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let mut topics = Vec::with_capacity(N);
+    for _ in 0..N {
+        topics.push(B256::from(interpreter.stack.pop()?));
+    }
+
+    let (addr, length) = interpreter.resize_memory_range(addr, length)?;
+    interpreter
+        .gas
+        .record_cost(gas::LOGDATA.saturating_mul(length as u64))?;
+    let data = Bytes::copy_from_slice(interpreter.memory.slice(addr, length));
+
+    interpreter.journal.log(Log {
+        address: interpreter.contract.address,
+        topics,
+        data,
+    });
+    Ok(1)
+}
+
+/// Deploys a contract with the initcode in memory. `CREATE2` derives the
+/// address from a salt and the initcode rather than from the creator's nonce
+/// (EIP-1014).
+pub fn create<const IS_CREATE2: bool>(interpreter: &mut Interpreter) -> InstructionResult {
+    check_not_static(interpreter)?;
+    let value = interpreter.stack.pop()?;
+    let offset = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let salt = if IS_CREATE2 {
+        Some(B256::from(interpreter.stack.pop()?))
+    } else {
+        None
+    };
+
+    let (offset, length) = interpreter.resize_memory_range(offset, length)?;
+    if interpreter.env.cfg.spec.is_enabled_in(SpecId::Shanghai) {
+        // EIP-3860.
+        if length > MAX_INITCODE_SIZE {
+            return Err(anyhow!("initcode size exceeded"));
+        }
+        let cost = gas::INITCODE_WORD * gas::num_words(length as u64);
+        interpreter.gas.record_cost(cost)?;
+    }
+    if IS_CREATE2 {
+        interpreter
+            .gas
+            .record_cost(gas::keccak256_cost(length as u64))?;
+    }
+    let initcode = Bytes::copy_from_slice(interpreter.memory.slice(offset, length));
+    interpreter.return_data = Bytes::new();
+
+    // These failures leave the creator's nonce and gas untouched.
+    let creator = interpreter.contract.address;
+    let nonce = interpreter.journal.nonce(&creator);
+    if interpreter.depth + 1 > CALL_STACK_LIMIT
+        || interpreter.journal.balance(&creator) < value
+        || nonce == u64::MAX
+    {
+        interpreter.stack.push(U256::ZERO)?;
+        return Ok(1);
+    }
+
+    let address = match salt {
+        Some(salt) => creator.create2_from_code(salt, &initcode),
+        None => creator.create(nonce),
+    };
+    interpreter.journal.increment_nonce(creator)?;
+    interpreter.journal.warm_address(address);
+
+    // All but one 64th of the remaining gas goes to the initcode (EIP-150).
+    let remaining = interpreter.gas.remaining;
+    let gas_limit = if interpreter.env.cfg.spec.is_enabled_in(SpecId::Tangerine) {
+        remaining - remaining / 64
+    } else {
+        remaining
+    };
+    interpreter.gas.record_cost(gas_limit)?;
+
+    let mut contract = Contract::new(address, initcode, Bytes::new());
+    contract.caller = creator;
+    contract.value = value;
+    let env = interpreter.env.clone();
+    let depth = interpreter.depth + 1;
+    let result = frame::create(&mut interpreter.journal, env, contract, gas_limit, depth);
+
+    interpreter.gas.remaining += result.gas.remaining;
+    if result.status == ExitStatus::Success {
+        interpreter.gas.record_refund(result.gas.refunded);
+    }
+    // Only a reverted initcode leaves return data.
+    interpreter.return_data = result.output;
+    let address = result.address.map_or(U256::ZERO, |a| a.into_word().into());
+    interpreter.stack.push(address)?;
+    Ok(1)
+}
+
+/// Whether sending `value` to `address` brings a new account into the state.
+/// Before EIP-161 any message does so for a missing account. Since then,
+/// only sending value to an empty account does.
+fn creates_account(interpreter: &Interpreter, address: Address, value: U256) -> bool {
+    if interpreter
+        .env
+        .cfg
+        .spec
+        .is_enabled_in(SpecId::SpuriousDragon)
+    {
+        !value.is_zero()
+            && interpreter
+                .journal
+                .account(&address)
+                .is_none_or(|a| a.is_empty())
+    } else {
+        !interpreter.journal.exists(&address)
+    }
+}
+
+pub fn call(interpreter: &mut Interpreter) -> InstructionResult {
+    call_inner(interpreter, CallKind::Call)
+}
+
+pub fn call_code(interpreter: &mut Interpreter) -> InstructionResult {
+    call_inner(interpreter, CallKind::CallCode)
+}
+
+pub fn delegate_call(interpreter: &mut Interpreter) -> InstructionResult {
+    call_inner(interpreter, CallKind::DelegateCall)
+}
+
+pub fn static_call(interpreter: &mut Interpreter) -> InstructionResult {
+    call_inner(interpreter, CallKind::StaticCall)
+}
+
+fn call_inner(interpreter: &mut Interpreter, kind: CallKind) -> InstructionResult {
+    let requested_gas = interpreter.stack.pop()?;
+    let to = Address::from_word(interpreter.stack.pop()?.into());
+    let value = if kind.transfers_value() {
+        interpreter.stack.pop()?
+    } else {
+        U256::ZERO
+    };
+    let in_offset = interpreter.stack.pop()?;
+    let in_length = interpreter.stack.pop()?;
+    let out_offset = interpreter.stack.pop()?;
+    let out_length = interpreter.stack.pop()?;
+
+    if kind == CallKind::Call && !value.is_zero() {
+        check_not_static(interpreter)?;
+    }
+
+    let (in_offset, in_length) = interpreter.resize_memory_range(in_offset, in_length)?;
+    let (out_offset, out_length) = interpreter.resize_memory_range(out_offset, out_length)?;
+    let input = Bytes::copy_from_slice(interpreter.memory.slice(in_offset, in_length));
+
+    charge_account_access(interpreter, to)?;
+    if interpreter.env.cfg.spec.is_enabled_in(SpecId::Prague) {
+        // Loading the code of a delegated account costs an extra access.
+        if let Some(target) = bytecode::delegation(&interpreter.journal.code(&to)) {
+            charge_account_access(interpreter, target)?;
+        }
+    }
+    let spec = interpreter.env.cfg.spec;
+    if kind == CallKind::Call && creates_account(interpreter, to, value) {
+        interpreter.gas.record_cost(gas::NEWACCOUNT)?;
+    }
+    if !value.is_zero() {
+        interpreter.gas.record_cost(gas::CALLVALUE)?;
+    }
+
+    // All but one 64th of the remaining gas can be forwarded (EIP-150).
+    // Before that, asking for more gas than is left runs out of gas.
+    let mut gas_limit = u64::try_from(requested_gas).unwrap_or(u64::MAX);
+    if spec.is_enabled_in(SpecId::Tangerine) {
+        let remaining = interpreter.gas.remaining;
+        gas_limit = gas_limit.min(remaining - remaining / 64);
+    }
+    interpreter.gas.record_cost(gas_limit)?;
+    if !value.is_zero() {
+        gas_limit += gas::CALL_STIPEND;
+    }
+
+    let this = &interpreter.contract;
+    let (address, caller, value) = match kind {
+        CallKind::Call | CallKind::StaticCall => (to, this.address, value),
+        CallKind::CallCode => (this.address, this.address, value),
+        CallKind::DelegateCall => (this.address, this.caller, this.value),
+    };
+    let mut contract = Contract::new(address, Bytes::new(), input);
+    contract.caller = caller;
+    contract.value = value;
+    let inputs = CallInputs {
+        contract,
+        code_address: to,
+        gas_limit,
+        kind,
+        is_static: interpreter.is_static || kind == CallKind::StaticCall,
+        depth: interpreter.depth + 1,
+    };
+    let result = frame::call(&mut interpreter.journal, interpreter.env.clone(), inputs);
+
+    interpreter.gas.remaining += result.gas.remaining;
+    let success = result.status == ExitStatus::Success;
+    if success {
+        interpreter.gas.record_refund(result.gas.refunded);
+    }
+
+    let length = out_length.min(result.output.len());
+    interpreter.memory.set(out_offset, &result.output[..length]);
+    interpreter.return_data = result.output;
+    interpreter.stack.push(U256::from(success))?;
+    Ok(1)
+}
+
+/// Sends the whole balance to the target and halts. The account is deleted
+/// at the end of the transaction, though since Cancun only if it was created
+/// in the same transaction (EIP-6780).
+pub fn selfdestruct(interpreter: &mut Interpreter) -> InstructionResult {
+    check_not_static(interpreter)?;
+    let target = Address::from_word(interpreter.stack.pop()?.into());
+    let spec = interpreter.env.cfg.spec;
+    if spec.is_enabled_in(SpecId::Tangerine) {
+        interpreter.gas.record_cost(gas::SELFDESTRUCT)?;
+    }
+    if spec.is_enabled_in(SpecId::Berlin) && interpreter.journal.warm_address(target) {
+        interpreter.gas.record_cost(gas::COLD_ACCOUNT_ACCESS)?;
+    }
+
+    let address = interpreter.contract.address;
+    let balance = interpreter.journal.balance(&address);
+    // Creating the target was free before EIP-150.
+    if spec.is_enabled_in(SpecId::Tangerine) && creates_account(interpreter, target, balance) {
+        interpreter.gas.record_cost(gas::NEWACCOUNT)?;
+    }
+
+    if target != address {
+        interpreter.journal.transfer(address, target, balance)?;
+    }
+    if !spec.is_enabled_in(SpecId::Cancun) || interpreter.journal.is_created(&address) {
+        let destroyed = interpreter.journal.selfdestruct(address);
+        // EIP-3529 removed the refund.
+        if !destroyed && !spec.is_enabled_in(SpecId::London) {
+            interpreter.gas.record_refund(gas::SELFDESTRUCT_REFUND);
+        }
+    }
+    interpreter.stop();
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use alloy_primitives::{Address, Bytes, B256, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        bytecode,
+        env::Env,
+        gas::Gas,
+        precompiles::{CustomPrecompile, PrecompileContext},
+        spec::SpecId,
+        state::{Account, State},
+        Contract, Interpreter, JournaledState,
+    };
+
+    fn interpreter(bytecode: &str) -> Interpreter {
+        let bytes = bytecode.parse().unwrap();
+        let address = Address::with_last_byte(0xAA);
+        let contract = Box::new(Contract::new(address, bytes, Default::default()));
+        Interpreter::new(contract)
+    }
+
+    #[test]
+    fn stores_and_logs() {
+        // PUSH1 0x2a
+        // PUSH1 0x01
+        // SSTORE
+        // PUSH1 0x07
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // LOG1
+        // STOP
+        let mut interpreter = interpreter("0x602a600155600760006000a100");
+        interpreter.execute().expect("should finish execution");
+        let address = Address::with_last_byte(0xAA);
+        assert_eq!(
+            U256::from(0x2a),
+            interpreter.journal.sload(&address, U256::from(1))
+        );
+        assert_eq!(1, interpreter.journal.logs.len());
+        assert_eq!(
+            vec![B256::from(U256::from(7))],
+            interpreter.journal.logs[0].topics
+        );
+    }
+
+    #[test]
+    fn reverts_state_changes() {
+        // PUSH1 0x2a
+        // PUSH1 0x01
+        // SSTORE
+        // PUSH1 0x2a
+        // PUSH1 0x01
+        // TSTORE
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // LOG0
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // REVERT
+        let mut interpreter = interpreter("0x602a600155602a60015d60006000a060006000fd");
+        interpreter.execute().expect("should finish execution");
+        let address = Address::with_last_byte(0xAA);
+        assert_eq!(true, interpreter.reverted.get());
+        assert_eq!(
+            U256::ZERO,
+            interpreter.journal.sload(&address, U256::from(1))
+        );
+        assert_eq!(
+            U256::ZERO,
+            interpreter.journal.tload(&address, U256::from(1))
+        );
+        assert_eq!(true, interpreter.journal.logs.is_empty());
+        assert_eq!(false, interpreter.journal.exists(&address));
+    }
+
+    #[test]
+    fn calls_precompiles() {
+        // PUSH1 0x2a
+        // PUSH1 0x00
+        // MSTORE8
+        // PUSH1 0x20
+        // PUSH1 0x00
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // PUSH1 0x02
+        // GAS
+        // STATICCALL
+        // PUSH1 0x20
+        // PUSH1 0x00
+        // RETURN
+        let mut interpreter = interpreter("0x602a600053602060006001600060025afa60206000f3");
+        let result = interpreter.execute().expect("should finish execution");
+        let expected: Bytes = "0x684888c0ebb17f374298b65ee2807526c066094c701bcc7ebbe1c1095f494fc1"
+            .parse()
+            .unwrap();
+        assert_eq!(expected, result);
+        assert_eq!(expected, interpreter.return_data);
+    }
+
+    #[test]
+    fn reverts_sub_call() {
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // SSTORE
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // REVERT
+        let callee = Address::with_last_byte(0xBB);
+        let mut state = State::new();
+        state.insert(
+            callee,
+            Account {
+                code: "0x600160005560006000fd".parse().unwrap(),
+                ..Default::default()
+            },
+        );
+
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0xbb
+        // GAS
+        // CALL
+        // PUSH1 0x01
+        // ADD
+        // PUSH1 0x00
+        // SSTORE
+        // STOP
+        let bytes = "0x6000600060006000600060bb5af16001016000555b00"
+            .parse()
+            .unwrap();
+        let address = Address::with_last_byte(0xAA);
+        let contract = Box::new(Contract::new(address, bytes, Default::default()));
+        let mut interpreter = Interpreter::with_journal(contract, JournaledState::new(state));
+        interpreter.execute().expect("should finish execution");
+
+        // The call failed, so the caller stored `0 + 1`.
+        assert_eq!(
+            U256::from(1),
+            interpreter.journal.sload(&address, U256::ZERO)
+        );
+        assert_eq!(U256::ZERO, interpreter.journal.sload(&callee, U256::ZERO));
+        assert_eq!(
+            false,
+            interpreter
+                .journal
+                .warm_slots
+                .contains(&(callee, U256::ZERO))
+        );
+        assert_eq!(true, interpreter.journal.warm_addresses.contains(&callee));
+    }
+
+    #[test]
+    fn follows_delegations() {
+        let delegated = Address::with_last_byte(0xBB);
+        let target = Address::with_last_byte(0xCC);
+        let mut state = State::new();
+        state.insert(
+            delegated,
+            Account {
+                code: bytecode::delegation_designator(target),
+                ..Default::default()
+            },
+        );
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // SSTORE
+        // STOP
+        state.insert(
+            target,
+            Account {
+                code: "0x600160005500".parse().unwrap(),
+                ..Default::default()
+            },
+        );
+
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0xbb
+        // GAS
+        // CALL
+        // POP
+        // PUSH1 0xbb
+        // EXTCODESIZE
+        // PUSH1 0x01
+        // SSTORE
+        // STOP
+        let bytes = "0x6000600060006000600060bb5af15060bb3b60015500"
+            .parse()
+            .unwrap();
+        let address = Address::with_last_byte(0xAA);
+        let contract = Box::new(Contract::new(address, bytes, Default::default()));
+        let mut interpreter = Interpreter::with_journal(contract, JournaledState::new(state));
+        let mut env = Env::default();
+        env.cfg.spec = SpecId::Prague;
+        interpreter.env = Rc::new(env);
+        interpreter.execute().expect("should finish execution");
+
+        // The code ran in the storage of the delegated account, while
+        // EXTCODESIZE saw the designator.
+        assert_eq!(
+            U256::from(1),
+            interpreter.journal.sload(&delegated, U256::ZERO)
+        );
+        assert_eq!(U256::ZERO, interpreter.journal.sload(&target, U256::ZERO));
+        assert_eq!(
+            U256::from(23),
+            interpreter.journal.sload(&address, U256::from(1))
+        );
+        assert_eq!(true, interpreter.journal.warm_addresses.contains(&target));
+    }
+
+    #[test]
+    fn creates_contracts() {
+        // PUSH10 initcode, deploying 0x2a:
+        //   PUSH1 0x2a
+        //   PUSH1 0x00
+        //   MSTORE8
+        //   PUSH1 0x01
+        //   PUSH1 0x00
+        //   RETURN
+        // PUSH1 0x00
+        // MSTORE
+        // PUSH1 0x0a
+        // PUSH1 0x16
+        // PUSH1 0x00
+        // CREATE
+        // PUSH1 0x00
+        // SSTORE
+        // PUSH1 0x01
+        // PUSH1 0x0a
+        // PUSH1 0x16
+        // PUSH1 0x00
+        // CREATE2
+        // PUSH1 0x01
+        // SSTORE
+        // STOP
+        let mut interpreter = interpreter(
+            "0x69602a60005360016000f3600052600a60166000f0600055\
+             6001600a60166000f560015500",
+        );
+        interpreter.execute().expect("should finish execution");
+
+        let creator = Address::with_last_byte(0xAA);
+        let initcode: Bytes = "0x602a60005360016000f3".parse().unwrap();
+        let created = creator.create(0);
+        let created2 = creator.create2_from_code(B256::from(U256::from(1)), &initcode);
+        assert_eq!(
+            U256::from_be_bytes(created.into_word().0),
+            interpreter.journal.sload(&creator, U256::ZERO)
+        );
+        assert_eq!(
+            U256::from_be_bytes(created2.into_word().0),
+            interpreter.journal.sload(&creator, U256::from(1))
+        );
+        assert_eq!(
+            Bytes::from_static(&[0x2a]),
+            interpreter.journal.code(&created)
+        );
+        assert_eq!(
+            Bytes::from_static(&[0x2a]),
+            interpreter.journal.code(&created2)
+        );
+        assert_eq!(2, interpreter.journal.nonce(&creator));
+        assert_eq!(1, interpreter.journal.nonce(&created));
+    }
+
+    #[test]
+    fn selfdestructs() {
+        let address = Address::with_last_byte(0xAA);
+        let beneficiary = Address::with_last_byte(0xBB);
+        let run = |spec| {
+            let mut state = State::new();
+            // PUSH1 0xbb
+            // SELFDESTRUCT
+            state.insert(
+                address,
+                Account {
+                    balance: U256::from(10),
+                    code: "0x60bbff".parse().unwrap(),
+                    ..Default::default()
+                },
+            );
+            let code = "0x60bbff".parse().unwrap();
+            let contract = Box::new(Contract::new(address, code, Default::default()));
+            let mut interpreter = Interpreter::with_journal(contract, JournaledState::new(state));
+            let mut env = Env::default();
+            env.cfg.spec = spec;
+            interpreter.env = Rc::new(env);
+            interpreter.execute().expect("should finish execution");
+            interpreter.journal.remove_selfdestructed();
+            interpreter.journal
+        };
+
+        let journal = run(SpecId::Shanghai);
+        assert_eq!(U256::from(10), journal.balance(&beneficiary));
+        assert_eq!(false, journal.exists(&address));
+
+        // Only contracts created in the same transaction are deleted.
+        let journal = run(SpecId::Cancun);
+        assert_eq!(U256::from(10), journal.balance(&beneficiary));
+        assert_eq!(U256::ZERO, journal.balance(&address));
+        assert_eq!(true, journal.exists(&address));
+    }
+
+    #[test]
+    fn prices_state_access_by_fork() {
+        // PUSH1 0xbb
+        // BALANCE
+        // POP
+        // PUSH1 0x00
+        // SLOAD
+        // POP
+        // STOP
+        let gas_spent = |spec| {
+            let mut interpreter = interpreter("0x60bb31506000545000");
+            let mut env = Env::default();
+            env.cfg.spec = spec;
+            interpreter.env = Rc::new(env);
+            interpreter.execute().expect("should finish execution");
+            interpreter.gas.spent()
+        };
+        assert_eq!(3 + 20 + 2 + 3 + 50 + 2, gas_spent(SpecId::Homestead));
+        assert_eq!(3 + 700 + 2 + 3 + 800 + 2, gas_spent(SpecId::Istanbul));
+        assert_eq!(3 + 2600 + 2 + 3 + 2100 + 2, gas_spent(SpecId::Cancun));
+    }
+
+    #[test]
+    fn forwards_all_requested_gas_before_tangerine() {
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0xbb
+        // PUSH2 0xffff
+        // CALL
+        // STOP
+        let run = |spec| {
+            let bytes = "0x6000600060006000600060bb61fffff100".parse().unwrap();
+            let contract = Box::new(Contract::new(Address::ZERO, bytes, Default::default()));
+            let mut interpreter = Interpreter::new(contract);
+            interpreter.gas = Gas::new(50000);
+            let mut env = Env::default();
+            env.cfg.spec = spec;
+            interpreter.env = Rc::new(env);
+            interpreter.execute().map(|_| interpreter.gas.spent())
+        };
+        // Asking for more gas than is left was an error.
+        let err = run(SpecId::Homestead).unwrap_err();
+        assert_eq!("out of gas", err.to_string());
+        assert_eq!(3 * 6 + 3 + 2600, run(SpecId::Cancun).unwrap());
+    }
+
+    #[test]
+    fn forbids_state_changes_in_static_calls() {
+        let mut interpreter = interpreter("0x600160005500");
+        interpreter.is_static = true;
+        let err = interpreter.execute().unwrap_err();
+        assert_eq!("state change during static call", err.to_string());
+    }
+
+    /// Counts its calls in its own storage and returns its caller.
+    #[derive(Debug)]
+    struct Counter;
+
+    impl CustomPrecompile for Counter {
+        fn gas(&self, _: &[u8]) -> u64 {
+            100
+        }
+
+        fn run(&self, context: &mut PrecompileContext<'_>, _: &[u8]) -> eyre::Result<Bytes> {
+            let count = context.journal.sload(&context.address, U256::ZERO);
+            context
+                .journal
+                .sstore(context.address, U256::ZERO, count + U256::from(1));
+            Ok(Bytes::copy_from_slice(
+                context.caller.into_word().as_slice(),
+            ))
+        }
+    }
+
+    #[test]
+    fn calls_custom_precompiles() {
+        // PUSH1 0x20
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0x00
+        // PUSH1 0xcc
+        // GAS
+        // CALL
+        // POP
+        // PUSH1 0x20
+        // PUSH1 0x00
+        // RETURN
+        let mut interpreter = interpreter("0x6020600060006000600060cc5af15060206000f3");
+        let mut env = Env::default();
+        let counter = Address::with_last_byte(0xCC);
+        env.cfg.precompiles.insert_custom(counter, Rc::new(Counter));
+        interpreter.env = Rc::new(env);
+        let result = interpreter.execute().expect("should finish execution");
+
+        let caller = Address::with_last_byte(0xAA);
+        assert_eq!(caller.into_word().as_slice(), result.as_ref());
+        assert_eq!(
+            U256::from(1),
+            interpreter.journal.sload(&counter, U256::ZERO)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use alloy_primitives::U256;
+
+use crate::{spec::SpecId, utils::ToUsize, Interpreter};
+
+use super::InstructionResult;
+
+pub fn origin(interpreter: &mut Interpreter) -> InstructionResult {
+    let origin = interpreter.env.tx.caller.into_word();
+    interpreter.stack.push(origin.into())?;
+    Ok(1)
+}
+
+pub fn gasprice(interpreter: &mut Interpreter) -> InstructionResult {
+    let price = interpreter.env.effective_gas_price();
+    interpreter.stack.push(price)?;
+    Ok(1)
+}
+
+/// Returns the hash of one of the 256 most recent blocks, or zero for any
+/// other block.
+pub fn blockhash(interpreter: &mut Interpreter) -> InstructionResult {
+    let number = interpreter.stack.pop()?;
+    let block = &interpreter.env.block;
+    let hash = match u64::try_from(number) {
+        Ok(number)
+            if U256::from(number) < block.number
+                && block.number - U256::from(number) <= U256::from(256) =>
+        {
+            block
+                .block_hashes
+                .get(&number)
+                .map_or(U256::ZERO, |hash| (*hash).into())
+        }
+        _ => U256::ZERO,
+    };
+    interpreter.stack.push(hash)?;
+    Ok(1)
+}
+
+pub fn coinbase(interpreter: &mut Interpreter) -> InstructionResult {
+    let coinbase = interpreter.env.block.coinbase.into_word();
+    interpreter.stack.push(coinbase.into())?;
+    Ok(1)
+}
+
+pub fn timestamp(interpreter: &mut Interpreter) -> InstructionResult {
+    interpreter.stack.push(interpreter.env.block.timestamp)?;
+    Ok(1)
+}
+
+pub fn number(interpreter: &mut Interpreter) -> InstructionResult {
+    interpreter.stack.push(interpreter.env.block.number)?;
+    Ok(1)
+}
+
+/// Returns `PREVRANDAO` after the merge (EIP-4399).
+pub fn difficulty(interpreter: &mut Interpreter) -> InstructionResult {
+    let block = &interpreter.env.block;
+    let value = match block.prevrandao {
+        Some(prevrandao) if interpreter.env.cfg.spec.is_enabled_in(SpecId::Merge) => {
+            prevrandao.into()
+        }
+        _ => block.difficulty,
+    };
+    interpreter.stack.push(value)?;
+    Ok(1)
+}
+
+pub fn gaslimit(interpreter: &mut Interpreter) -> InstructionResult {
+    interpreter.stack.push(interpreter.env.block.gas_limit)?;
+    Ok(1)
+}
+
+pub fn chainid(interpreter: &mut Interpreter) -> InstructionResult {
+    let chain_id = U256::from(interpreter.env.cfg.chain_id);
+    interpreter.stack.push(chain_id)?;
+    Ok(1)
+}
+
+pub fn basefee(interpreter: &mut Interpreter) -> InstructionResult {
+    interpreter.stack.push(interpreter.env.block.basefee)?;
+    Ok(1)
+}
+
+pub fn blob_hash(interpreter: &mut Interpreter) -> InstructionResult {
+    let index = interpreter.stack.pop()?.as_usize_saturated();
+    let hash = match interpreter.env.tx.blob_hashes.get(index) {
+        Some(hash) => (*hash).into(),
+        None => U256::ZERO,
+    };
+    interpreter.stack.push(hash)?;
+    Ok(1)
+}
+
+pub fn blob_basefee(interpreter: &mut Interpreter) -> InstructionResult {
+    let basefee = interpreter.env.block.blob_basefee.unwrap_or_default();
+    interpreter.stack.push(basefee)?;
+    Ok(1)
+}
@@ -25,13 +25,54 @@ pub fn i256_cmp(a: &U256, b: &U256) -> Ordering {
     }
 }
 
+/// The smallest signed value, `-2^255`.
+const MIN_NEGATIVE: U256 = U256::from_limbs([0, 0, 0, 0x8000000000000000]);
+
+fn i256_abs(val: U256) -> U256 {
+    match i256_sign(&val) {
+        Sign::Negative => val.wrapping_neg(),
+        Sign::Positive => val,
+    }
+}
+
+/// Signed division, rounding towards zero. Dividing by zero gives zero, and
+/// `-2^255 / -1` overflows back to `-2^255`.
+pub fn i256_div(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::ZERO;
+    } else if a == MIN_NEGATIVE && b == U256::MAX {
+        return MIN_NEGATIVE;
+    }
+
+    let quotient = i256_abs(a) / i256_abs(b);
+    if i256_sign(&a) == i256_sign(&b) {
+        quotient
+    } else {
+        quotient.wrapping_neg()
+    }
+}
+
+/// Signed remainder, taking the sign of the dividend. The remainder of a
+/// division by zero is zero.
+pub fn i256_mod(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return U256::ZERO;
+    }
+
+    let remainder = i256_abs(a) % i256_abs(b);
+    match i256_sign(&a) {
+        Sign::Negative => remainder.wrapping_neg(),
+        Sign::Positive => remainder,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{cmp::Ordering, ops::Sub};
 
     use alloy_primitives::U256;
 
-    use super::i256_cmp;
+    use super::{i256_cmp, i256_div, i256_mod, MIN_NEGATIVE};
 
     const MSB_BITMASK_U64: u64 = 0x8000000000000000;
 
@@ -63,4 +104,19 @@ mod tests {
         assert_eq!(Ordering::Greater, i256_cmp(&max_positive, &max));
         assert_eq!(Ordering::Equal, i256_cmp(&max_positive, &max_positive));
     }
+
+    #[test]
+    fn divides_i256() {
+        let minus = |value: usize| u(value).wrapping_neg();
+        assert_eq!(u(3), i256_div(u(7), u(2)));
+        assert_eq!(minus(3), i256_div(minus(7), u(2)));
+        assert_eq!(minus(3), i256_div(u(7), minus(2)));
+        assert_eq!(u(3), i256_div(minus(7), minus(2)));
+        assert_eq!(U256::ZERO, i256_div(u(7), U256::ZERO));
+        assert_eq!(MIN_NEGATIVE, i256_div(MIN_NEGATIVE, U256::MAX));
+
+        assert_eq!(u(1), i256_mod(u(7), minus(2)));
+        assert_eq!(minus(1), i256_mod(minus(7), u(2)));
+        assert_eq!(U256::ZERO, i256_mod(minus(7), U256::ZERO));
+    }
 }
@@ -4,16 +4,14 @@ use std::ops::Rem;
 use super::InstructionResult;
 use crate::{
     constants::{WORD_SIZE, WORD_SIZE_BYTES},
+    gas,
     utils::ToUsize,
     Interpreter,
 };
 
 pub fn mload(interpreter: &mut Interpreter) -> InstructionResult {
-    let addr = interpreter.stack.pop()?.as_usize()?;
-    let size = addr + WORD_SIZE_BYTES;
-    if size > interpreter.memory.len() {
-        interpreter.memory.resize(size);
-    }
+    let addr = interpreter.stack.pop()?;
+    let (addr, _) = interpreter.resize_memory_range(addr, U256::from(WORD_SIZE_BYTES))?;
     let word = interpreter.memory.get_u256(addr);
     interpreter.stack.push(word)?;
 
@@ -21,23 +19,17 @@ pub fn mload(interpreter: &mut Interpreter) -> InstructionResult {
 }
 
 pub fn mstore(interpreter: &mut Interpreter) -> InstructionResult {
-    let addr = interpreter.stack.pop()?.as_usize()?;
+    let addr = interpreter.stack.pop()?;
     let word = interpreter.stack.pop()?;
-    let size = addr + WORD_SIZE_BYTES;
-    if size > interpreter.memory.len() {
-        interpreter.memory.resize(size);
-    }
+    let (addr, _) = interpreter.resize_memory_range(addr, U256::from(WORD_SIZE_BYTES))?;
     interpreter.memory.set_u256(addr, &word);
     Ok(1)
 }
 
 pub fn mstore8(interpreter: &mut Interpreter) -> InstructionResult {
-    let addr = interpreter.stack.pop()?.as_usize()?;
+    let addr = interpreter.stack.pop()?;
     let word = interpreter.stack.pop()?;
-    let size = addr + 1;
-    if size > interpreter.memory.len() {
-        interpreter.memory.resize(size);
-    }
+    let (addr, _) = interpreter.resize_memory_range(addr, U256::from(1))?;
     let byte = word.rem(U256::from(WORD_SIZE)).byte(0);
     interpreter.memory.set_byte(addr, byte);
     Ok(1)
@@ -48,3 +40,21 @@ pub fn msize(interpreter: &mut Interpreter) -> InstructionResult {
     interpreter.stack.push(U256::from(size))?;
     Ok(1)
 }
+
+/// Copies within memory, handling overlapping ranges (EIP-5656).
+pub fn mcopy(interpreter: &mut Interpreter) -> InstructionResult {
+    let dst = interpreter.stack.pop()?;
+    let src = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let length = length.as_usize()?;
+    interpreter.gas.record_cost(gas::copy_cost(length as u64))?;
+    if length == 0 {
+        return Ok(1);
+    }
+
+    // Memory must fit both ranges.
+    let (src, _) = interpreter.resize_memory_range(src, U256::from(length))?;
+    let (dst, _) = interpreter.resize_memory_range(dst, U256::from(length))?;
+    interpreter.memory.copy_within(src, dst, length);
+    Ok(1)
+}
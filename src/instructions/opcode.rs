@@ -1,4 +1,8 @@
-use super::{arithmetic, bitwise, control, memory, stack, system, Instruction};
+use crate::spec::SpecId;
+
+use super::{
+    arithmetic, bitwise, control, data, host, host_env, memory, stack, system, Instruction,
+};
 
 macro_rules! opcodes {
     ($($val:literal => $name:ident => $f:expr),* $(,)?) => {
@@ -22,12 +26,16 @@ macro_rules! opcodes {
             map
         };
 
-        /// Returns the instruction function for the given opcode and spec.
+        /// Maps each opcode to its instruction. Unknown opcodes fail.
+        pub static INSTRUCTIONS: [Instruction; 256] = {
+            let mut table = [control::unknown as Instruction; 256];
+            $(table[$val] = $f;)*
+            table
+        };
+
+        /// Returns the instruction function for the given opcode.
         pub fn instruction(opcode: u8) -> Instruction {
-            match opcode {
-                $($name => $f,)*
-                _ => control::unknown,
-            }
+            INSTRUCTIONS[opcode as usize]
         }
 
         /// Returns the instruction opcode given it's name.
@@ -47,13 +55,13 @@ opcodes! {
     0x02 => MUL        => arithmetic::mul,
     0x03 => SUB        => arithmetic::sub,
     0x04 => DIV        => arithmetic::div,
-    // 0x05 => SDIV       => arithmetic::sdiv,
-    // 0x06 => MOD        => arithmetic::rem,
-    // 0x07 => SMOD       => arithmetic::smod,
-    // 0x08 => ADDMOD     => arithmetic::addmod,
-    // 0x09 => MULMOD     => arithmetic::mulmod,
-    // 0x0A => EXP        => arithmetic::exp,
-    // 0x0B => SIGNEXTEND => arithmetic::signextend,
+    0x05 => SDIV       => arithmetic::sdiv,
+    0x06 => MOD        => arithmetic::rem,
+    0x07 => SMOD       => arithmetic::smod,
+    0x08 => ADDMOD     => arithmetic::addmod,
+    0x09 => MULMOD     => arithmetic::mulmod,
+    0x0A => EXP        => arithmetic::exp,
+    0x0B => SIGNEXTEND => arithmetic::signextend,
     // 0x0C
     // 0x0D
     // 0x0E
@@ -71,10 +79,10 @@ opcodes! {
     0x1A => BYTE   => bitwise::byte,
     0x1B => SHL    => bitwise::shl,
     0x1C => SHR    => bitwise::shr,
-    // 0x1D => SAR    => bitwise::sar,
+    0x1D => SAR    => bitwise::sar,
     // 0x1E
     // 0x1F
-    // 0x20 => KECCAK256 => system::keccak256,
+    0x20 => KECCAK256 => system::keccak256,
     // 0x21
     // 0x22
     // 0x23
@@ -90,34 +98,34 @@ opcodes! {
     // 0x2D
     // 0x2E
     // 0x2F
-    // 0x30 => ADDRESS        => system::address,
-    // 0x31 => BALANCE        => host::balance,
-    // 0x32 => ORIGIN         => host_env::origin,
-    // 0x33 => CALLER         => system::caller,
-    // 0x34 => CALLVALUE      => system::callvalue,
+    0x30 => ADDRESS        => system::address,
+    0x31 => BALANCE        => host::balance,
+    0x32 => ORIGIN         => host_env::origin,
+    0x33 => CALLER         => system::caller,
+    0x34 => CALLVALUE      => system::callvalue,
     0x35 => CALLDATALOAD   => system::calldataload,
     0x36 => CALLDATASIZE   => system::calldatasize,
     0x37 => CALLDATACOPY   => system::calldatacopy,
-    // 0x38 => CODESIZE       => system::codesize,
-    // 0x39 => CODECOPY       => system::codecopy,
+    0x38 => CODESIZE       => system::codesize,
+    0x39 => CODECOPY       => system::codecopy,
     //
-    // 0x3A => GASPRICE       => host_env::gasprice,
-    // 0x3B => EXTCODESIZE    => host::extcodesize,
-    // 0x3C => EXTCODECOPY    => host::extcodecopy,
-    // 0x3D => RETURNDATASIZE => system::returndatasize,
-    // 0x3E => RETURNDATACOPY => system::returndatacopy,
-    // 0x3F => EXTCODEHASH    => host::extcodehash,
-    // 0x40 => BLOCKHASH      => host::blockhash,
-    // 0x41 => COINBASE       => host_env::coinbase,
-    // 0x42 => TIMESTAMP      => host_env::timestamp,
-    // 0x43 => NUMBER         => host_env::number,
-    // 0x44 => DIFFICULTY     => host_env::difficulty,
-    // 0x45 => GASLIMIT       => host_env::gaslimit,
-    // 0x46 => CHAINID        => host_env::chainid,
-    // 0x47 => SELFBALANCE    => host::selfbalance,
-    // 0x48 => BASEFEE        => host_env::basefee,
-    // 0x49 => BLOBHASH       => host_env::blob_hash,
-    // 0x4A => BLOBBASEFEE    => host_env::blob_basefee,
+    0x3A => GASPRICE       => host_env::gasprice,
+    0x3B => EXTCODESIZE    => host::extcodesize,
+    0x3C => EXTCODECOPY    => host::extcodecopy,
+    0x3D => RETURNDATASIZE => system::returndatasize,
+    0x3E => RETURNDATACOPY => system::returndatacopy,
+    0x3F => EXTCODEHASH    => host::extcodehash,
+    0x40 => BLOCKHASH      => host_env::blockhash,
+    0x41 => COINBASE       => host_env::coinbase,
+    0x42 => TIMESTAMP      => host_env::timestamp,
+    0x43 => NUMBER         => host_env::number,
+    0x44 => DIFFICULTY     => host_env::difficulty,
+    0x45 => GASLIMIT       => host_env::gaslimit,
+    0x46 => CHAINID        => host_env::chainid,
+    0x47 => SELFBALANCE    => host::selfbalance,
+    0x48 => BASEFEE        => host_env::basefee,
+    0x49 => BLOBHASH       => host_env::blob_hash,
+    0x4A => BLOBBASEFEE    => host_env::blob_basefee,
     // 0x4B
     // 0x4C
     // 0x4D
@@ -127,17 +135,17 @@ opcodes! {
     0x51 => MLOAD    => memory::mload,
     0x52 => MSTORE   => memory::mstore,
     0x53 => MSTORE8  => memory::mstore8,
-    // 0x54 => SLOAD    => host::sload,
-    // 0x55 => SSTORE   => host::sstore,
+    0x54 => SLOAD    => host::sload,
+    0x55 => SSTORE   => host::sstore,
     0x56 => JUMP     => control::jump,
     0x57 => JUMPI    => control::jumpi,
     0x58 => PC       => control::pc,
     0x59 => MSIZE    => memory::msize,
-    // 0x5A => GAS      => system::gas,
+    0x5A => GAS      => system::gas,
     0x5B => JUMPDEST => control::jumpdest,
-    // 0x5C => TLOAD    => host::tload,
-    // 0x5D => TSTORE   => host::tstore,
-    // 0x5E => MCOPY    => memory::mcopy,
+    0x5C => TLOAD    => host::tload,
+    0x5D => TSTORE   => host::tstore,
+    0x5E => MCOPY    => memory::mcopy,
     //
     0x5F => PUSH0  => stack::push0,
     0x60 => PUSH1  => stack::push::<1>,
@@ -207,11 +215,11 @@ opcodes! {
     0x9E => SWAP15 => stack::swap::<15>,
     0x9F => SWAP16 => stack::swap::<16>,
     //
-    // 0xA0 => LOG0 => host::log::<0>,
-    // 0xA1 => LOG1 => host::log::<1>,
-    // 0xA2 => LOG2 => host::log::<2>,
-    // 0xA3 => LOG3 => host::log::<3>,
-    // 0xA4 => LOG4 => host::log::<4>,
+    0xA0 => LOG0 => host::log::<0>,
+    0xA1 => LOG1 => host::log::<1>,
+    0xA2 => LOG2 => host::log::<2>,
+    0xA3 => LOG3 => host::log::<3>,
+    0xA4 => LOG4 => host::log::<4>,
     // 0xA5
     // 0xA6
     // 0xA7
@@ -255,10 +263,10 @@ opcodes! {
     // 0xCD
     // 0xCE
     // 0xCF
-    // 0xD0
-    // 0xD1
-    // 0xD2
-    // 0xD3
+    0xD0 => DATALOAD  => data::dataload,
+    0xD1 => DATALOADN => data::dataloadn,
+    0xD2 => DATASIZE  => data::datasize,
+    0xD3 => DATACOPY  => data::datacopy,
     // 0xD4
     // 0xD5
     // 0xD6
@@ -271,36 +279,84 @@ opcodes! {
     // 0xDD
     // 0xDE
     // 0xDF
-    // 0xE0
-    // 0xE1
-    // 0xE2
-    // 0xE3
-    // 0xE4
-    // 0xE5
-    // 0xE6
-    // 0xE7
-    // 0xE8
+    0xE0 => RJUMP    => control::rjump,
+    0xE1 => RJUMPI   => control::rjumpi,
+    0xE2 => RJUMPV   => control::rjumpv,
+    0xE3 => CALLF    => control::callf,
+    0xE4 => RETF     => control::retf,
+    0xE5 => JUMPF    => control::jumpf,
+    0xE6 => DUPN     => stack::dupn,
+    0xE7 => SWAPN    => stack::swapn,
+    0xE8 => EXCHANGE => stack::exchange,
     // 0xE9
     // 0xEA
     // 0xEB
-    // 0xEC
+    // 0xEC => EOFCREATE      => host::eofcreate,
     // 0xED
-    // 0xEE
+    // 0xEE => RETURNCONTRACT => host::returncontract,
     // 0xEF
-    // 0xF0 => CREATE       => host::create::<false, H, SPEC>,
-    // 0xF1 => CALL         => host::call,
-    // 0xF2 => CALLCODE     => host::call_code,
+    0xF0 => CREATE       => host::create::<false>,
+    0xF1 => CALL         => host::call,
+    0xF2 => CALLCODE     => host::call_code,
     0xF3 => RETURN       => control::ret,
-    // 0xF4 => DELEGATECALL => host::delegate_call,
-    // 0xF5 => CREATE2      => host::create::<true, H, SPEC>,
+    0xF4 => DELEGATECALL => host::delegate_call,
+    0xF5 => CREATE2      => host::create::<true>,
     // 0xF6
     // 0xF7
     // 0xF8
     // 0xF9
-    // 0xFA => STATICCALL   => host::static_call,
+    0xFA => STATICCALL   => host::static_call,
     // 0xFB
     // 0xFC
-    // 0xFD => REVERT       => control::revert,
+    0xFD => REVERT       => control::revert,
     0xFE => INVALID      => control::invalid,
-    // 0xFF => SELFDESTRUCT => host::selfdestruct,
+    0xFF => SELFDESTRUCT => host::selfdestruct,
+}
+
+/// The number of items each opcode pops from and pushes onto the stack.
+/// Unassigned opcodes touch nothing.
+pub const fn stack_io(op: u8) -> (u8, u8) {
+    match op {
+        ADD..=SMOD | EXP | SIGNEXTEND => (2, 1),
+        ADDMOD | MULMOD => (3, 1),
+        ISZERO | NOT => (1, 1),
+        LT..=SAR | KECCAK256 => (2, 1),
+        BALANCE | CALLDATALOAD | BLOBHASH | MLOAD | SLOAD | TLOAD | DATALOAD => (1, 1),
+        DATALOADN | DATASIZE => (0, 1),
+        DATACOPY => (3, 0),
+        RJUMPI | RJUMPV => (1, 0),
+        // The other operands of DUPN, SWAPN, EXCHANGE and the function
+        // instructions depend on immediates, and are checked when they run.
+        DUPN => (0, 1),
+        EXTCODESIZE | EXTCODEHASH | BLOCKHASH => (1, 1),
+        CALLDATACOPY | CODECOPY | RETURNDATACOPY | MCOPY => (3, 0),
+        EXTCODECOPY => (4, 0),
+        ADDRESS..=BLOBBASEFEE | PC | MSIZE | GAS | PUSH0..=PUSH32 => (0, 1),
+        POP | JUMP => (1, 0),
+        MSTORE | MSTORE8 | SSTORE | JUMPI | TSTORE | RETURN | REVERT => (2, 0),
+        DUP1..=DUP16 => (op - DUP1 + 1, op - DUP1 + 2),
+        SWAP1..=SWAP16 => (op - SWAP1 + 2, op - SWAP1 + 2),
+        LOG0..=LOG4 => (op - LOG0 + 2, 0),
+        CREATE => (3, 1),
+        CREATE2 => (4, 1),
+        CALL | CALLCODE => (7, 1),
+        DELEGATECALL | STATICCALL => (6, 1),
+        SELFDESTRUCT => (1, 0),
+        _ => (0, 0),
+    }
+}
+
+/// The fork that introduced each opcode. Opcodes outside mainnet, such as
+/// custom ones, are available from the start.
+pub const fn introduced_in(op: u8) -> SpecId {
+    match op {
+        DELEGATECALL => SpecId::Homestead,
+        RETURNDATASIZE | RETURNDATACOPY | STATICCALL | REVERT => SpecId::Byzantium,
+        SHL | SHR | SAR | EXTCODEHASH | CREATE2 => SpecId::Constantinople,
+        CHAINID | SELFBALANCE => SpecId::Istanbul,
+        BASEFEE => SpecId::London,
+        PUSH0 => SpecId::Shanghai,
+        BLOBHASH | BLOBBASEFEE | TLOAD | TSTORE | MCOPY => SpecId::Cancun,
+        _ => SpecId::Frontier,
+    }
 }
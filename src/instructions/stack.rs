@@ -1,6 +1,6 @@
 use alloy_primitives::U256;
 
-use crate::{utils::ToUsize, Interpreter};
+use crate::Interpreter;
 
 use super::InstructionResult;
 
@@ -10,7 +10,7 @@ pub fn push0(interpreter: &mut Interpreter) -> InstructionResult {
 }
 
 pub fn push<const N: usize>(interpreter: &mut Interpreter) -> InstructionResult {
-    let ip = interpreter.instruction_pointer.get().as_usize()?;
+    let ip = interpreter.instruction_pointer;
 
     let bytecode = &interpreter.contract.bytecode.bytes[ip + 1..];
     let bytes = N.min(bytecode.len());
@@ -35,3 +35,30 @@ pub fn swap<const N: usize>(interpreter: &mut Interpreter) -> InstructionResult
     interpreter.stack.swap(N)?;
     Ok(1)
 }
+
+/// Duplicates the item at the depth in its immediate, plus one (EIP-663).
+pub fn dupn(interpreter: &mut Interpreter) -> InstructionResult {
+    let n = immediate(interpreter)?;
+    interpreter.stack.dup(n as usize)?;
+    Ok(2)
+}
+
+pub fn swapn(interpreter: &mut Interpreter) -> InstructionResult {
+    let n = immediate(interpreter)?;
+    interpreter.stack.swap(n as usize + 1)?;
+    Ok(2)
+}
+
+/// Swaps the items at the depths in the two nibbles of its immediate.
+pub fn exchange(interpreter: &mut Interpreter) -> InstructionResult {
+    let imm = immediate(interpreter)?;
+    let n = (imm >> 4) as usize + 1;
+    let m = (imm & 0x0F) as usize + 1;
+    interpreter.stack.exchange(n, m)?;
+    Ok(2)
+}
+
+fn immediate(interpreter: &Interpreter) -> eyre::Result<u8> {
+    let ip = interpreter.instruction_pointer;
+    Ok(interpreter.contract.bytecode.bytes[ip + 1])
+}
@@ -1,9 +1,39 @@
-use alloy_primitives::{B256, U256};
+use alloy_primitives::{keccak256 as hash, B256, U256};
+use eyre::anyhow;
 
-use crate::{constants::WORD_SIZE_BYTES, utils::ToUsize, Interpreter};
+use crate::{constants::WORD_SIZE_BYTES, gas, utils::ToUsize, Interpreter};
 
 use super::InstructionResult;
 
+pub fn keccak256(interpreter: &mut Interpreter) -> InstructionResult {
+    let offset = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let (offset, length) = interpreter.resize_memory_range(offset, length)?;
+    interpreter
+        .gas
+        .record_cost(gas::keccak256_cost(length as u64))?;
+    let hash = hash(interpreter.memory.slice(offset, length));
+    interpreter.stack.push(hash.into())?;
+    Ok(1)
+}
+
+pub fn address(interpreter: &mut Interpreter) -> InstructionResult {
+    let address = interpreter.contract.address.into_word();
+    interpreter.stack.push(address.into())?;
+    Ok(1)
+}
+
+pub fn caller(interpreter: &mut Interpreter) -> InstructionResult {
+    let caller = interpreter.contract.caller.into_word();
+    interpreter.stack.push(caller.into())?;
+    Ok(1)
+}
+
+pub fn callvalue(interpreter: &mut Interpreter) -> InstructionResult {
+    interpreter.stack.push(interpreter.contract.value)?;
+    Ok(1)
+}
+
 pub fn calldataload(interpreter: &mut Interpreter) -> InstructionResult {
     let index = interpreter.stack.pop()?.as_usize_saturated();
     let bytes = if index < interpreter.contract.input.len() {
@@ -27,25 +57,76 @@ pub fn calldatasize(interpreter: &mut Interpreter) -> InstructionResult {
 }
 
 pub fn calldatacopy(interpreter: &mut Interpreter) -> InstructionResult {
-    let mem_offset = interpreter.stack.pop()?.as_usize()?;
+    let mem_offset = interpreter.stack.pop()?;
     let data_offset = interpreter.stack.pop()?;
-    let length = interpreter.stack.pop()?.as_usize()?;
-    if length == 0 {
-        return Ok(1);
-    }
+    let length = interpreter.stack.pop()?;
+    let input = interpreter.contract.input.clone();
+    copy_to_memory(interpreter, mem_offset, &input, data_offset, length)?;
+    Ok(1)
+}
 
-    let data_offset = data_offset.as_usize_saturated();
-    let bytes = if data_offset < interpreter.contract.input.len() {
-        let bytes = WORD_SIZE_BYTES.min(interpreter.contract.input.len() - data_offset);
-        let mut slice = [0u8; WORD_SIZE_BYTES];
-        let data_slice = &interpreter.contract.input[data_offset..data_offset + bytes];
-        slice[..bytes].copy_from_slice(data_slice);
-        slice
-    } else {
-        [0u8; WORD_SIZE_BYTES]
-    };
+pub fn codesize(interpreter: &mut Interpreter) -> InstructionResult {
+    let len = U256::from(interpreter.contract.bytecode.len());
+    interpreter.stack.push(len)?;
+    Ok(1)
+}
+
+pub fn codecopy(interpreter: &mut Interpreter) -> InstructionResult {
+    let mem_offset = interpreter.stack.pop()?;
+    let code_offset = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    let code = interpreter.contract.bytecode.bytes.clone();
+    copy_to_memory(interpreter, mem_offset, &code, code_offset, length)?;
+    Ok(1)
+}
+
+pub fn returndatasize(interpreter: &mut Interpreter) -> InstructionResult {
+    let len = U256::from(interpreter.return_data.len());
+    interpreter.stack.push(len)?;
+    Ok(1)
+}
 
-    interpreter.memory.set(mem_offset, &bytes);
+pub fn returndatacopy(interpreter: &mut Interpreter) -> InstructionResult {
+    let mem_offset = interpreter.stack.pop()?;
+    let data_offset = interpreter.stack.pop()?;
+    let length = interpreter.stack.pop()?;
+    // Unlike the other copies, reading past the end is an error (EIP-211).
+    let end = data_offset.saturating_add(length);
+    if end > U256::from(interpreter.return_data.len()) {
+        return Err(anyhow!("return data out of bounds"));
+    }
+
+    let data = interpreter.return_data.clone();
+    copy_to_memory(interpreter, mem_offset, &data, data_offset, length)?;
+    Ok(1)
+}
 
+pub fn gas(interpreter: &mut Interpreter) -> InstructionResult {
+    let remaining = U256::from(interpreter.gas.remaining);
+    interpreter.stack.push(remaining)?;
     Ok(1)
 }
+
+/// Copies `length` bytes of `data` starting at `data_offset` into memory,
+/// padding with zeroes past the end of `data`. Charges for the memory
+/// expansion and the copy.
+pub(crate) fn copy_to_memory(
+    interpreter: &mut Interpreter,
+    mem_offset: U256,
+    data: &[u8],
+    data_offset: U256,
+    length: U256,
+) -> eyre::Result<()> {
+    let (mem_offset, length) = interpreter.resize_memory_range(mem_offset, length)?;
+    interpreter.gas.record_cost(gas::copy_cost(length as u64))?;
+    if length == 0 {
+        return Ok(());
+    }
+
+    let data_offset = data_offset.as_usize_saturated().min(data.len());
+    let available = length.min(data.len() - data_offset);
+    let memory = interpreter.memory.slice_mut(mem_offset, length);
+    memory[..available].copy_from_slice(&data[data_offset..data_offset + available]);
+    memory[available..].fill(0);
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use crate::gas;
+
+use super::{
+    control,
+    opcode::{
+        self, CALLF, DATALOADN, DUPN, EXCHANGE, JUMPF, OPCODE_NAMES, PUSH0, PUSH1, PUSH32, RJUMP,
+        RJUMPI, RJUMPV, SWAPN,
+    },
+    Instruction,
+};
+
+/// What the interpreter, the assembler and the disassembler need to know
+/// about an opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpcodeInfo {
+    pub name: &'static str,
+    /// The number of items popped from the stack.
+    pub inputs: u8,
+    /// The number of items pushed onto the stack.
+    pub outputs: u8,
+    /// The gas charged before the instruction runs. Anything depending on
+    /// the operands is charged by the instruction itself.
+    pub static_gas: u64,
+    /// The number of bytes of immediate data following the opcode.
+    pub immediate_size: u8,
+}
+
+impl OpcodeInfo {
+    pub const fn new(name: &'static str, inputs: u8, outputs: u8, static_gas: u64) -> Self {
+        Self {
+            name,
+            inputs,
+            outputs,
+            static_gas,
+            immediate_size: 0,
+        }
+    }
+
+    pub const fn with_immediate_size(mut self, immediate_size: u8) -> Self {
+        self.immediate_size = immediate_size;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct InstructionEntry {
+    pub info: OpcodeInfo,
+    pub instruction: Instruction,
+}
+
+/// Maps each opcode to its instruction. Embedders can add opcodes in unused
+/// slots or replace the instruction of existing ones, for example with an
+/// instrumented version.
+#[derive(Clone, Debug)]
+pub struct InstructionTable {
+    entries: Box<[Option<InstructionEntry>; 256]>,
+    /// Whether the table is still [`InstructionTable::standard`], which lets
+    /// the interpreter dispatch from static tables instead.
+    standard: bool,
+}
+
+impl Default for InstructionTable {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl InstructionTable {
+    /// A table where every opcode is unknown.
+    pub fn empty() -> Self {
+        Self {
+            entries: Box::new([None; 256]),
+            standard: false,
+        }
+    }
+
+    /// The opcodes of Ethereum mainnet.
+    pub fn standard() -> Self {
+        let mut table = Self::empty();
+        for (op, name) in OPCODE_NAMES.iter().enumerate() {
+            let Some(name) = name else {
+                continue;
+            };
+            let op = op as u8;
+            let (inputs, outputs) = opcode::stack_io(op);
+            let mut info = OpcodeInfo::new(name, inputs, outputs, gas::static_gas(op));
+            match op {
+                PUSH1..=PUSH32 => info = info.with_immediate_size(op - PUSH0),
+                RJUMP | RJUMPI | CALLF | JUMPF | DATALOADN => info = info.with_immediate_size(2),
+                // The jump table of RJUMPV follows its size.
+                RJUMPV | DUPN | SWAPN | EXCHANGE => info = info.with_immediate_size(1),
+                _ => {}
+            }
+            table.insert(op, info, opcode::instruction(op));
+        }
+        table.standard = true;
+        table
+    }
+
+    /// Registers `instruction` at `opcode`, returning whatever was there.
+    /// Names are expected to be unique across the table.
+    pub fn insert(
+        &mut self,
+        opcode: u8,
+        info: OpcodeInfo,
+        instruction: Instruction,
+    ) -> Option<InstructionEntry> {
+        self.standard = false;
+        self.entries[opcode as usize].replace(InstructionEntry { info, instruction })
+    }
+
+    /// Makes `opcode` unknown, returning whatever was there.
+    pub fn remove(&mut self, opcode: u8) -> Option<InstructionEntry> {
+        self.standard = false;
+        self.entries[opcode as usize].take()
+    }
+
+    /// Whether the table holds the standard opcodes, unchanged.
+    pub fn is_standard(&self) -> bool {
+        self.standard
+    }
+
+    pub fn get(&self, opcode: u8) -> Option<&InstructionEntry> {
+        self.entries[opcode as usize].as_ref()
+    }
+
+    /// Every slot of the table, indexed by opcode.
+    pub fn entries(&self) -> &[Option<InstructionEntry>; 256] {
+        &self.entries
+    }
+
+    pub fn info(&self, opcode: u8) -> Option<&OpcodeInfo> {
+        self.get(opcode).map(|entry| &entry.info)
+    }
+
+    /// Returns the instruction for `opcode`, which fails for unknown
+    /// opcodes.
+    pub fn instruction(&self, opcode: u8) -> Instruction {
+        self.get(opcode)
+            .map_or(control::unknown, |entry| entry.instruction)
+    }
+
+    /// Returns the opcode with the given name.
+    pub fn opcode(&self, name: &str) -> Option<u8> {
+        self.entries
+            .iter()
+            .position(|entry| entry.is_some_and(|entry| entry.info.name == name))
+            .map(|op| op as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use alloy_primitives::{Bytes, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        env::Env,
+        instructions::{opcode, InstructionResult},
+        Contract, Interpreter,
+    };
+
+    use super::{InstructionTable, OpcodeInfo};
+
+    fn push_answer(interpreter: &mut Interpreter) -> InstructionResult {
+        interpreter.stack.push(U256::from(42))?;
+        Ok(1)
+    }
+
+    fn interpreter(bytecode: &str, table: InstructionTable) -> Interpreter {
+        let bytes = bytecode.parse().unwrap();
+        let contract = Box::new(Contract::new(Default::default(), bytes, Default::default()));
+        let mut interpreter = Interpreter::new(contract);
+        let mut env = Env::default();
+        env.cfg.instructions = table;
+        interpreter.env = Rc::new(env);
+        interpreter
+    }
+
+    #[test]
+    fn describes_standard_opcodes() {
+        let table = InstructionTable::standard();
+        assert_eq!(
+            Some(&OpcodeInfo::new("ADD", 2, 1, 3)),
+            table.info(opcode::ADD)
+        );
+        assert_eq!(
+            Some(&OpcodeInfo::new("PUSH2", 0, 1, 3).with_immediate_size(2)),
+            table.info(opcode::PUSH2)
+        );
+        assert_eq!(
+            Some((3, 4)),
+            table.info(opcode::DUP3).map(|i| (i.inputs, i.outputs))
+        );
+        assert_eq!(Some(opcode::SWAP1), table.opcode("SWAP1"));
+        assert_eq!(None, table.info(0xB0));
+        assert_eq!(None, table.opcode("ANSWER"));
+        assert_eq!(true, table.is_standard());
+    }
+
+    #[test]
+    fn runs_custom_opcodes() {
+        let mut table = InstructionTable::standard();
+        table.insert(0xB0, OpcodeInfo::new("ANSWER", 0, 1, 7), push_answer);
+        assert_eq!(Some(0xB0), table.opcode("ANSWER"));
+        assert_eq!(false, table.is_standard());
+
+        // ANSWER
+        // PUSH1 0x00
+        // MSTORE8
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // RETURN
+        let mut interpreter = interpreter("0xb060005360016000f3", table.clone());
+        let result = interpreter.execute().expect("should finish execution");
+        assert_eq!(Bytes::from_static(&[42]), result);
+        assert_eq!(7 + 3 + 3 + 3 + 3 + 3, interpreter.gas.spent());
+
+        // Without the opcode, the same code fails.
+        table.remove(0xB0);
+        let err = self::interpreter("0xb060005360016000f3", table)
+            .execute()
+            .unwrap_err();
+        assert_eq!("unknown opcode", err.to_string());
+    }
+
+    #[test]
+    fn overrides_opcodes() {
+        let mut table = InstructionTable::standard();
+        let info = *table.info(opcode::ADD).unwrap();
+        table.insert(opcode::ADD, info, push_answer);
+
+        // PUSH1 0x01
+        // PUSH1 0x02
+        // ADD
+        // PUSH1 0x00
+        // MSTORE8
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // RETURN
+        let mut interpreter = interpreter("0x600160020160005360016000f3", table);
+        let result = interpreter.execute().expect("should finish execution");
+        assert_eq!(Bytes::from_static(&[42]), result);
+    }
+
+    #[test]
+    fn checks_stack_arity() {
+        let mut table = InstructionTable::standard();
+        table.insert(0xB0, OpcodeInfo::new("ANSWER", 1, 2, 0), push_answer);
+
+        // ANSWER
+        let mut interpreter = interpreter("0xb0", table);
+        let err = interpreter.execute().unwrap_err();
+        assert_eq!("stack underflow", err.to_string());
+    }
+}
@@ -1,9 +1,22 @@
-use std::cell::{Cell, RefCell};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use alloy_primitives::{Bytes, U256};
+use eyre::anyhow;
 
 use crate::{
-    instructions::{opcode::instruction, Instruction},
+    env::Env,
+    eof,
+    gas::{self, Gas},
+    instructions::{
+        control,
+        opcode::{self, INSTRUCTIONS, OPCODE_NAMES, STOP},
+        Instruction,
+    },
+    journal::JournaledState,
+    spec::SpecId,
     utils::ToUsize,
     Contract, Memory, Stack,
 };
@@ -13,22 +26,56 @@ pub struct Interpreter {
     pub stack: Stack,
     pub memory: Memory,
     pub contract: Box<Contract>,
-    pub gas: Cell<U256>,
-    pub instruction_pointer: Cell<U256>,
+    pub gas: Gas,
+    pub instruction_pointer: usize,
     pub return_data_buffer: RefCell<Bytes>,
     pub stopped: Cell<bool>,
+    pub reverted: Cell<bool>,
+    pub journal: JournaledState,
+    pub env: Rc<Env>,
+    /// The output of the last sub-call, read by `RETURNDATASIZE` and
+    /// `RETURNDATACOPY`.
+    pub return_data: Bytes,
+    /// The number of frames above this one.
+    pub depth: usize,
+    /// Whether state changes are forbidden, as inside `STATICCALL`.
+    pub is_static: bool,
+    /// The EOF code section being run.
+    pub code_section: usize,
+    /// The code section and instruction pointer `RETF` returns to, for each
+    /// `CALLF` in progress.
+    pub return_stack: Vec<(usize, usize)>,
 }
 
 impl Interpreter {
     pub fn new(contract: Box<Contract>) -> Self {
+        Self::with_journal(contract, JournaledState::default())
+    }
+
+    /// Creates an interpreter that runs against an existing world state.
+    pub fn with_journal(contract: Box<Contract>, journal: JournaledState) -> Self {
+        // EOF code starts at its first code section, past the header.
+        let ip = contract
+            .bytecode
+            .eof
+            .as_ref()
+            .map_or(0, |eof| eof.code_offsets[0]);
         Self {
             stack: Stack::default(),
             memory: Memory::default(),
             contract,
-            gas: Default::default(),
-            instruction_pointer: Default::default(),
+            gas: Gas::new(u64::MAX),
+            instruction_pointer: ip,
             return_data_buffer: Default::default(),
             stopped: Default::default(),
+            reverted: Default::default(),
+            journal,
+            env: Default::default(),
+            return_data: Default::default(),
+            depth: 0,
+            is_static: false,
+            code_section: 0,
+            return_stack: Vec::new(),
         }
     }
 
@@ -36,41 +83,201 @@ impl Interpreter {
         self.stopped.set(true);
     }
 
-    pub fn next(&self) -> Instruction {
-        let ip = self
-            .instruction_pointer
-            .get()
-            .as_usize()
-            .expect("ip should fit in a usize");
+    /// Stops execution and marks the frame as reverted, so that its state
+    /// changes get undone.
+    pub fn revert(&self) {
+        self.reverted.set(true);
+        self.stop();
+    }
 
+    /// Returns the opcode at the instruction pointer.
+    pub fn opcode(&self) -> u8 {
         // Section 9.4.1 of the yellowpaper, the operation to be executed if the
         // instruction pointer is outside code is STOP.
-        if ip > self.contract.bytecode.len() {
-            return instruction(0);
+        self.contract
+            .bytecode
+            .bytes
+            .get(self.instruction_pointer)
+            .copied()
+            .unwrap_or(STOP)
+    }
+
+    /// Expands memory to fit `size` bytes, rounded up to a whole word, and
+    /// charges for the expansion.
+    pub fn resize_memory(&mut self, size: usize) -> eyre::Result<()> {
+        if size <= self.memory.len() {
+            return Ok(());
+        } else if size > u32::MAX as usize {
+            // No block gas limit could ever pay for this much memory.
+            return Err(anyhow!("out of gas"));
+        }
+
+        let old_words = gas::num_words(self.memory.len() as u64);
+        let new_words = gas::num_words(size as u64);
+        let cost = gas::memory_cost(new_words) - gas::memory_cost(old_words);
+        self.gas.record_cost(cost)?;
+        self.memory.resize(new_words as usize * 32);
+        Ok(())
+    }
+
+    /// Expands memory to fit `length` bytes at `addr`. Does nothing when
+    /// `length` is zero, no matter the offset.
+    pub fn resize_memory_range(
+        &mut self,
+        addr: U256,
+        length: U256,
+    ) -> eyre::Result<(usize, usize)> {
+        let length = length.as_usize()?;
+        if length == 0 {
+            return Ok((0, 0));
         }
 
-        let opcode = self.contract.bytecode.bytes[ip];
-        instruction(opcode)
+        let addr = addr.as_usize()?;
+        let size = addr
+            .checked_add(length)
+            .ok_or_else(|| anyhow!("out of gas"))?;
+        self.resize_memory(size)?;
+        Ok((addr, length))
     }
 
+    /// Runs the contract until it halts. Every change made to the world state
+    /// is undone if the frame reverts or fails.
     pub fn execute(&mut self) -> eyre::Result<Bytes> {
+        let checkpoint = self.journal.checkpoint();
+        let result = self.run();
+        if result.is_ok() && !self.reverted.get() {
+            self.journal.commit(checkpoint);
+        } else {
+            self.journal.revert(checkpoint);
+        }
+
+        result
+    }
+
+    fn run(&mut self) -> eyre::Result<Bytes> {
+        // Everything looked up on each step is resolved once up front, so
+        // the loop only indexes into fixed tables.
+        let env = Rc::clone(&self.env);
+        let table = &env.cfg.instructions;
+        let code = self.contract.bytecode.bytes.clone();
+        let defined = &DEFINED[env.cfg.spec as usize][self.contract.bytecode.is_eof() as usize];
+        if table.is_standard() {
+            // The standard opcodes are known at compile time, so they are
+            // read from a static table rather than through the configured one.
+            self.dispatch(&code, defined, |op| STANDARD[op as usize])
+        } else {
+            let entries = table.entries();
+            self.dispatch(&code, defined, |op| match &entries[op as usize] {
+                Some(entry) => Step {
+                    static_gas: entry.info.static_gas,
+                    inputs: entry.info.inputs,
+                    outputs: entry.info.outputs,
+                    instruction: entry.instruction,
+                },
+                None => UNKNOWN,
+            })
+        }
+    }
+
+    /// Runs `code` until it halts, looking up each opcode with `lookup`.
+    #[inline(always)]
+    fn dispatch(
+        &mut self,
+        code: &[u8],
+        defined: &[bool; 256],
+        lookup: impl Fn(u8) -> Step,
+    ) -> eyre::Result<Bytes> {
         while !self.stopped.get() {
-            let instruction = self.next();
-            let offset = instruction(self)?;
-            let ip = self.instruction_pointer.get() + U256::from(offset);
-            self.instruction_pointer.set(ip);
+            let opcode = code.get(self.instruction_pointer).copied().unwrap_or(STOP);
+            if !defined[opcode as usize] {
+                return Err(anyhow!("unknown opcode"));
+            }
+            let step = lookup(opcode);
+            self.gas.record_cost(step.static_gas)?;
+            // Custom instructions may rely on their declared arity.
+            let (inputs, outputs) = (step.inputs as usize, step.outputs as usize);
+            if self.stack.len() < inputs {
+                return Err(anyhow!("stack underflow"));
+            } else if self.stack.len() - inputs + outputs > self.stack.size {
+                return Err(anyhow!("stack overflow"));
+            }
+            self.instruction_pointer += (step.instruction)(self)?;
         }
 
         Ok(self.return_data_buffer.borrow().clone())
     }
 }
 
+/// What the dispatch loop needs to know about an opcode. Unknown opcodes
+/// run an instruction that fails.
+#[derive(Clone, Copy)]
+struct Step {
+    static_gas: u64,
+    inputs: u8,
+    outputs: u8,
+    instruction: Instruction,
+}
+
+const UNKNOWN: Step = Step {
+    static_gas: 0,
+    inputs: 0,
+    outputs: 0,
+    instruction: control::unknown,
+};
+
+/// The opcodes of
+/// [`InstructionTable::standard`](crate::instructions::table::InstructionTable::standard).
+static STANDARD: [Step; 256] = {
+    let mut standard = [UNKNOWN; 256];
+    let mut op = 0;
+    while op < 256 {
+        if OPCODE_NAMES[op].is_some() {
+            let (inputs, outputs) = opcode::stack_io(op as u8);
+            standard[op] = Step {
+                static_gas: gas::static_gas(op as u8),
+                inputs,
+                outputs,
+                instruction: INSTRUCTIONS[op],
+            };
+        }
+        op += 1;
+    }
+    standard
+};
+
+/// The number of forks in [`SpecId`].
+const SPECS: usize = SpecId::Osaka as usize + 1;
+
+/// Whether each opcode is defined under each fork, in legacy code and in EOF
+/// code. Each format has opcodes the other doesn't.
+const DEFINED: [[[bool; 256]; 2]; SPECS] = {
+    let mut defined = [[[false; 256]; 2]; SPECS];
+    let mut op = 0;
+    while op < 256 {
+        let mut spec = opcode::introduced_in(op as u8) as usize;
+        while spec < SPECS {
+            defined[spec][0][op] = !eof::is_eof_only(op as u8);
+            defined[spec][1][op] = eof::is_valid(op as u8);
+            spec += 1;
+        }
+        op += 1;
+    }
+    defined
+};
+
 #[cfg(test)]
 mod tests {
-    use alloy_primitives::Bytes;
+    use std::rc::Rc;
+
+    use alloy_primitives::{Bytes, U256};
     use pretty_assertions::assert_eq;
 
-    use crate::Contract;
+    use crate::{
+        env::Env,
+        eof::{ContainerKind, Eof},
+        spec::SpecId,
+        Contract,
+    };
 
     use super::Interpreter;
 
@@ -100,6 +307,24 @@ mod tests {
         assert_eq!("0x2a".parse::<Bytes>().unwrap(), result);
     }
 
+    #[test]
+    fn rejects_opcodes_from_later_forks() {
+        // PUSH0
+        // STOP
+        let run = |spec| {
+            let bytes = "0x5f00".parse().unwrap();
+            let contract = Box::new(Contract::new(Default::default(), bytes, Default::default()));
+            let mut interpreter = Interpreter::new(contract);
+            let mut env = Env::default();
+            env.cfg.spec = spec;
+            interpreter.env = Rc::new(env);
+            interpreter.execute()
+        };
+        assert_eq!(true, run(SpecId::Shanghai).is_ok());
+        let err = run(SpecId::London).unwrap_err();
+        assert_eq!("unknown opcode", err.to_string());
+    }
+
     #[test]
     fn returns_four_squared() {
         //             # stack
@@ -147,4 +372,81 @@ mod tests {
         let result = interpreter.execute().expect("should finish execution");
         assert_eq!("0x10".parse::<Bytes>().unwrap(), result);
     }
+
+    fn eof_interpreter(container: &str) -> Interpreter {
+        let bytes: Bytes = container.parse().unwrap();
+        Eof::decode_valid(bytes.clone(), ContainerKind::Runtime).expect("should be valid");
+        let contract = Box::new(Contract::new(Default::default(), bytes, Default::default()));
+        Interpreter::new(contract)
+    }
+
+    #[test]
+    fn runs_eof_functions() {
+        // Section 0:
+        // PUSH1 0x03
+        // CALLF 0x0001
+        // DATALOADN 0x0000
+        // ADD
+        // PUSH0
+        // MSTORE
+        // PUSH1 0x20
+        // PUSH0
+        // RETURN
+        //
+        // Section 1, doubling its input:
+        // DUP1
+        // ADD
+        // RETF
+        //
+        // Data: 5 as a word.
+        let mut interpreter = eof_interpreter(
+            "0xef0001010008020002000f0003ff00200000800002010100026003e30001d10000015f5260205ff3\
+             8001e40000000000000000000000000000000000000000000000000000000000000005",
+        );
+        let result = interpreter.execute().expect("should finish execution");
+        assert_eq!(U256::from(11), U256::from_be_slice(&result));
+        assert_eq!(true, interpreter.return_stack.is_empty());
+    }
+
+    #[test]
+    fn runs_eof_loops() {
+        // PUSH1 0x05
+        // loop:
+        // PUSH1 0x01
+        // SWAP1
+        // SUB
+        // DUP1
+        // RJUMPI loop
+        // PUSH0
+        // MSTORE
+        // PUSH1 0x20
+        // PUSH0
+        // RETURN
+        let mut interpreter = eof_interpreter(
+            "0xef00010100040200010010ff0000000080000260056001900380e1fff85f5260205ff3",
+        );
+        let result = interpreter.execute().expect("should finish execution");
+        assert_eq!(Bytes::from([0; 32]), result);
+        assert_eq!(
+            3 + 5 * (3 + 3 + 3 + 3 + 4) + 2 + 6 + 3 + 2,
+            interpreter.gas.spent()
+        );
+    }
+
+    #[test]
+    fn separates_eof_and_legacy_opcodes() {
+        // PC, STOP in an unvalidated container.
+        let bytes = "0xef00010100040200010002ff000000008000015800"
+            .parse()
+            .unwrap();
+        let contract = Box::new(Contract::new(Default::default(), bytes, Default::default()));
+        let err = Interpreter::new(contract).execute().unwrap_err();
+        assert_eq!("unknown opcode", err.to_string());
+
+        // RJUMP 0x0000, STOP in legacy code.
+        let bytes = "0xe0000000".parse().unwrap();
+        let contract = Box::new(Contract::new(Default::default(), bytes, Default::default()));
+        let err = Interpreter::new(contract).execute().unwrap_err();
+        assert_eq!("unknown opcode", err.to_string());
+    }
 }
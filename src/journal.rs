@@ -0,0 +1,567 @@
+use std::collections::{HashMap, HashSet};
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use eyre::anyhow;
+
+use crate::state::{Account, Log, State};
+
+/// A single undoable change to the world state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JournalEntry {
+    /// An account that did not exist was created.
+    AccountCreated {
+        address: Address,
+    },
+    /// An account was marked warm (EIP-2929).
+    AccountWarmed {
+        address: Address,
+    },
+    /// A storage slot was marked warm (EIP-2929).
+    StorageWarmed {
+        address: Address,
+        key: U256,
+    },
+    BalanceChanged {
+        address: Address,
+        previous: U256,
+    },
+    BalanceTransferred {
+        from: Address,
+        to: Address,
+        value: U256,
+    },
+    NonceChanged {
+        address: Address,
+        previous: u64,
+    },
+    CodeChanged {
+        address: Address,
+        previous: Bytes,
+    },
+    StorageChanged {
+        address: Address,
+        key: U256,
+        previous: U256,
+    },
+    TransientStorageChanged {
+        address: Address,
+        key: U256,
+        previous: U256,
+    },
+    /// A contract was deployed at an address.
+    ContractCreated {
+        address: Address,
+    },
+    /// An account ran `SELFDESTRUCT` for the first time in the transaction.
+    SelfDestructed {
+        address: Address,
+    },
+    /// An account was touched for the first time in the transaction
+    /// (EIP-161).
+    AccountTouched {
+        address: Address,
+    },
+}
+
+/// A position in the journal that changes can be reverted to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    entries: usize,
+    logs: usize,
+}
+
+/// The world state plus a journal of every change made to it during the
+/// current transaction, so that the changes made by a frame can be undone
+/// when the frame reverts or halts exceptionally.
+///
+/// Every frame should take a [`Checkpoint`] before running and then either
+/// [`commit`](JournaledState::commit) or [`revert`](JournaledState::revert)
+/// it.
+#[derive(Clone, Debug, Default)]
+pub struct JournaledState {
+    pub state: State,
+    pub transient_storage: HashMap<(Address, U256), U256>,
+    pub logs: Vec<Log>,
+    pub warm_addresses: HashSet<Address>,
+    pub warm_slots: HashSet<(Address, U256)>,
+    /// The value of each written storage slot at the start of the
+    /// transaction. Needed to price `SSTORE` (EIP-2200).
+    original_storage: HashMap<(Address, U256), U256>,
+    /// The contracts deployed during the transaction (EIP-6780).
+    created: HashSet<Address>,
+    /// The accounts to delete at the end of the transaction.
+    selfdestructed: HashSet<Address>,
+    /// The accounts a message or a state change went to during the
+    /// transaction. Those left empty are deleted (EIP-161).
+    touched: HashSet<Address>,
+    entries: Vec<JournalEntry>,
+    depth: usize,
+}
+
+impl JournaledState {
+    pub fn new(state: State) -> Self {
+        Self {
+            state,
+            ..Default::default()
+        }
+    }
+
+    /// The number of open checkpoints.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.depth += 1;
+        Checkpoint {
+            entries: self.entries.len(),
+            logs: self.logs.len(),
+        }
+    }
+
+    /// Keeps the changes made since `checkpoint`. They stay in the journal so
+    /// that an enclosing checkpoint can still revert them.
+    pub fn commit(&mut self, _checkpoint: Checkpoint) {
+        self.depth -= 1;
+    }
+
+    /// Undoes every change made since `checkpoint`, in reverse order.
+    pub fn revert(&mut self, checkpoint: Checkpoint) {
+        self.depth -= 1;
+        self.logs.truncate(checkpoint.logs);
+        while self.entries.len() > checkpoint.entries {
+            let entry = self.entries.pop().expect("should pop a journal entry");
+            self.undo(entry);
+        }
+    }
+
+    fn undo(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::AccountCreated { address } => {
+                self.state.remove(&address);
+            }
+            JournalEntry::AccountWarmed { address } => {
+                self.warm_addresses.remove(&address);
+            }
+            JournalEntry::StorageWarmed { address, key } => {
+                self.warm_slots.remove(&(address, key));
+            }
+            JournalEntry::BalanceChanged { address, previous } => {
+                self.account_mut(address).balance = previous;
+            }
+            JournalEntry::BalanceTransferred { from, to, value } => {
+                // Undo in reverse order in case `from == to`.
+                self.account_mut(to).balance -= value;
+                self.account_mut(from).balance += value;
+            }
+            JournalEntry::NonceChanged { address, previous } => {
+                self.account_mut(address).nonce = previous;
+            }
+            JournalEntry::CodeChanged { address, previous } => {
+                self.account_mut(address).code = previous;
+            }
+            JournalEntry::StorageChanged {
+                address,
+                key,
+                previous,
+            } => {
+                let storage = &mut self.account_mut(address).storage;
+                if previous.is_zero() {
+                    storage.remove(&key);
+                } else {
+                    storage.insert(key, previous);
+                }
+            }
+            JournalEntry::TransientStorageChanged {
+                address,
+                key,
+                previous,
+            } => {
+                if previous.is_zero() {
+                    self.transient_storage.remove(&(address, key));
+                } else {
+                    self.transient_storage.insert((address, key), previous);
+                }
+            }
+            JournalEntry::ContractCreated { address } => {
+                self.created.remove(&address);
+            }
+            JournalEntry::SelfDestructed { address } => {
+                self.selfdestructed.remove(&address);
+            }
+            JournalEntry::AccountTouched { address } => {
+                self.touched.remove(&address);
+            }
+        }
+    }
+
+    /// Clears everything scoped to a single transaction: the journal, the
+    /// logs, transient storage and the warm sets. Returns the logs.
+    pub fn finish_transaction(&mut self) -> Vec<Log> {
+        self.entries.clear();
+        self.depth = 0;
+        self.transient_storage.clear();
+        self.warm_addresses.clear();
+        self.warm_slots.clear();
+        self.original_storage.clear();
+        self.created.clear();
+        self.selfdestructed.clear();
+        self.touched.clear();
+        std::mem::take(&mut self.logs)
+    }
+
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.state.get(address)
+    }
+
+    pub fn exists(&self, address: &Address) -> bool {
+        self.state.contains_key(address)
+    }
+
+    /// Returns the account at `address`, creating it if it does not exist.
+    /// Every change to an account goes through here, which touches it.
+    pub fn load_account(&mut self, address: Address) -> &mut Account {
+        if !self.state.contains_key(&address) {
+            self.entries.push(JournalEntry::AccountCreated { address });
+        }
+        self.touch(address);
+        self.state.entry(address).or_default()
+    }
+
+    /// Marks `address` as touched, even if nothing about it changes.
+    pub fn touch(&mut self, address: Address) {
+        if self.touched.insert(address) {
+            self.entries.push(JournalEntry::AccountTouched { address });
+        }
+    }
+
+    fn account_mut(&mut self, address: Address) -> &mut Account {
+        self.state
+            .get_mut(&address)
+            .expect("journaled account should exist")
+    }
+
+    pub fn balance(&self, address: &Address) -> U256 {
+        self.account(address).map(|a| a.balance).unwrap_or_default()
+    }
+
+    pub fn nonce(&self, address: &Address) -> u64 {
+        self.account(address).map(|a| a.nonce).unwrap_or_default()
+    }
+
+    pub fn code(&self, address: &Address) -> Bytes {
+        self.account(address)
+            .map(|a| a.code.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn code_hash(&self, address: &Address) -> B256 {
+        self.account(address)
+            .map(Account::code_hash)
+            .unwrap_or_default()
+    }
+
+    pub fn set_balance(&mut self, address: Address, balance: U256) {
+        let previous = self.load_account(address).balance;
+        self.entries
+            .push(JournalEntry::BalanceChanged { address, previous });
+        self.account_mut(address).balance = balance;
+    }
+
+    pub fn transfer(&mut self, from: Address, to: Address, value: U256) -> eyre::Result<()> {
+        if self.balance(&from) < value {
+            return Err(anyhow!("insufficient balance"));
+        }
+
+        self.load_account(from);
+        self.load_account(to);
+        self.entries
+            .push(JournalEntry::BalanceTransferred { from, to, value });
+        self.account_mut(from).balance -= value;
+        self.account_mut(to).balance += value;
+        Ok(())
+    }
+
+    pub fn increment_nonce(&mut self, address: Address) -> eyre::Result<()> {
+        let previous = self.load_account(address).nonce;
+        let nonce = previous
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("nonce overflow"))?;
+        self.entries
+            .push(JournalEntry::NonceChanged { address, previous });
+        self.account_mut(address).nonce = nonce;
+        Ok(())
+    }
+
+    pub fn set_code(&mut self, address: Address, code: Bytes) {
+        let previous = std::mem::replace(&mut self.load_account(address).code, code);
+        self.entries
+            .push(JournalEntry::CodeChanged { address, previous });
+    }
+
+    /// Marks `address` as warm. Returns whether it was cold.
+    pub fn warm_address(&mut self, address: Address) -> bool {
+        let is_cold = self.warm_addresses.insert(address);
+        if is_cold {
+            self.entries.push(JournalEntry::AccountWarmed { address });
+        }
+        is_cold
+    }
+
+    /// Marks the storage slot `key` of `address` as warm. Returns whether it
+    /// was cold.
+    pub fn warm_slot(&mut self, address: Address, key: U256) -> bool {
+        let is_cold = self.warm_slots.insert((address, key));
+        if is_cold {
+            self.entries
+                .push(JournalEntry::StorageWarmed { address, key });
+        }
+        is_cold
+    }
+
+    pub fn sload(&self, address: &Address, key: U256) -> U256 {
+        self.account(address)
+            .and_then(|a| a.storage.get(&key).copied())
+            .unwrap_or_default()
+    }
+
+    /// The value of a storage slot at the start of the transaction.
+    pub fn original_storage(&self, address: &Address, key: U256) -> U256 {
+        self.original_storage
+            .get(&(*address, key))
+            .copied()
+            .unwrap_or_else(|| self.sload(address, key))
+    }
+
+    /// Writes a storage slot and returns its previous value.
+    pub fn sstore(&mut self, address: Address, key: U256, value: U256) -> U256 {
+        let previous = self.sload(&address, key);
+        self.original_storage
+            .entry((address, key))
+            .or_insert(previous);
+        self.load_account(address);
+        self.entries.push(JournalEntry::StorageChanged {
+            address,
+            key,
+            previous,
+        });
+        let storage = &mut self.account_mut(address).storage;
+        if value.is_zero() {
+            storage.remove(&key);
+        } else {
+            storage.insert(key, value);
+        }
+        previous
+    }
+
+    pub fn tload(&self, address: &Address, key: U256) -> U256 {
+        self.transient_storage
+            .get(&(*address, key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn tstore(&mut self, address: Address, key: U256, value: U256) {
+        let previous = if value.is_zero() {
+            self.transient_storage.remove(&(address, key))
+        } else {
+            self.transient_storage.insert((address, key), value)
+        };
+        self.entries.push(JournalEntry::TransientStorageChanged {
+            address,
+            key,
+            previous: previous.unwrap_or_default(),
+        });
+    }
+
+    pub fn log(&mut self, log: Log) {
+        self.logs.push(log);
+    }
+
+    /// Records that a contract was deployed at `address` in this transaction.
+    pub fn mark_created(&mut self, address: Address) {
+        if self.created.insert(address) {
+            self.entries.push(JournalEntry::ContractCreated { address });
+        }
+    }
+
+    /// Whether a contract was deployed at `address` in this transaction.
+    pub fn is_created(&self, address: &Address) -> bool {
+        self.created.contains(address)
+    }
+
+    /// Schedules `address` for deletion at the end of the transaction,
+    /// zeroing its balance. Returns whether it was scheduled already.
+    pub fn selfdestruct(&mut self, address: Address) -> bool {
+        self.set_balance(address, U256::ZERO);
+        let is_new = self.selfdestructed.insert(address);
+        if is_new {
+            self.entries.push(JournalEntry::SelfDestructed { address });
+        }
+        !is_new
+    }
+
+    /// Deletes the accounts that ran `SELFDESTRUCT`. Called once the
+    /// transaction has finished executing.
+    pub fn remove_selfdestructed(&mut self) {
+        for address in std::mem::take(&mut self.selfdestructed) {
+            self.state.remove(&address);
+        }
+    }
+
+    /// Deletes the touched accounts that are empty (EIP-161). Called once
+    /// the transaction has finished executing.
+    pub fn remove_touched_empty(&mut self) {
+        for address in std::mem::take(&mut self.touched) {
+            if self.state.get(&address).is_some_and(Account::is_empty) {
+                self.state.remove(&address);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, Bytes, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::state::{Account, Log, State};
+
+    use super::JournaledState;
+
+    fn address(byte: u8) -> Address {
+        Address::with_last_byte(byte)
+    }
+
+    fn journal() -> JournaledState {
+        let mut state = State::new();
+        state.insert(
+            address(1),
+            Account {
+                balance: U256::from(100),
+                ..Default::default()
+            },
+        );
+        JournaledState::new(state)
+    }
+
+    #[test]
+    fn reverts_storage() {
+        let mut journal = journal();
+        journal.sstore(address(1), U256::from(1), U256::from(10));
+
+        let checkpoint = journal.checkpoint();
+        journal.sstore(address(1), U256::from(1), U256::from(20));
+        journal.sstore(address(1), U256::from(2), U256::from(30));
+        assert_eq!(U256::from(20), journal.sload(&address(1), U256::from(1)));
+        journal.revert(checkpoint);
+
+        assert_eq!(U256::from(10), journal.sload(&address(1), U256::from(1)));
+        assert_eq!(U256::ZERO, journal.sload(&address(1), U256::from(2)));
+        assert_eq!(
+            U256::ZERO,
+            journal.original_storage(&address(1), U256::from(1))
+        );
+    }
+
+    #[test]
+    fn reverts_transfers_and_created_accounts() {
+        let mut journal = journal();
+        let checkpoint = journal.checkpoint();
+        journal
+            .transfer(address(1), address(2), U256::from(40))
+            .expect("should transfer");
+        journal.increment_nonce(address(1)).expect("should bump");
+        journal.set_code(address(2), Bytes::from_static(&[0x00]));
+        assert_eq!(U256::from(60), journal.balance(&address(1)));
+        assert_eq!(U256::from(40), journal.balance(&address(2)));
+        journal.revert(checkpoint);
+
+        assert_eq!(U256::from(100), journal.balance(&address(1)));
+        assert_eq!(0, journal.nonce(&address(1)));
+        assert_eq!(false, journal.exists(&address(2)));
+    }
+
+    #[test]
+    fn fails_transfer_without_balance() {
+        let mut journal = journal();
+        let err = journal
+            .transfer(address(1), address(2), U256::from(101))
+            .unwrap_err();
+        assert_eq!("insufficient balance", err.to_string());
+        assert_eq!(true, journal.entries().is_empty());
+    }
+
+    #[test]
+    fn reverts_inner_frame_only() {
+        let mut journal = journal();
+        let outer = journal.checkpoint();
+        journal.tstore(address(1), U256::from(1), U256::from(1));
+        journal.warm_slot(address(1), U256::from(1));
+        journal.log(Log {
+            address: address(1),
+            ..Default::default()
+        });
+
+        let inner = journal.checkpoint();
+        journal.tstore(address(1), U256::from(1), U256::from(2));
+        journal.warm_address(address(3));
+        journal.log(Log {
+            address: address(3),
+            ..Default::default()
+        });
+        assert_eq!(2, journal.depth());
+        journal.revert(inner);
+        journal.commit(outer);
+
+        assert_eq!(0, journal.depth());
+        assert_eq!(U256::from(1), journal.tload(&address(1), U256::from(1)));
+        assert_eq!(
+            true,
+            journal.warm_slots.contains(&(address(1), U256::from(1)))
+        );
+        assert_eq!(false, journal.warm_addresses.contains(&address(3)));
+        assert_eq!(1, journal.logs.len());
+        assert_eq!(address(1), journal.logs[0].address);
+    }
+
+    #[test]
+    fn reverts_committed_inner_frame_with_outer_frame() {
+        let mut journal = journal();
+        let outer = journal.checkpoint();
+        journal.set_balance(address(1), U256::from(50));
+
+        let inner = journal.checkpoint();
+        journal.sstore(address(1), U256::from(1), U256::from(1));
+        journal.warm_address(address(1));
+        journal.commit(inner);
+        assert_eq!(U256::from(1), journal.sload(&address(1), U256::from(1)));
+
+        journal.revert(outer);
+        assert_eq!(U256::from(100), journal.balance(&address(1)));
+        assert_eq!(U256::ZERO, journal.sload(&address(1), U256::from(1)));
+        assert_eq!(true, journal.warm_addresses.is_empty());
+        assert_eq!(true, journal.entries().is_empty());
+    }
+
+    #[test]
+    fn finishes_transaction() {
+        let mut journal = journal();
+        journal.tstore(address(1), U256::from(1), U256::from(1));
+        journal.sstore(address(1), U256::from(1), U256::from(1));
+        journal.log(Default::default());
+        let logs = journal.finish_transaction();
+
+        assert_eq!(1, logs.len());
+        assert_eq!(U256::ZERO, journal.tload(&address(1), U256::from(1)));
+        assert_eq!(U256::from(1), journal.sload(&address(1), U256::from(1)));
+        assert_eq!(
+            U256::from(1),
+            journal.original_storage(&address(1), U256::from(1))
+        );
+    }
+}
@@ -1,15 +1,35 @@
+pub mod alloc;
+pub mod block;
+pub mod blocktest;
 pub mod bytecode;
+pub mod chainspec;
 pub mod cli;
 pub mod constants;
 pub mod contract;
+pub mod env;
+pub mod envelope;
+pub mod eof;
+pub mod frame;
+pub mod gas;
 pub mod instructions;
 pub mod interpreter;
+pub mod journal;
 pub mod memory;
+pub mod precompiles;
+pub mod receipt;
+pub mod spec;
 pub mod stack;
+pub mod state;
+pub mod statetest;
+pub mod t8n;
+pub mod transaction;
+pub mod trie;
 pub mod utils;
 
 pub use bytecode::Bytecode;
 pub use contract::Contract;
 pub use interpreter::Interpreter;
+pub use journal::JournaledState;
 pub use memory::Memory;
 pub use stack::Stack;
+pub use transaction::transact;
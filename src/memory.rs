@@ -66,6 +66,11 @@ impl Memory {
         }
     }
 
+    /// Copies `size` bytes from `src` to `dst`. The ranges may overlap.
+    pub fn copy_within(&mut self, src: usize, dst: usize, size: usize) {
+        self.buffer.copy_within(src..src + size, dst);
+    }
+
     pub fn set_byte(&mut self, addr: usize, byte: u8) {
         self.set(addr, &[byte]);
     }
@@ -0,0 +1,193 @@
+use eyre::anyhow;
+
+use super::{charge, PrecompileOutput, PrecompileResult};
+
+/// The exact size of the input: the rounds, the state, the message block,
+/// the offset counters and the final block flag.
+const INPUT_LEN: usize = 4 + 64 + 128 + 16 + 1;
+/// The cost of each round.
+const GAS_PER_ROUND: u64 = 1;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The `BLAKE2F` precompile (0x09), which runs the BLAKE2b compression
+/// function `F` for a caller-chosen number of rounds (EIP-152).
+pub fn run(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    if input.len() != INPUT_LEN {
+        return Err(anyhow!("invalid input length"));
+    }
+    let rounds = u32::from_be_bytes(input[..4].try_into().expect("should be 4 bytes"));
+    let cost = GAS_PER_ROUND * rounds as u64;
+    charge(cost, gas_limit)?;
+
+    let last = match input[212] {
+        0 => false,
+        1 => true,
+        _ => return Err(anyhow!("invalid final block flag")),
+    };
+    let mut h = [0u64; 8];
+    for (word, bytes) in h.iter_mut().zip(input[4..68].chunks_exact(8)) {
+        *word = read_u64(bytes);
+    }
+    let mut m = [0u64; 16];
+    for (word, bytes) in m.iter_mut().zip(input[68..196].chunks_exact(8)) {
+        *word = read_u64(bytes);
+    }
+    let t = [read_u64(&input[196..204]), read_u64(&input[204..212])];
+
+    compress(rounds as usize, &mut h, &m, t, last);
+
+    let output: Vec<u8> = h.iter().flat_map(|word| word.to_le_bytes()).collect();
+    Ok(PrecompileOutput::new(cost, output.into()))
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().expect("should be 8 bytes"))
+}
+
+/// The compression function `F` from RFC 7693, section 3.2.
+fn compress(rounds: usize, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], last: bool) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if last {
+        v[14] = !v[14];
+    }
+
+    for round in 0..rounds {
+        let s = &SIGMA[round % 10];
+        mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// The mixing function `G` from RFC 7693, section 3.1.
+#[allow(clippy::too_many_arguments)]
+fn mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::run;
+
+    fn input(s: &str) -> Bytes {
+        s.parse().unwrap()
+    }
+
+    /// EIP-152 test vector 4, with `rounds` and the final block flag
+    /// replaced.
+    fn vector(rounds: &str, last: &str) -> Bytes {
+        input(&format!(
+            "0x{rounds}\
+            48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+            d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b\
+            6162630000000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000000000000\
+            03000000000000000000000000000000{last}"
+        ))
+    }
+
+    #[test]
+    fn compresses() {
+        // Vector 4: twelve rounds hash "abc".
+        let output = run(&vector("0000000c", "01"), u64::MAX).expect("should compress");
+        let expected = input(
+            "0x\
+            ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+            7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+        );
+        assert_eq!(expected, output.bytes);
+        assert_eq!(12, output.gas_used);
+
+        // Vector 5: not the final block.
+        let output = run(&vector("0000000c", "00"), u64::MAX).expect("should compress");
+        let expected = input(
+            "0x\
+            75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d28752\
+            98743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735",
+        );
+        assert_eq!(expected, output.bytes);
+
+        // Vector 3: no rounds at all.
+        let output = run(&vector("00000000", "01"), u64::MAX).expect("should compress");
+        let expected = input(
+            "0x\
+            08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+            d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b",
+        );
+        assert_eq!(expected, output.bytes);
+        assert_eq!(0, output.gas_used);
+
+        // Vector 6: a single round.
+        let output = run(&vector("00000001", "01"), u64::MAX).expect("should compress");
+        let expected = input(
+            "0x\
+            b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fb\
+            a551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421",
+        );
+        assert_eq!(expected, output.bytes);
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        // Vectors 0 to 2: wrong lengths and an invalid final block flag.
+        assert_eq!(true, run(&[], u64::MAX).is_err());
+        let valid = vector("0000000c", "01");
+        assert_eq!(true, run(&valid[..212], u64::MAX).is_err());
+        assert_eq!(
+            true,
+            run(&[valid.as_ref(), &[0]].concat(), u64::MAX).is_err()
+        );
+        assert_eq!(true, run(&vector("0000000c", "02"), u64::MAX).is_err());
+
+        // Each round costs one gas.
+        assert_eq!(true, run(&valid, 11).is_err());
+    }
+}
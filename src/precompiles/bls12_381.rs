@@ -0,0 +1,482 @@
+//! The BLS12-381 precompiles from EIP-2537.
+//!
+//! Field elements are encoded as 64 bytes, big endian, with the top 16 bytes
+//! zero. Points are encoded as their affine coordinates, with all zeroes
+//! for the point at infinity, and elements of the quadratic extension as
+//! `c0 || c1`. Scalars are 32 bytes, big endian, and not reduced.
+
+use std::ptr;
+
+use alloy_primitives::Bytes;
+use blst::{
+    blst_bendian_from_fp, blst_final_exp, blst_fp, blst_fp12, blst_fp12_is_one, blst_fp12_mul,
+    blst_fp12_one, blst_fp2, blst_fp_from_bendian, blst_map_to_g1, blst_map_to_g2,
+    blst_miller_loop, blst_p1, blst_p1_add_or_double, blst_p1_affine, blst_p1_affine_in_g1,
+    blst_p1_affine_on_curve, blst_p1_from_affine, blst_p1_mult, blst_p1_to_affine, blst_p2,
+    blst_p2_add_or_double, blst_p2_affine, blst_p2_affine_in_g2, blst_p2_affine_on_curve,
+    blst_p2_from_affine, blst_p2_mult, blst_p2_to_affine, blst_scalar, blst_scalar_from_bendian,
+};
+use eyre::anyhow;
+
+use super::{charge, PrecompileOutput, PrecompileResult};
+
+const FP_LEN: usize = 64;
+/// The meaningful bytes of an encoded field element.
+const FP_VALUE_LEN: usize = 48;
+const FP2_LEN: usize = 2 * FP_LEN;
+const G1_LEN: usize = 2 * FP_LEN;
+const G2_LEN: usize = 2 * FP2_LEN;
+const SCALAR_LEN: usize = 32;
+
+/// The base field modulus, big endian.
+const MODULUS: [u8; FP_VALUE_LEN] = alloy_primitives::hex!(
+    "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab"
+);
+
+pub const G1_ADD_GAS: u64 = 375;
+pub const G1_MSM_GAS: u64 = 12_000;
+pub const G2_ADD_GAS: u64 = 600;
+pub const G2_MSM_GAS: u64 = 22_500;
+pub const PAIRING_BASE_GAS: u64 = 37_700;
+pub const PAIRING_PAIR_GAS: u64 = 32_600;
+pub const MAP_FP_TO_G1_GAS: u64 = 5_500;
+pub const MAP_FP2_TO_G2_GAS: u64 = 23_800;
+
+/// The discount, in thousandths, for a G1 MSM of `k` pairs is found at
+/// index `k - 1`. Larger MSMs get the last discount.
+const G1_MSM_DISCOUNTS: [u16; 128] = [
+    1000, 949, 848, 797, 764, 750, 738, 728, 719, 712, 705, 698, 692, 687, 682, 677, 673, 669, 665,
+    661, 658, 654, 651, 648, 645, 642, 640, 637, 635, 632, 630, 627, 625, 623, 621, 619, 617, 615,
+    613, 611, 609, 608, 606, 604, 603, 601, 599, 598, 596, 595, 593, 592, 591, 589, 588, 586, 585,
+    584, 582, 581, 580, 579, 577, 576, 575, 574, 573, 572, 570, 569, 568, 567, 566, 565, 564, 563,
+    562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 551, 550, 549, 548, 547, 547, 546, 545,
+    544, 543, 542, 541, 540, 540, 539, 538, 537, 536, 536, 535, 534, 533, 532, 532, 531, 530, 529,
+    528, 528, 527, 526, 525, 525, 524, 523, 522, 522, 521, 520, 520, 519,
+];
+
+/// Like [`G1_MSM_DISCOUNTS`], for G2 MSMs.
+const G2_MSM_DISCOUNTS: [u16; 128] = [
+    1000, 1000, 923, 884, 855, 832, 812, 796, 782, 770, 759, 749, 740, 732, 724, 717, 711, 704,
+    699, 693, 688, 683, 679, 674, 670, 666, 663, 659, 655, 652, 649, 646, 643, 640, 637, 634, 632,
+    629, 627, 624, 622, 620, 618, 615, 613, 611, 609, 607, 606, 604, 602, 600, 598, 597, 595, 593,
+    592, 590, 589, 587, 586, 584, 583, 582, 580, 579, 578, 576, 575, 574, 573, 571, 570, 569, 568,
+    567, 566, 565, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 552, 551, 550, 549,
+    548, 547, 546, 545, 545, 544, 543, 542, 541, 541, 540, 539, 538, 537, 537, 536, 535, 535, 534,
+    533, 532, 532, 531, 530, 530, 529, 528, 528, 527, 526, 526, 525, 524, 524,
+];
+
+/// The `BLS12_G1ADD` precompile (0x0B). Points are not checked to be in the
+/// subgroup.
+pub fn g1_add(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    charge(G1_ADD_GAS, gas_limit)?;
+    if input.len() != 2 * G1_LEN {
+        return Err(anyhow!("invalid input length"));
+    }
+    let a = read_g1(&input[..G1_LEN], false)?;
+    let b = read_g1(&input[G1_LEN..], false)?;
+
+    let mut sum = blst_p1::default();
+    // SAFETY: all pointers are valid for the duration of the calls.
+    unsafe {
+        let mut b_jacobian = blst_p1::default();
+        blst_p1_from_affine(&mut sum, &a);
+        blst_p1_from_affine(&mut b_jacobian, &b);
+        blst_p1_add_or_double(&mut sum, &sum, &b_jacobian);
+    }
+    Ok(PrecompileOutput::new(G1_ADD_GAS, write_g1(&sum)))
+}
+
+/// The `BLS12_G1MSM` precompile (0x0C), a multi-scalar multiplication of
+/// subgroup points.
+pub fn g1_msm(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let pair_len = G1_LEN + SCALAR_LEN;
+    if input.is_empty() || !input.len().is_multiple_of(pair_len) {
+        return Err(anyhow!("invalid input length"));
+    }
+    let k = input.len() / pair_len;
+    let cost = msm_cost(k, G1_MSM_GAS, &G1_MSM_DISCOUNTS);
+    charge(cost, gas_limit)?;
+
+    let mut sum = blst_p1::default();
+    for pair in input.chunks_exact(pair_len) {
+        let point = read_g1(&pair[..G1_LEN], true)?;
+        let scalar = read_scalar(&pair[G1_LEN..]);
+        // SAFETY: all pointers are valid for the duration of the calls.
+        unsafe {
+            let mut product = blst_p1::default();
+            blst_p1_from_affine(&mut product, &point);
+            blst_p1_mult(&mut product, &product, scalar.b.as_ptr(), 8 * SCALAR_LEN);
+            blst_p1_add_or_double(&mut sum, &sum, &product);
+        }
+    }
+    Ok(PrecompileOutput::new(cost, write_g1(&sum)))
+}
+
+/// The `BLS12_G2ADD` precompile (0x0D). Points are not checked to be in the
+/// subgroup.
+pub fn g2_add(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    charge(G2_ADD_GAS, gas_limit)?;
+    if input.len() != 2 * G2_LEN {
+        return Err(anyhow!("invalid input length"));
+    }
+    let a = read_g2(&input[..G2_LEN], false)?;
+    let b = read_g2(&input[G2_LEN..], false)?;
+
+    let mut sum = blst_p2::default();
+    // SAFETY: all pointers are valid for the duration of the calls.
+    unsafe {
+        let mut b_jacobian = blst_p2::default();
+        blst_p2_from_affine(&mut sum, &a);
+        blst_p2_from_affine(&mut b_jacobian, &b);
+        blst_p2_add_or_double(&mut sum, &sum, &b_jacobian);
+    }
+    Ok(PrecompileOutput::new(G2_ADD_GAS, write_g2(&sum)))
+}
+
+/// The `BLS12_G2MSM` precompile (0x0E), a multi-scalar multiplication of
+/// subgroup points.
+pub fn g2_msm(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let pair_len = G2_LEN + SCALAR_LEN;
+    if input.is_empty() || !input.len().is_multiple_of(pair_len) {
+        return Err(anyhow!("invalid input length"));
+    }
+    let k = input.len() / pair_len;
+    let cost = msm_cost(k, G2_MSM_GAS, &G2_MSM_DISCOUNTS);
+    charge(cost, gas_limit)?;
+
+    let mut sum = blst_p2::default();
+    for pair in input.chunks_exact(pair_len) {
+        let point = read_g2(&pair[..G2_LEN], true)?;
+        let scalar = read_scalar(&pair[G2_LEN..]);
+        // SAFETY: all pointers are valid for the duration of the calls.
+        unsafe {
+            let mut product = blst_p2::default();
+            blst_p2_from_affine(&mut product, &point);
+            blst_p2_mult(&mut product, &product, scalar.b.as_ptr(), 8 * SCALAR_LEN);
+            blst_p2_add_or_double(&mut sum, &sum, &product);
+        }
+    }
+    Ok(PrecompileOutput::new(cost, write_g2(&sum)))
+}
+
+/// The `BLS12_PAIRING_CHECK` precompile (0x0F). Returns one if the product of
+/// the pairings of each (G1, G2) pair in the input is one, zero otherwise.
+pub fn pairing(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let pair_len = G1_LEN + G2_LEN;
+    if input.is_empty() || !input.len().is_multiple_of(pair_len) {
+        return Err(anyhow!("invalid input length"));
+    }
+    let k = (input.len() / pair_len) as u64;
+    let cost = PAIRING_PAIR_GAS * k + PAIRING_BASE_GAS;
+    charge(cost, gas_limit)?;
+
+    // SAFETY: `blst_fp12_one` points to a static constant.
+    let mut product = unsafe { *blst_fp12_one() };
+    for pair in input.chunks_exact(pair_len) {
+        let p = read_g1(&pair[..G1_LEN], true)?;
+        let q = read_g2(&pair[G1_LEN..], true)?;
+        // SAFETY: all pointers are valid for the duration of the calls.
+        unsafe {
+            let mut loop_result = blst_fp12::default();
+            blst_miller_loop(&mut loop_result, &q, &p);
+            blst_fp12_mul(&mut product, &product, &loop_result);
+        }
+    }
+    // SAFETY: all pointers are valid for the duration of the calls.
+    let success = unsafe {
+        let mut result = blst_fp12::default();
+        blst_final_exp(&mut result, &product);
+        blst_fp12_is_one(&result)
+    };
+
+    let mut output = [0u8; 32];
+    output[31] = success as u8;
+    Ok(PrecompileOutput::new(cost, Bytes::copy_from_slice(&output)))
+}
+
+/// The `BLS12_MAP_FP_TO_G1` precompile (0x10).
+pub fn map_fp_to_g1(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    charge(MAP_FP_TO_G1_GAS, gas_limit)?;
+    if input.len() != FP_LEN {
+        return Err(anyhow!("invalid input length"));
+    }
+    let u = read_fp(input)?;
+
+    let mut point = blst_p1::default();
+    // SAFETY: all pointers are valid for the duration of the call, and a
+    // null `v` maps a single element.
+    unsafe { blst_map_to_g1(&mut point, &u, ptr::null()) };
+    Ok(PrecompileOutput::new(MAP_FP_TO_G1_GAS, write_g1(&point)))
+}
+
+/// The `BLS12_MAP_FP2_TO_G2` precompile (0x11).
+pub fn map_fp2_to_g2(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    charge(MAP_FP2_TO_G2_GAS, gas_limit)?;
+    if input.len() != FP2_LEN {
+        return Err(anyhow!("invalid input length"));
+    }
+    let u = read_fp2(input)?;
+
+    let mut point = blst_p2::default();
+    // SAFETY: all pointers are valid for the duration of the call, and a
+    // null `v` maps a single element.
+    unsafe { blst_map_to_g2(&mut point, &u, ptr::null()) };
+    Ok(PrecompileOutput::new(MAP_FP2_TO_G2_GAS, write_g2(&point)))
+}
+
+/// Prices a multi-scalar multiplication of `k` pairs.
+fn msm_cost(k: usize, multiplication_cost: u64, discounts: &[u16; 128]) -> u64 {
+    let discount = discounts[k.min(discounts.len()) - 1] as u64;
+    (k as u64)
+        .saturating_mul(multiplication_cost)
+        .saturating_mul(discount)
+        / 1000
+}
+
+/// Decodes a field element, rejecting non-zero padding and values not
+/// smaller than the modulus.
+fn read_fp(bytes: &[u8]) -> eyre::Result<blst_fp> {
+    let (padding, value) = bytes.split_at(FP_LEN - FP_VALUE_LEN);
+    if padding.iter().any(|byte| *byte != 0) {
+        return Err(anyhow!("invalid field element padding"));
+    }
+    // Both are big endian, so comparing bytes compares values.
+    if value >= &MODULUS[..] {
+        return Err(anyhow!("invalid field element"));
+    }
+    let mut fp = blst_fp::default();
+    // SAFETY: `value` holds exactly 48 bytes.
+    unsafe { blst_fp_from_bendian(&mut fp, value.as_ptr()) };
+    Ok(fp)
+}
+
+fn read_fp2(bytes: &[u8]) -> eyre::Result<blst_fp2> {
+    Ok(blst_fp2 {
+        fp: [read_fp(&bytes[..FP_LEN])?, read_fp(&bytes[FP_LEN..])?],
+    })
+}
+
+/// Decodes a G1 point, checking that it is on the curve and, if
+/// `subgroup_check` is set, in the prime order subgroup.
+fn read_g1(bytes: &[u8], subgroup_check: bool) -> eyre::Result<blst_p1_affine> {
+    let point = blst_p1_affine {
+        x: read_fp(&bytes[..FP_LEN])?,
+        y: read_fp(&bytes[FP_LEN..])?,
+    };
+    // SAFETY: `point` is a valid affine point.
+    unsafe {
+        if !blst_p1_affine_on_curve(&point) {
+            return Err(anyhow!("point not on curve"));
+        }
+        if subgroup_check && !blst_p1_affine_in_g1(&point) {
+            return Err(anyhow!("point not in subgroup"));
+        }
+    }
+    Ok(point)
+}
+
+/// Like [`read_g1`], for G2 points.
+fn read_g2(bytes: &[u8], subgroup_check: bool) -> eyre::Result<blst_p2_affine> {
+    let point = blst_p2_affine {
+        x: read_fp2(&bytes[..FP2_LEN])?,
+        y: read_fp2(&bytes[FP2_LEN..])?,
+    };
+    // SAFETY: `point` is a valid affine point.
+    unsafe {
+        if !blst_p2_affine_on_curve(&point) {
+            return Err(anyhow!("point not on curve"));
+        }
+        if subgroup_check && !blst_p2_affine_in_g2(&point) {
+            return Err(anyhow!("point not in subgroup"));
+        }
+    }
+    Ok(point)
+}
+
+fn read_scalar(bytes: &[u8]) -> blst_scalar {
+    let mut scalar = blst_scalar::default();
+    // SAFETY: `bytes` holds exactly 32 bytes.
+    unsafe { blst_scalar_from_bendian(&mut scalar, bytes.as_ptr()) };
+    scalar
+}
+
+fn write_fp(output: &mut [u8], fp: &blst_fp) {
+    // SAFETY: the output slice holds at least 48 bytes past the padding.
+    unsafe { blst_bendian_from_fp(output[FP_LEN - FP_VALUE_LEN..].as_mut_ptr(), fp) };
+}
+
+fn write_g1(point: &blst_p1) -> Bytes {
+    let mut affine = blst_p1_affine::default();
+    // SAFETY: all pointers are valid for the duration of the call. The point
+    // at infinity converts to all zeroes.
+    unsafe { blst_p1_to_affine(&mut affine, point) };
+    let mut output = vec![0u8; G1_LEN];
+    write_fp(&mut output[..FP_LEN], &affine.x);
+    write_fp(&mut output[FP_LEN..], &affine.y);
+    output.into()
+}
+
+fn write_g2(point: &blst_p2) -> Bytes {
+    let mut affine = blst_p2_affine::default();
+    // SAFETY: all pointers are valid for the duration of the call. The point
+    // at infinity converts to all zeroes.
+    unsafe { blst_p2_to_affine(&mut affine, point) };
+    let mut output = vec![0u8; G2_LEN];
+    for (i, fp) in [affine.x.fp, affine.y.fp].iter().flatten().enumerate() {
+        write_fp(&mut output[i * FP_LEN..(i + 1) * FP_LEN], fp);
+    }
+    output.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::{g1_add, g1_msm, g2_add, g2_msm, map_fp2_to_g2, map_fp_to_g1, pairing};
+
+    fn input(s: &str) -> Bytes {
+        s.parse().unwrap()
+    }
+
+    fn concat(parts: &[&Bytes]) -> Vec<u8> {
+        parts.iter().flat_map(|part| part.iter().copied()).collect()
+    }
+
+    const G1: &str = "0x\
+        0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0f\
+        c3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb\
+        0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4\
+        fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1";
+
+    const NEG_G1: &str = "0x\
+        0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0f\
+        c3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb\
+        00000000000000000000000000000000114d1d6855d545a8aa7d76c8cf2e21f2\
+        67816aef1db507c96655b9d5caac42364e6f38ba0ecb751bad54dcd6b939c2ca";
+
+    const G2: &str = "0x\
+        00000000000000000000000000000000024aa2b2f08f0a91260805272dc51051\
+        c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8\
+        0000000000000000000000000000000013e02b6052719f607dacd3a088274f65\
+        596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e\
+        000000000000000000000000000000000ce5d527727d6e118cc9cdc6da2e351a\
+        adfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801\
+        000000000000000000000000000000000606c4a02ea734cc32acd2b02bc28b99\
+        cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be";
+
+    /// The order of the subgroups.
+    const ORDER: &str = "0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+    const TWO: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+
+    #[test]
+    fn adds_g1_points() {
+        let g1 = input(G1);
+        let infinity = Bytes::from(vec![0; 128]);
+        let output = g1_add(&concat(&[&g1, &infinity]), u64::MAX).expect("should add");
+        assert_eq!(g1, output.bytes);
+        assert_eq!(375, output.gas_used);
+
+        let output = g1_add(&concat(&[&g1, &input(NEG_G1)]), u64::MAX).expect("should add");
+        assert_eq!(infinity, output.bytes);
+
+        let double = g1_add(&concat(&[&g1, &g1]), u64::MAX).expect("should add");
+        let product = g1_msm(&concat(&[&g1, &input(TWO)]), u64::MAX).expect("should multiply");
+        assert_eq!(double.bytes, product.bytes);
+        assert_eq!(12_000, product.gas_used);
+    }
+
+    #[test]
+    fn adds_g2_points() {
+        let g2 = input(G2);
+        let double = g2_add(&concat(&[&g2, &g2]), u64::MAX).expect("should add");
+        assert_eq!(600, double.gas_used);
+        let product = g2_msm(&concat(&[&g2, &input(TWO)]), u64::MAX).expect("should multiply");
+        assert_eq!(double.bytes, product.bytes);
+        assert_eq!(22_500, product.gas_used);
+
+        // Multiplying by the order gives the point at infinity.
+        let output = g2_msm(&concat(&[&g2, &input(ORDER)]), u64::MAX).expect("should multiply");
+        assert_eq!(Bytes::from(vec![0; 256]), output.bytes);
+    }
+
+    #[test]
+    fn prices_msm_with_discounts() {
+        let pair = concat(&[&input(G1), &input(TWO)]);
+        let output = g1_msm(&pair.repeat(2), u64::MAX).expect("should multiply");
+        assert_eq!(2 * 12_000 * 949 / 1000, output.gas_used);
+        let output = g1_msm(&pair.repeat(130), u64::MAX).expect("should multiply");
+        assert_eq!(130 * 12_000 * 519 / 1000, output.gas_used);
+        assert_eq!(true, g1_msm(&pair, 11_999).is_err());
+        assert_eq!(true, g1_msm(&[], u64::MAX).is_err());
+    }
+
+    #[test]
+    fn checks_pairings() {
+        let (g1, neg_g1, g2) = (input(G1), input(NEG_G1), input(G2));
+        let one = input("0x0000000000000000000000000000000000000000000000000000000000000001");
+
+        // e(P, Q) * e(-P, Q) == 1.
+        let output = pairing(&concat(&[&g1, &g2, &neg_g1, &g2]), u64::MAX).expect("should pair");
+        assert_eq!(one, output.bytes);
+        assert_eq!(2 * 32_600 + 37_700, output.gas_used);
+
+        let output = pairing(&concat(&[&g1, &g2]), u64::MAX).expect("should pair");
+        assert_eq!(Bytes::from(vec![0; 32]), output.bytes);
+        assert_eq!(true, pairing(&[], u64::MAX).is_err());
+    }
+
+    #[test]
+    fn maps_to_curve() {
+        let fp = input(
+            "0x\
+            0000000000000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000000000001",
+        );
+        let output = map_fp_to_g1(&fp, u64::MAX).expect("should map");
+        assert_eq!(5_500, output.gas_used);
+        // Mapped points are in the subgroup, so MSM accepts them.
+        assert_eq!(
+            true,
+            g1_msm(&concat(&[&output.bytes, &input(TWO)]), u64::MAX).is_ok()
+        );
+
+        let output = map_fp2_to_g2(&concat(&[&fp, &fp]), u64::MAX).expect("should map");
+        assert_eq!(23_800, output.gas_used);
+        assert_eq!(
+            true,
+            g2_msm(&concat(&[&output.bytes, &input(TWO)]), u64::MAX).is_ok()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        let g1 = input(G1);
+
+        // Not on the curve.
+        let mut off_curve = g1.to_vec();
+        off_curve[127] ^= 1;
+        assert_eq!(
+            true,
+            g1_add(&concat(&[&g1, &off_curve.into()]), u64::MAX).is_err()
+        );
+
+        // Non-zero padding.
+        let mut padded = g1.to_vec();
+        padded[0] = 1;
+        assert_eq!(
+            true,
+            g1_add(&concat(&[&g1, &padded.into()]), u64::MAX).is_err()
+        );
+
+        // The modulus itself is not a field element.
+        let modulus = input(
+            "0x\
+            000000000000000000000000000000001a0111ea397fe69a4b1ba7b6434bacd7\
+            64774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab",
+        );
+        assert_eq!(true, map_fp_to_g1(&modulus, u64::MAX).is_err());
+
+        assert_eq!(true, g1_add(&g1, u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use alloy_primitives::Bytes;
+use bn::{AffineG1, AffineG2, Fq, Fq2, Group, Gt, G1, G2};
+use eyre::anyhow;
+
+use super::{charge, right_pad, PrecompileOutput, PrecompileResult};
+
+/// The size of an encoded G1 point: two field elements.
+const G1_LEN: usize = 64;
+/// The size of an encoded G2 point: two elements of the quadratic extension.
+const G2_LEN: usize = 128;
+/// The size of a pairing input pair.
+const PAIR_LEN: usize = G1_LEN + G2_LEN;
+
+/// The `ECADD` precompile (0x06) priced as in EIP-196.
+pub fn add_byzantium(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    add(input, gas_limit, 500)
+}
+
+/// The `ECADD` precompile (0x06) priced as in EIP-1108.
+pub fn add_istanbul(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    add(input, gas_limit, 150)
+}
+
+/// The `ECMUL` precompile (0x07) priced as in EIP-196.
+pub fn mul_byzantium(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    mul(input, gas_limit, 40_000)
+}
+
+/// The `ECMUL` precompile (0x07) priced as in EIP-1108.
+pub fn mul_istanbul(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    mul(input, gas_limit, 6_000)
+}
+
+/// The `ECPAIRING` precompile (0x08) priced as in EIP-197.
+pub fn pairing_byzantium(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    pairing(input, gas_limit, 100_000, 80_000)
+}
+
+/// The `ECPAIRING` precompile (0x08) priced as in EIP-1108.
+pub fn pairing_istanbul(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    pairing(input, gas_limit, 45_000, 34_000)
+}
+
+/// Adds two G1 points. Missing input bytes are zeroes.
+fn add(input: &[u8], gas_limit: u64, cost: u64) -> PrecompileResult {
+    charge(cost, gas_limit)?;
+    let input = right_pad(input, 0, 2 * G1_LEN);
+    let a = read_g1(&input[..G1_LEN])?;
+    let b = read_g1(&input[G1_LEN..])?;
+    Ok(PrecompileOutput::new(cost, write_g1(a + b)))
+}
+
+/// Multiplies a G1 point by a scalar. Missing input bytes are zeroes.
+fn mul(input: &[u8], gas_limit: u64, cost: u64) -> PrecompileResult {
+    charge(cost, gas_limit)?;
+    let input = right_pad(input, 0, G1_LEN + 32);
+    let point = read_g1(&input[..G1_LEN])?;
+    // Scalars are reduced modulo the group order.
+    let scalar = bn::Fr::from_slice(&input[G1_LEN..]).map_err(|_| anyhow!("invalid scalar"))?;
+    Ok(PrecompileOutput::new(cost, write_g1(point * scalar)))
+}
+
+/// Checks whether the product of the pairings of each (G1, G2) pair in the
+/// input is one. The input must be a whole number of pairs; an empty input
+/// trivially succeeds.
+fn pairing(input: &[u8], gas_limit: u64, base: u64, per_pair: u64) -> PrecompileResult {
+    if !input.len().is_multiple_of(PAIR_LEN) {
+        return Err(anyhow!("invalid pairing input length"));
+    }
+    let pairs = (input.len() / PAIR_LEN) as u64;
+    let cost = per_pair.saturating_mul(pairs).saturating_add(base);
+    charge(cost, gas_limit)?;
+
+    let points = input
+        .chunks_exact(PAIR_LEN)
+        .map(|pair| Ok((read_g1(&pair[..G1_LEN])?, read_g2(&pair[G1_LEN..])?)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let success = bn::pairing_batch(&points) == Gt::one();
+
+    let mut output = [0u8; 32];
+    output[31] = success as u8;
+    Ok(PrecompileOutput::new(cost, Bytes::copy_from_slice(&output)))
+}
+
+fn read_fq(bytes: &[u8]) -> eyre::Result<Fq> {
+    Fq::from_slice(bytes).map_err(|_| anyhow!("invalid field element"))
+}
+
+/// Decodes a G1 point, checking that it is on the curve. `(0, 0)` encodes the
+/// point at infinity.
+fn read_g1(bytes: &[u8]) -> eyre::Result<G1> {
+    let x = read_fq(&bytes[..32])?;
+    let y = read_fq(&bytes[32..64])?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+    let point = AffineG1::new(x, y).map_err(|_| anyhow!("point not on curve"))?;
+    Ok(point.into())
+}
+
+/// Decodes a G2 point, checking that it is on the curve and in the right
+/// subgroup. Each coordinate is encoded imaginary part first.
+fn read_g2(bytes: &[u8]) -> eyre::Result<G2> {
+    let x = Fq2::new(read_fq(&bytes[32..64])?, read_fq(&bytes[..32])?);
+    let y = Fq2::new(read_fq(&bytes[96..128])?, read_fq(&bytes[64..96])?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2::zero());
+    }
+    let point = AffineG2::new(x, y).map_err(|_| anyhow!("point not on curve"))?;
+    Ok(point.into())
+}
+
+/// Encodes a G1 point, with the point at infinity as `(0, 0)`.
+fn write_g1(point: G1) -> Bytes {
+    let mut output = [0u8; G1_LEN];
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point
+            .x()
+            .to_big_endian(&mut output[..32])
+            .expect("should fit a word");
+        point
+            .y()
+            .to_big_endian(&mut output[32..])
+            .expect("should fit a word");
+    }
+    Bytes::copy_from_slice(&output)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::{add_byzantium, add_istanbul, mul_istanbul, pairing_byzantium, pairing_istanbul};
+
+    fn input(s: &str) -> Bytes {
+        s.parse().unwrap()
+    }
+
+    const GENERATOR: &str = "0x\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        0000000000000000000000000000000000000000000000000000000000000002";
+
+    const DOUBLE_GENERATOR: &str = "0x\
+        030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+        15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+
+    #[test]
+    fn adds_points() {
+        let point = input(GENERATOR);
+        let output =
+            add_istanbul(&[point.as_ref(), point.as_ref()].concat(), u64::MAX).expect("should add");
+        assert_eq!(input(DOUBLE_GENERATOR), output.bytes);
+        assert_eq!(150, output.gas_used);
+        assert_eq!(500, add_byzantium(&[], u64::MAX).unwrap().gas_used);
+
+        // The empty input adds the point at infinity to itself.
+        let output = add_istanbul(&[], u64::MAX).expect("should add");
+        assert_eq!(Bytes::from(vec![0; 64]), output.bytes);
+    }
+
+    #[test]
+    fn multiplies_points() {
+        let scalar = input("0x0000000000000000000000000000000000000000000000000000000000000002");
+        let output =
+            mul_istanbul(&[input(GENERATOR), scalar].concat(), u64::MAX).expect("should multiply");
+        assert_eq!(input(DOUBLE_GENERATOR), output.bytes);
+        assert_eq!(6000, output.gas_used);
+        assert_eq!(true, mul_istanbul(&[], 5999).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        // (1, 3) is not on the curve.
+        let point = input(
+            "0x\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000003",
+        );
+        assert_eq!(true, add_istanbul(&point, u64::MAX).is_err());
+        assert_eq!(true, mul_istanbul(&point, u64::MAX).is_err());
+
+        // Coordinates must be smaller than the field modulus.
+        let point = input(
+            "0x\
+            30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47\
+            0000000000000000000000000000000000000000000000000000000000000000",
+        );
+        assert_eq!(true, add_istanbul(&point, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn checks_pairings() {
+        // e(P, Q) * e(-P, Q) == 1, with P and Q the generators of G1 and G2.
+        let pairs = input(
+            "0x\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+            1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+            090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+            12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45\
+            198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+            1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+            090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+            12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+        );
+        let one = input("0x0000000000000000000000000000000000000000000000000000000000000001");
+        let output = pairing_istanbul(&pairs, u64::MAX).expect("should pair");
+        assert_eq!(one, output.bytes);
+        assert_eq!(45_000 + 2 * 34_000, output.gas_used);
+        assert_eq!(
+            100_000 + 2 * 80_000,
+            pairing_byzantium(&pairs, u64::MAX).unwrap().gas_used
+        );
+
+        // A single pair does not pair to one.
+        let output = pairing_istanbul(&pairs[..192], u64::MAX).expect("should pair");
+        assert_eq!(Bytes::from(vec![0; 32]), output.bytes);
+
+        // The empty product is one.
+        let output = pairing_istanbul(&[], u64::MAX).expect("should pair");
+        assert_eq!(one, output.bytes);
+
+        assert_eq!(true, pairing_istanbul(&pairs[..191], u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt::Debug;
+
+use alloy_primitives::{Address, Bytes, U256};
+
+use crate::{env::Env, journal::JournaledState};
+
+/// What a custom precompile can see of the call it serves.
+///
+/// Changes made through `journal` are part of the call: they are kept if it
+/// succeeds and undone otherwise.
+pub struct PrecompileContext<'a> {
+    pub journal: &'a mut JournaledState,
+    pub env: &'a Env,
+    pub caller: Address,
+    /// The address whose context the call runs in. Differs from the
+    /// precompile's own address for `DELEGATECALL` and `CALLCODE`.
+    pub address: Address,
+    pub value: U256,
+    pub is_static: bool,
+    pub depth: usize,
+}
+
+/// A precompile implemented by the embedder, for chain-specific contracts.
+///
+/// Gas is charged before running it: calls that can't afford `gas` fail,
+/// consuming all the gas given to them, as do calls where `run` errors.
+pub trait CustomPrecompile: Debug {
+    /// The cost of running the precompile on `input`.
+    fn gas(&self, input: &[u8]) -> u64;
+
+    fn run(&self, context: &mut PrecompileContext<'_>, input: &[u8]) -> eyre::Result<Bytes>;
+}
@@ -0,0 +1,101 @@
+use alloy_primitives::{keccak256, Address, Bytes, B256};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+use super::{charge, right_pad, PrecompileOutput, PrecompileResult};
+
+pub const GAS: u64 = 3000;
+
+/// The `ECRECOVER` precompile (0x01). Takes a message hash, `v`, `r` and `s`
+/// and returns the signer's address, left padded to a word. Invalid
+/// signatures produce no output.
+pub fn run(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    charge(GAS, gas_limit)?;
+    let input = right_pad(input, 0, 128);
+
+    // `v` is a whole word that must hold either 27 or 28.
+    let v = &input[32..64];
+    let valid_v = v[..31].iter().all(|b| *b == 0) && matches!(v[31], 27 | 28);
+    let hash = B256::from_slice(&input[..32]);
+    let signer = if valid_v {
+        recover_signer(&hash, &input[64..128], v[31] - 27)
+    } else {
+        None
+    };
+
+    let output = signer
+        .map(|address| Bytes::copy_from_slice(address.into_word().as_slice()))
+        .unwrap_or_default();
+    Ok(PrecompileOutput::new(GAS, output))
+}
+
+/// Recovers the address that produced the 64 byte signature `r || s` of
+/// `hash`, given the recovery id (the parity of the signature's `y`).
+///
+/// Any `s` is accepted, as by the precompile. Transactions and
+/// authorizations must reject a high `s` themselves (EIP-2).
+pub fn recover_signer(hash: &B256, signature: &[u8], recovery_id: u8) -> Option<Address> {
+    let mut signature = Signature::from_slice(signature).ok()?;
+    let mut recovery_id = RecoveryId::from_byte(recovery_id)?;
+    // k256 only recovers from a low `s`. Negating `s` negates the point
+    // `R` too, which flips the parity of its `y`.
+    if let Some(normalized) = signature.normalize_s() {
+        signature = normalized;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+    }
+    let key = VerifyingKey::recover_from_prehash(hash.as_slice(), &signature, recovery_id).ok()?;
+    let point = key.to_encoded_point(false);
+    // Skip the 0x04 prefix of the uncompressed encoding.
+    let hash = keccak256(&point.as_bytes()[1..]);
+    Some(Address::from_slice(&hash[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::run;
+
+    const INPUT: &str = "0x\
+        456e9aea5e197a1f1af7a3e85a3212fa4049a3ba34c2289b4c860fc0b0c64ef3\
+        000000000000000000000000000000000000000000000000000000000000001c\
+        9242685bf161793cc25603c231bc2f568eb630ea16aa137d2664ac8038825608\
+        4f8ae3bd7535248d0bd448298cc2e2071e56992d0774dc340c368ae950852ada";
+
+    #[test]
+    fn recovers_signer() {
+        let input: Bytes = INPUT.parse().unwrap();
+        let output = run(&input, 3000).expect("should recover");
+        let expected: Bytes = "0x0000000000000000000000007156526fbd7a3c72969b54f64e42c10fbb768c8a"
+            .parse()
+            .unwrap();
+        assert_eq!(expected, output.bytes);
+        assert_eq!(3000, output.gas_used);
+
+        // The same signature with `s` replaced by `n - s` and `v` flipped.
+        let mut input = input.to_vec();
+        input[63] = 27;
+        let high_s: Bytes = "0xb0751c428acadb72f42bb7d6733d1df79c5843b9a7d3c407b39bd3a37fb11667"
+            .parse()
+            .unwrap();
+        input[96..].copy_from_slice(&high_s);
+        assert_eq!(expected, run(&input, 3000).unwrap().bytes);
+    }
+
+    #[test]
+    fn returns_nothing_for_invalid_signatures() {
+        let mut input: Vec<u8> = INPUT.parse::<Bytes>().unwrap().to_vec();
+        // `v` must be 27 or 28.
+        input[63] = 29;
+        assert_eq!(Bytes::new(), run(&input, 3000).unwrap().bytes);
+
+        // `r` must not be zero.
+        input[63] = 28;
+        input[64..96].fill(0);
+        assert_eq!(Bytes::new(), run(&input, 3000).unwrap().bytes);
+
+        // Short inputs get padded, so are simply invalid.
+        assert_eq!(Bytes::new(), run(&[1, 2, 3], 3000).unwrap().bytes);
+        assert_eq!(true, run(&input, 2999).is_err());
+    }
+}
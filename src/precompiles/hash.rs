@@ -0,0 +1,57 @@
+use alloy_primitives::Bytes;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use super::{charge, linear_cost, PrecompileOutput, PrecompileResult};
+
+pub const SHA256_BASE: u64 = 60;
+pub const SHA256_WORD: u64 = 12;
+pub const RIPEMD160_BASE: u64 = 600;
+pub const RIPEMD160_WORD: u64 = 120;
+
+/// The `SHA256` precompile (0x02).
+pub fn sha256(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let cost = linear_cost(input.len(), SHA256_BASE, SHA256_WORD);
+    charge(cost, gas_limit)?;
+    let hash = Sha256::digest(input);
+    Ok(PrecompileOutput::new(cost, Bytes::copy_from_slice(&hash)))
+}
+
+/// The `RIPEMD160` precompile (0x03). The 20 byte hash is left padded to a
+/// word.
+pub fn ripemd160(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let cost = linear_cost(input.len(), RIPEMD160_BASE, RIPEMD160_WORD);
+    charge(cost, gas_limit)?;
+    let mut output = [0u8; 32];
+    output[12..].copy_from_slice(&Ripemd160::digest(input));
+    Ok(PrecompileOutput::new(cost, Bytes::copy_from_slice(&output)))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::{ripemd160, sha256};
+
+    #[test]
+    fn hashes_sha256() {
+        let output = sha256(b"", 60).expect("should hash");
+        let expected: Bytes = "0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            .parse()
+            .unwrap();
+        assert_eq!(expected, output.bytes);
+        assert_eq!(60, output.gas_used);
+        assert_eq!(true, sha256(&[0; 33], 83).is_err());
+    }
+
+    #[test]
+    fn hashes_ripemd160() {
+        let output = ripemd160(b"", 600).expect("should hash");
+        let expected: Bytes = "0x0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31"
+            .parse()
+            .unwrap();
+        assert_eq!(expected, output.bytes);
+        assert_eq!(720, ripemd160(&[0; 32], 720).unwrap().gas_used);
+    }
+}
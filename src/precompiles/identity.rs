@@ -0,0 +1,13 @@
+use alloy_primitives::Bytes;
+
+use super::{charge, linear_cost, PrecompileOutput, PrecompileResult};
+
+pub const BASE: u64 = 15;
+pub const WORD: u64 = 3;
+
+/// The `IDENTITY` precompile (0x04) returns its input.
+pub fn run(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let cost = linear_cost(input.len(), BASE, WORD);
+    charge(cost, gas_limit)?;
+    Ok(PrecompileOutput::new(cost, Bytes::copy_from_slice(input)))
+}
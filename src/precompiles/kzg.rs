@@ -0,0 +1,108 @@
+use alloy_primitives::{hex, Bytes};
+use c_kzg::{Bytes32, Bytes48, KzgSettings};
+use eyre::anyhow;
+use sha2::{Digest, Sha256};
+
+use super::{charge, PrecompileOutput, PrecompileResult};
+
+pub const GAS: u64 = 50_000;
+/// The exact size of the input: the versioned hash, the evaluation point,
+/// the claimed value, the commitment and the proof.
+const INPUT_LEN: usize = 32 + 32 + 32 + 48 + 48;
+/// The version byte of KZG commitment hashes.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// `FIELD_ELEMENTS_PER_BLOB` and `BLS_MODULUS` as words, the output of every
+/// successful evaluation.
+const RETURN_VALUE: [u8; 64] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000001000"
+    "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+);
+
+/// The `POINT_EVALUATION` precompile (0x0A), which verifies that the blob
+/// committed to by a versioned hash evaluates to a claimed value at a given
+/// point (EIP-4844). Uses the trusted setup of the Ethereum KZG ceremony,
+/// embedded in the crate.
+pub fn run(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    charge(GAS, gas_limit)?;
+    if input.len() != INPUT_LEN {
+        return Err(anyhow!("invalid input length"));
+    }
+
+    let commitment = &input[96..144];
+    if input[..32] != commitment_to_versioned_hash(commitment) {
+        return Err(anyhow!("mismatched versioned hash"));
+    }
+
+    let z = Bytes32::from_bytes(&input[32..64]).expect("should be 32 bytes");
+    let y = Bytes32::from_bytes(&input[64..96]).expect("should be 32 bytes");
+    let commitment = Bytes48::from_bytes(commitment).expect("should be 48 bytes");
+    let proof = Bytes48::from_bytes(&input[144..]).expect("should be 48 bytes");
+    let valid = settings()
+        .verify_kzg_proof(&commitment, &z, &y, &proof)
+        .unwrap_or(false);
+    if !valid {
+        return Err(anyhow!("invalid kzg proof"));
+    }
+
+    Ok(PrecompileOutput::new(
+        GAS,
+        Bytes::from_static(&RETURN_VALUE),
+    ))
+}
+
+/// Hashes a commitment into the versioned hash that transactions carry.
+pub fn commitment_to_versioned_hash(commitment: &[u8]) -> [u8; 32] {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+fn settings() -> &'static KzgSettings {
+    c_kzg::ethereum_kzg_settings(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::{run, RETURN_VALUE};
+
+    // From the EIP-4844 reference tests.
+    const INPUT: &str = "0x\
+        01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b\
+        564c0a11a0f704f4fc3e8acfe0f8245f0ad1347b378fbf96e206da11a5d36306\
+        24d25032e67a7e6a4910df5834b8fe70e6bcfeeac0352434196bdf4b2485d5a1\
+        8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f\
+        26936857bc3a7c2539ea8ec3a952b7873033e038326e87ed3e1276fd140253fa\
+        08e9fc25fb2d9a98527fc22a2c9612fbeafdad446cbc7bcdbdcd780af2c16a";
+
+    fn input() -> Bytes {
+        INPUT.parse().unwrap()
+    }
+
+    #[test]
+    fn verifies_proofs() {
+        let output = run(&input(), u64::MAX).expect("should verify");
+        assert_eq!(Bytes::from_static(&RETURN_VALUE), output.bytes);
+        assert_eq!(50_000, output.gas_used);
+        assert_eq!(true, run(&input(), 49_999).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let input = input();
+        assert_eq!(true, run(&input[..191], u64::MAX).is_err());
+
+        // A different versioned hash.
+        let mut wrong_hash = input.to_vec();
+        wrong_hash[31] ^= 1;
+        assert_eq!(true, run(&wrong_hash, u64::MAX).is_err());
+
+        // A different claimed value.
+        let mut wrong_value = input.to_vec();
+        wrong_value[95] ^= 1;
+        assert_eq!(true, run(&wrong_value, u64::MAX).is_err());
+    }
+}
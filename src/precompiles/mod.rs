@@ -0,0 +1,269 @@
+use std::{collections::HashMap, rc::Rc};
+
+use alloy_primitives::{Address, Bytes};
+use eyre::anyhow;
+
+use crate::{gas, spec::SpecId};
+
+pub mod blake2;
+pub mod bls12_381;
+pub mod bn254;
+mod custom;
+pub mod ecrecover;
+pub mod hash;
+pub mod identity;
+pub mod kzg;
+pub mod modexp;
+pub mod p256;
+
+pub use custom::{CustomPrecompile, PrecompileContext};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrecompileOutput {
+    pub gas_used: u64,
+    pub bytes: Bytes,
+}
+
+impl PrecompileOutput {
+    pub fn new(gas_used: u64, bytes: Bytes) -> Self {
+        Self { gas_used, bytes }
+    }
+}
+
+/// Errors make the call fail, consuming all the gas given to it.
+pub type PrecompileResult = eyre::Result<PrecompileOutput>;
+/// A precompile takes its input and the gas limit of the call.
+pub type PrecompileFn = fn(&[u8], u64) -> PrecompileResult;
+
+/// The registry of precompiled contracts, consulted by every call before
+/// loading code.
+///
+/// An address can hold several implementations, each active from a given
+/// spec on, so that repricings can be expressed as new entries. Custom
+/// precompiles are active under every spec and take precedence over the
+/// built-in ones.
+#[derive(Clone, Debug)]
+pub struct Precompiles {
+    entries: HashMap<Address, Vec<(SpecId, PrecompileFn)>>,
+    custom: HashMap<Address, Rc<dyn CustomPrecompile>>,
+}
+
+impl Default for Precompiles {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl Precompiles {
+    /// A registry without any precompiles.
+    pub fn empty() -> Self {
+        Self {
+            entries: HashMap::new(),
+            custom: HashMap::new(),
+        }
+    }
+
+    /// The precompiles of Ethereum mainnet.
+    pub fn standard() -> Self {
+        let mut precompiles = Self::empty();
+        precompiles.insert(address(1), SpecId::Frontier, ecrecover::run);
+        precompiles.insert(address(2), SpecId::Frontier, hash::sha256);
+        precompiles.insert(address(3), SpecId::Frontier, hash::ripemd160);
+        precompiles.insert(address(4), SpecId::Frontier, identity::run);
+        precompiles.insert(address(5), SpecId::Byzantium, modexp::byzantium);
+        precompiles.insert(address(5), SpecId::Berlin, modexp::berlin);
+        precompiles.insert(address(5), SpecId::Osaka, modexp::osaka);
+        precompiles.insert(address(6), SpecId::Byzantium, bn254::add_byzantium);
+        precompiles.insert(address(6), SpecId::Istanbul, bn254::add_istanbul);
+        precompiles.insert(address(7), SpecId::Byzantium, bn254::mul_byzantium);
+        precompiles.insert(address(7), SpecId::Istanbul, bn254::mul_istanbul);
+        precompiles.insert(address(8), SpecId::Byzantium, bn254::pairing_byzantium);
+        precompiles.insert(address(8), SpecId::Istanbul, bn254::pairing_istanbul);
+        precompiles.insert(address(9), SpecId::Istanbul, blake2::run);
+        precompiles.insert(address(10), SpecId::Cancun, kzg::run);
+        precompiles.insert(address(11), SpecId::Prague, bls12_381::g1_add);
+        precompiles.insert(address(12), SpecId::Prague, bls12_381::g1_msm);
+        precompiles.insert(address(13), SpecId::Prague, bls12_381::g2_add);
+        precompiles.insert(address(14), SpecId::Prague, bls12_381::g2_msm);
+        precompiles.insert(address(15), SpecId::Prague, bls12_381::pairing);
+        precompiles.insert(address(16), SpecId::Prague, bls12_381::map_fp_to_g1);
+        precompiles.insert(address(17), SpecId::Prague, bls12_381::map_fp2_to_g2);
+        precompiles.insert(address(0x100), SpecId::Osaka, p256::osaka);
+        precompiles
+    }
+
+    /// Enables `P256VERIFY` from `spec` on, as chains adopting RIP-7212 ahead
+    /// of Osaka do. Osaka's repricing still applies once active.
+    pub fn enable_p256verify(&mut self, spec: SpecId) {
+        self.insert(address(0x100), spec, p256::run);
+    }
+
+    /// Registers `precompile` at `address`, active from `spec` on. Replaces
+    /// any precompile registered at the same address and spec.
+    pub fn insert(&mut self, address: Address, spec: SpecId, precompile: PrecompileFn) {
+        let entries = self.entries.entry(address).or_default();
+        entries.retain(|(s, _)| *s != spec);
+        entries.push((spec, precompile));
+        entries.sort_by_key(|(s, _)| *s);
+    }
+
+    /// Returns the precompile at `address` that is active under `spec`.
+    pub fn get(&self, address: &Address, spec: SpecId) -> Option<PrecompileFn> {
+        self.entries
+            .get(address)?
+            .iter()
+            .rev()
+            .find(|(s, _)| spec.is_enabled_in(*s))
+            .map(|(_, precompile)| *precompile)
+    }
+
+    /// Registers a custom precompile at `address`, replacing any custom
+    /// precompile there and shadowing any built-in one.
+    pub fn insert_custom(&mut self, address: Address, precompile: Rc<dyn CustomPrecompile>) {
+        self.custom.insert(address, precompile);
+    }
+
+    /// Returns the custom precompile at `address`, if any.
+    pub fn get_custom(&self, address: &Address) -> Option<Rc<dyn CustomPrecompile>> {
+        self.custom.get(address).cloned()
+    }
+
+    pub fn contains(&self, address: &Address, spec: SpecId) -> bool {
+        self.custom.contains_key(address) || self.get(address, spec).is_some()
+    }
+
+    /// The addresses of the precompiles active under `spec`.
+    pub fn addresses(&self, spec: SpecId) -> impl Iterator<Item = Address> + '_ {
+        let custom = self.custom.keys().copied();
+        let builtin = self.entries.keys().copied().filter(move |address| {
+            !self.custom.contains_key(address) && self.get(address, spec).is_some()
+        });
+        custom.chain(builtin)
+    }
+}
+
+/// Returns the precompile address with `index` as its last bytes.
+pub const fn address(index: u64) -> Address {
+    let bytes = index.to_be_bytes();
+    let mut address = [0u8; 20];
+    let mut i = 0;
+    while i < 8 {
+        address[12 + i] = bytes[i];
+        i += 1;
+    }
+    Address::new(address)
+}
+
+/// Prices an operation as a base cost plus a cost per word of input.
+pub(crate) fn linear_cost(len: usize, base: u64, word: u64) -> u64 {
+    base.saturating_add(word.saturating_mul(gas::num_words(len as u64)))
+}
+
+pub(crate) fn charge(cost: u64, gas_limit: u64) -> eyre::Result<()> {
+    if cost > gas_limit {
+        return Err(anyhow!("out of gas"));
+    }
+    Ok(())
+}
+
+/// Returns `len` bytes of `input` starting at `offset`, right padded with
+/// zeroes.
+pub(crate) fn right_pad(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut padded = vec![0u8; len];
+    if offset < input.len() {
+        let end = input.len().min(offset + len);
+        padded[..end - offset].copy_from_slice(&input[offset..end]);
+    }
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use alloy_primitives::{Address, Bytes};
+    use pretty_assertions::assert_eq;
+
+    use crate::spec::SpecId;
+
+    use super::{
+        address, identity, right_pad, CustomPrecompile, PrecompileContext, PrecompileOutput,
+        Precompiles,
+    };
+
+    fn free(_: &[u8], _: u64) -> super::PrecompileResult {
+        Ok(PrecompileOutput::default())
+    }
+
+    #[test]
+    fn builds_addresses() {
+        assert_eq!(Address::with_last_byte(4), address(4));
+        assert_eq!(
+            "0x0000000000000000000000000000000000000100"
+                .parse::<Address>()
+                .unwrap(),
+            address(0x100)
+        );
+    }
+
+    #[test]
+    fn selects_precompile_by_spec() {
+        let mut precompiles = Precompiles::empty();
+        precompiles.insert(address(9), SpecId::Istanbul, identity::run);
+        precompiles.insert(address(9), SpecId::Berlin, free);
+        assert_eq!(false, precompiles.contains(&address(9), SpecId::Byzantium));
+
+        let istanbul = precompiles.get(&address(9), SpecId::Istanbul).unwrap();
+        assert_eq!(18, istanbul(&[1], u64::MAX).unwrap().gas_used);
+        let cancun = precompiles.get(&address(9), SpecId::Cancun).unwrap();
+        assert_eq!(0, cancun(&[1], u64::MAX).unwrap().gas_used);
+    }
+
+    #[test]
+    fn enables_p256verify() {
+        let mut precompiles = Precompiles::standard();
+        assert_eq!(false, precompiles.contains(&address(0x100), SpecId::Cancun));
+        assert_eq!(true, precompiles.contains(&address(0x100), SpecId::Osaka));
+
+        precompiles.enable_p256verify(SpecId::Cancun);
+        let cancun = precompiles.get(&address(0x100), SpecId::Cancun).unwrap();
+        assert_eq!(3450, cancun(&[], u64::MAX).unwrap().gas_used);
+        let osaka = precompiles.get(&address(0x100), SpecId::Osaka).unwrap();
+        assert_eq!(6900, osaka(&[], u64::MAX).unwrap().gas_used);
+    }
+
+    #[derive(Debug)]
+    struct Free;
+
+    impl CustomPrecompile for Free {
+        fn gas(&self, _: &[u8]) -> u64 {
+            0
+        }
+
+        fn run(&self, _: &mut PrecompileContext<'_>, _: &[u8]) -> eyre::Result<Bytes> {
+            Ok(Bytes::new())
+        }
+    }
+
+    #[test]
+    fn registers_custom_precompiles() {
+        let mut precompiles = Precompiles::standard();
+        precompiles.insert_custom(address(0x1000), Rc::new(Free));
+        precompiles.insert_custom(address(1), Rc::new(Free));
+        assert_eq!(
+            true,
+            precompiles.contains(&address(0x1000), SpecId::Frontier)
+        );
+        assert_eq!(true, precompiles.get_custom(&address(1)).is_some());
+
+        let addresses: Vec<_> = precompiles.addresses(SpecId::Frontier).collect();
+        assert_eq!(5, addresses.len());
+        assert_eq!(true, addresses.contains(&address(0x1000)));
+    }
+
+    #[test]
+    fn pads_input() {
+        assert_eq!(vec![2, 3, 0, 0], right_pad(&[1, 2, 3], 1, 4));
+        assert_eq!(vec![0, 0], right_pad(&[1, 2, 3], 5, 2));
+    }
+}
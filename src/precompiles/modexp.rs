@@ -0,0 +1,229 @@
+use alloy_primitives::{Bytes, U256};
+use eyre::anyhow;
+use num_bigint::BigUint;
+
+use super::{charge, right_pad, PrecompileOutput, PrecompileResult};
+
+/// The `MODEXP` precompile (0x05) priced as in EIP-198.
+pub fn byzantium(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    run(input, gas_limit, byzantium_gas)
+}
+
+/// The `MODEXP` precompile (0x05) priced as in EIP-2565.
+pub fn berlin(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    run(input, gas_limit, berlin_gas)
+}
+
+/// The `MODEXP` precompile (0x05) priced as in EIP-7883.
+pub fn osaka(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    run(input, gas_limit, osaka_gas)
+}
+
+/// The lengths of the base, exponent and modulus, plus the first (up to) 32
+/// bytes of the exponent, which is all the pricing needs to know.
+struct Header {
+    base_len: U256,
+    exp_len: U256,
+    mod_len: U256,
+    exp_head: U256,
+}
+
+/// Computes `base ** exp % modulus`. The input starts with the lengths of the
+/// three operands as words, followed by the operands themselves, big endian.
+/// Missing input bytes are zeroes.
+fn run(input: &[u8], gas_limit: u64, gas: fn(&Header) -> U256) -> PrecompileResult {
+    let base_len = U256::from_be_slice(&right_pad(input, 0, 32));
+    let exp_len = U256::from_be_slice(&right_pad(input, 32, 32));
+    let mod_len = U256::from_be_slice(&right_pad(input, 64, 32));
+
+    // The exponent can only be located if the base length is sane. If it
+    // isn't, pricing makes the call run out of gas anyway.
+    let exp_head = match usize::try_from(base_len) {
+        Ok(base_len) => {
+            let head_len = exp_len.min(U256::from(32)).to::<usize>();
+            let head = right_pad(input, 96usize.saturating_add(base_len), head_len);
+            U256::from_be_slice(&head)
+        }
+        Err(_) => U256::ZERO,
+    };
+
+    let header = Header {
+        base_len,
+        exp_len,
+        mod_len,
+        exp_head,
+    };
+    let cost = u64::try_from(gas(&header)).map_err(|_| anyhow!("out of gas"))?;
+    charge(cost, gas_limit)?;
+
+    // An empty modulus means an empty output, no matter how large the other
+    // operands claim to be.
+    if mod_len.is_zero() {
+        return Ok(PrecompileOutput::new(cost, Bytes::new()));
+    }
+
+    // Paying for the operation bounds the lengths.
+    let base_len = base_len.to::<usize>();
+    let exp_len = exp_len.to::<usize>();
+    let mod_len = mod_len.to::<usize>();
+
+    let base = BigUint::from_bytes_be(&right_pad(input, 96, base_len));
+    let exp = BigUint::from_bytes_be(&right_pad(input, 96 + base_len, exp_len));
+    let modulus = BigUint::from_bytes_be(&right_pad(input, 96 + base_len + exp_len, mod_len));
+
+    let result = if modulus == BigUint::ZERO {
+        BigUint::ZERO
+    } else {
+        base.modpow(&exp, &modulus)
+    };
+
+    // Left pad the result to the length of the modulus.
+    let bytes = result.to_bytes_be();
+    let mut output = vec![0u8; mod_len];
+    if result != BigUint::ZERO {
+        output[mod_len - bytes.len()..].copy_from_slice(&bytes);
+    }
+    Ok(PrecompileOutput::new(cost, output.into()))
+}
+
+/// Approximates the number of squarings and multiplications needed, from the
+/// length of the exponent and its leading bits.
+fn iteration_count(header: &Header, multiplier: u64) -> U256 {
+    let head_bits = header.exp_head.bit_len().saturating_sub(1);
+    let count = if header.exp_len <= U256::from(32) {
+        U256::from(head_bits)
+    } else {
+        (header.exp_len - U256::from(32))
+            .saturating_mul(U256::from(multiplier))
+            .saturating_add(U256::from(head_bits))
+    };
+    count.max(U256::from(1))
+}
+
+fn max_len(header: &Header) -> U256 {
+    header.base_len.max(header.mod_len)
+}
+
+fn byzantium_gas(header: &Header) -> U256 {
+    let x = max_len(header);
+    let x2 = x.saturating_mul(x);
+    let complexity = if x <= U256::from(64) {
+        x2
+    } else if x <= U256::from(1024) {
+        x2 / U256::from(4) + x * U256::from(96) - U256::from(3072)
+    } else {
+        (x2 / U256::from(16))
+            .saturating_add(x.saturating_mul(U256::from(480)))
+            .saturating_sub(U256::from(199680))
+    };
+    complexity.saturating_mul(iteration_count(header, 8)) / U256::from(20)
+}
+
+fn berlin_gas(header: &Header) -> U256 {
+    let words = words(max_len(header));
+    let complexity = words.saturating_mul(words);
+    let gas = complexity.saturating_mul(iteration_count(header, 8)) / U256::from(3);
+    gas.max(U256::from(200))
+}
+
+fn osaka_gas(header: &Header) -> U256 {
+    let max_len = max_len(header);
+    let complexity = if max_len <= U256::from(32) {
+        U256::from(16)
+    } else {
+        let words = words(max_len);
+        words.saturating_mul(words).saturating_mul(U256::from(2))
+    };
+    let gas = complexity.saturating_mul(iteration_count(header, 16));
+    gas.max(U256::from(500))
+}
+
+fn words(len: U256) -> U256 {
+    len.saturating_add(U256::from(7)) / U256::from(8)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::{berlin, byzantium, osaka};
+
+    fn input(s: &str) -> Bytes {
+        s.parse().unwrap()
+    }
+
+    // 3 ** (2 ** 256 - 2 ** 32 - 978) % (2 ** 256 - 2 ** 32 - 977), from
+    // EIP-198.
+    const FERMAT: &str = "0x\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        0000000000000000000000000000000000000000000000000000000000000020\
+        0000000000000000000000000000000000000000000000000000000000000020\
+        03\
+        fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e\
+        fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+
+    #[test]
+    fn computes_modexp() {
+        let output = berlin(&input(FERMAT), u64::MAX).expect("should compute");
+        let expected = input("0x0000000000000000000000000000000000000000000000000000000000000001");
+        assert_eq!(expected, output.bytes);
+    }
+
+    #[test]
+    fn prices_modexp() {
+        assert_eq!(13056, byzantium(&input(FERMAT), u64::MAX).unwrap().gas_used);
+        assert_eq!(1360, berlin(&input(FERMAT), u64::MAX).unwrap().gas_used);
+        assert_eq!(4080, osaka(&input(FERMAT), u64::MAX).unwrap().gas_used);
+        assert_eq!(true, berlin(&input(FERMAT), 1359).is_err());
+    }
+
+    #[test]
+    fn handles_zero_lengths() {
+        // Empty input: all lengths are zero.
+        let output = berlin(&[], u64::MAX).expect("should compute");
+        assert_eq!(Bytes::new(), output.bytes);
+        assert_eq!(200, output.gas_used);
+        assert_eq!(0, byzantium(&[], u64::MAX).unwrap().gas_used);
+        assert_eq!(500, osaka(&[], u64::MAX).unwrap().gas_used);
+
+        // A zero modulus gives zeroes.
+        let output = berlin(
+            &input(
+                "0x\
+                0000000000000000000000000000000000000000000000000000000000000001\
+                0000000000000000000000000000000000000000000000000000000000000001\
+                0000000000000000000000000000000000000000000000000000000000000002\
+                0203",
+            ),
+            u64::MAX,
+        )
+        .expect("should compute");
+        assert_eq!(input("0x0000"), output.bytes);
+    }
+
+    #[test]
+    fn handles_huge_lengths() {
+        // A huge exponent with an empty base and modulus costs the minimum
+        // and produces nothing.
+        let huge_exp = input(
+            "0x\
+            0000000000000000000000000000000000000000000000000000000000000000\
+            ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\
+            0000000000000000000000000000000000000000000000000000000000000000",
+        );
+        let output = byzantium(&huge_exp, 0).expect("should compute");
+        assert_eq!(Bytes::new(), output.bytes);
+        assert_eq!(200, berlin(&huge_exp, 200).unwrap().gas_used);
+
+        // A huge modulus runs out of gas.
+        let huge_mod = input(
+            "0x\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        );
+        assert_eq!(true, berlin(&huge_mod, u64::MAX).is_err());
+        assert_eq!(true, byzantium(&huge_mod, u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use alloy_primitives::{Bytes, B256};
+use p256::{
+    ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey},
+    EncodedPoint,
+};
+
+use super::{charge, PrecompileOutput, PrecompileResult};
+
+/// The cost of a verification under RIP-7212.
+pub const GAS: u64 = 3_450;
+/// The cost of a verification under EIP-7951.
+pub const OSAKA_GAS: u64 = 6_900;
+/// The exact size of the input: the message hash, the signature's `r` and
+/// `s`, and the public key's `x` and `y`.
+const INPUT_LEN: usize = 160;
+
+/// The `P256VERIFY` precompile (0x100) priced as in RIP-7212, for chains
+/// that enable it before Osaka.
+pub fn run(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    verify(input, gas_limit, GAS)
+}
+
+/// The `P256VERIFY` precompile (0x100) priced as in EIP-7951.
+pub fn osaka(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    verify(input, gas_limit, OSAKA_GAS)
+}
+
+/// Verifies a secp256r1 signature over a message hash. Invalid inputs don't
+/// fail the call, they return empty output instead of a one word.
+fn verify(input: &[u8], gas_limit: u64, cost: u64) -> PrecompileResult {
+    charge(cost, gas_limit)?;
+    let output = if input.len() == INPUT_LEN && verify_signature(input) {
+        Bytes::copy_from_slice(B256::with_last_byte(1).as_slice())
+    } else {
+        Bytes::new()
+    };
+    Ok(PrecompileOutput::new(cost, output))
+}
+
+fn verify_signature(input: &[u8]) -> bool {
+    let (hash, rest) = input.split_at(32);
+    let (signature, key) = rest.split_at(64);
+
+    let Ok(signature) = Signature::from_slice(signature) else {
+        return false;
+    };
+    let point = EncodedPoint::from_affine_coordinates(key[..32].into(), key[32..].into(), false);
+    let Ok(key) = VerifyingKey::from_encoded_point(&point) else {
+        return false;
+    };
+    key.verify_prehash(hash, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Bytes, B256};
+    use pretty_assertions::assert_eq;
+
+    use super::{osaka, run};
+
+    fn input(s: &str) -> Bytes {
+        s.parse().unwrap()
+    }
+
+    // From the RIP-7212 test vectors.
+    const VALID: &str = "0x\
+        4cee90eb86eaa050036147a12d49004b6b9c72bd725d39d4785011fe190f0b4d\
+        a73bd4903f0ce3b639bbbf6e8e80d16931ff4bcf5993d58468e8fb19086e8cac\
+        36dbcd03009df8c59286b162af3bd7fcc0450c9aa81be5d10d312af6c66b1d60\
+        4aebd3099c618202fcfe16ae7770b0c49ab5eadf74b754204a3bb6060e44eff3\
+        7618b065f9832de4ca6ca971a7a1adc826d0f7c00181a5fb2ddf79ae00b4e10e";
+
+    #[test]
+    fn verifies_signatures() {
+        let output = run(&input(VALID), u64::MAX).expect("should verify");
+        assert_eq!(B256::with_last_byte(1).as_slice(), output.bytes.as_ref());
+        assert_eq!(3_450, output.gas_used);
+        assert_eq!(6_900, osaka(&input(VALID), u64::MAX).unwrap().gas_used);
+        assert_eq!(true, run(&input(VALID), 3_449).is_err());
+    }
+
+    #[test]
+    fn returns_nothing_for_invalid_signatures() {
+        let valid = input(VALID);
+
+        // A different message hash.
+        let mut wrong_hash = valid.to_vec();
+        wrong_hash[0] ^= 1;
+        // A public key that is not on the curve.
+        let mut wrong_key = valid.to_vec();
+        wrong_key[159] ^= 1;
+        // A zero `r`.
+        let mut zero_r = valid.to_vec();
+        zero_r[32..64].fill(0);
+
+        for input in [&wrong_hash[..], &wrong_key, &zero_r, &valid[..159], &[]] {
+            let output = run(input, u64::MAX).expect("should not fail");
+            assert_eq!(Bytes::new(), output.bytes);
+            assert_eq!(3_450, output.gas_used);
+        }
+    }
+}
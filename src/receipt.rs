@@ -0,0 +1,215 @@
+//! Transaction receipts, their logs bloom and the receipts root.
+
+use alloy_primitives::{keccak256, Bloom, B256, U256};
+use alloy_rlp::{Encodable, Header};
+use serde::{Serialize, Serializer};
+
+use crate::{state::Log, transaction::ExecutionResult, trie};
+
+/// The outcome of a transaction as committed to by the block header.
+///
+/// Serializes like the consensus fields of a JSON-RPC receipt.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    /// The EIP-2718 type of the transaction, 0 for legacy transactions.
+    #[serde(rename = "type", serialize_with = "serialize_quantity")]
+    pub tx_type: u8,
+    #[serde(serialize_with = "serialize_quantity")]
+    pub status: bool,
+    /// The state root after the transaction. Receipts only carried it,
+    /// instead of the status, before Byzantium (EIP-658).
+    #[serde(rename = "root", skip_serializing_if = "Option::is_none")]
+    pub state_root: Option<B256>,
+    /// The gas used by the transaction and every one before it in the block.
+    #[serde(serialize_with = "serialize_quantity")]
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Log>,
+    pub logs_bloom: Bloom,
+}
+
+impl Receipt {
+    pub fn new(tx_type: u8, status: bool, cumulative_gas_used: u64, logs: Vec<Log>) -> Self {
+        Self {
+            tx_type,
+            status,
+            state_root: None,
+            cumulative_gas_used,
+            logs_bloom: logs_bloom(&logs),
+            logs,
+        }
+    }
+
+    /// The encoding stored in the receipts trie: the RLP list of a legacy
+    /// receipt, or the type byte followed by the RLP list of a typed one.
+    pub fn encoded(&self) -> Vec<u8> {
+        let mut fields = Vec::new();
+        match self.state_root {
+            Some(root) => root.encode(&mut fields),
+            None => self.status.encode(&mut fields),
+        }
+        self.cumulative_gas_used.encode(&mut fields);
+        self.logs_bloom.encode(&mut fields);
+        self.logs.encode(&mut fields);
+
+        let mut out = Vec::with_capacity(fields.len() + 4);
+        if self.tx_type != 0 {
+            out.push(self.tx_type);
+        }
+        Header {
+            list: true,
+            payload_length: fields.len(),
+        }
+        .encode(&mut out);
+        out.extend_from_slice(&fields);
+        out
+    }
+}
+
+/// Hex encodes numbers the way JSON-RPC does.
+fn serialize_quantity<T: Copy + Into<u64>, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    U256::from((*value).into()).serialize(serializer)
+}
+
+/// Builds the receipts of transactions executed one after the other in a
+/// block, given the type of each transaction and its result.
+pub fn from_results<'a>(
+    results: impl IntoIterator<Item = (u8, &'a ExecutionResult)>,
+) -> Vec<Receipt> {
+    let mut cumulative_gas_used = 0;
+    results
+        .into_iter()
+        .map(|(tx_type, result)| {
+            cumulative_gas_used += result.gas_used;
+            Receipt::new(
+                tx_type,
+                result.is_success(),
+                cumulative_gas_used,
+                result.logs.clone(),
+            )
+        })
+        .collect()
+}
+
+/// The bloom filter of the addresses and topics of `logs`.
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    Bloom::logs_bloom(logs.into_iter().map(|log| {
+        (
+            log.address.0 .0,
+            log.topics.iter().map(|topic| topic.0).collect::<Vec<_>>(),
+        )
+    }))
+}
+
+/// The root of the trie of `receipts`, keyed by their index.
+pub fn receipts_root(receipts: &[Receipt]) -> B256 {
+    trie::ordered_trie_root(receipts.iter().map(Receipt::encoded))
+}
+
+/// The hash of the RLP encoded list of logs.
+pub fn logs_hash(logs: &[Log]) -> B256 {
+    let mut out = Vec::new();
+    alloy_rlp::encode_list(logs, &mut out);
+    keccak256(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{b256, Address, Bloom, BloomInput, B256};
+    use pretty_assertions::assert_eq;
+
+    use crate::{frame::ExitStatus, transaction::ExecutionResult};
+    use crate::{state::Log, trie::EMPTY_ROOT};
+
+    use super::{from_results, logs_bloom, logs_hash, receipts_root, Receipt};
+
+    #[test]
+    fn hashes_empty_logs() {
+        assert_eq!(
+            b256!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"),
+            logs_hash(&[])
+        );
+        assert_eq!(EMPTY_ROOT, receipts_root(&[]));
+    }
+
+    #[test]
+    fn builds_logs_bloom() {
+        let log = Log {
+            address: Address::with_last_byte(1),
+            topics: vec![B256::with_last_byte(2)],
+            ..Default::default()
+        };
+        let bloom = logs_bloom(&[log]);
+        assert_eq!(
+            true,
+            bloom.contains_input(BloomInput::Raw(Address::with_last_byte(1).as_slice()))
+        );
+        assert_eq!(
+            true,
+            bloom.contains_input(BloomInput::Raw(B256::with_last_byte(2).as_slice()))
+        );
+        assert_eq!(Bloom::ZERO, logs_bloom(&[]));
+    }
+
+    #[test]
+    fn encodes_typed_receipts() {
+        let legacy = Receipt::new(0, true, 21000, vec![]);
+        let typed = Receipt::new(2, true, 21000, vec![]);
+        assert_eq!(typed.encoded()[1..], legacy.encoded()[..]);
+        assert_eq!(2, typed.encoded()[0]);
+        // [0x01, 0x5208, bloom, []]
+        assert_eq!(
+            [0xF9, 0x01, 0x08, 0x01, 0x82, 0x52, 0x08, 0xB9, 0x01, 0x00],
+            legacy.encoded()[..10]
+        );
+    }
+
+    #[test]
+    fn accumulates_gas_used() {
+        let result = |status, gas_used| ExecutionResult {
+            status,
+            gas_used,
+            gas_refunded: 0,
+            output: Default::default(),
+            logs: vec![Log::default()],
+            contract_address: None,
+            state_root: B256::ZERO,
+        };
+        let results = [
+            result(ExitStatus::Success, 21000),
+            result(ExitStatus::Revert, 30000),
+        ];
+        let receipts = from_results([(2, &results[0]), (0, &results[1])]);
+        assert_eq!(
+            vec![
+                Receipt::new(2, true, 21000, vec![Log::default()]),
+                Receipt::new(0, false, 51000, vec![Log::default()]),
+            ],
+            receipts
+        );
+    }
+
+    #[test]
+    fn computes_receipts_root() {
+        // The receipts root of any block since Byzantium holding a single
+        // successful legacy transfer and nothing else.
+        assert_eq!(
+            b256!("056b23fbba480696b65fe5a59b8f2148a1299103c4f57df839233af2cf4ca2d2"),
+            receipts_root(&[Receipt::new(0, true, 21000, vec![])])
+        );
+    }
+
+    #[test]
+    fn serializes_like_rpc_receipts() {
+        let receipt = Receipt::new(2, true, 21000, vec![]);
+        let json = serde_json::to_value(&receipt).expect("should serialize the receipt");
+        assert_eq!("0x2", json["type"]);
+        assert_eq!("0x1", json["status"]);
+        assert_eq!("0x5208", json["cumulativeGasUsed"]);
+        assert_eq!(serde_json::Value::Null, json["root"]);
+        assert_eq!(serde_json::json!([]), json["logs"]);
+    }
+}
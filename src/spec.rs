@@ -0,0 +1,108 @@
+use std::{fmt::Display, str::FromStr};
+
+use eyre::anyhow;
+
+/// The Ethereum hard forks, in activation order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecId {
+    Frontier,
+    Homestead,
+    Tangerine,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Merge,
+    Shanghai,
+    #[default]
+    Cancun,
+    Prague,
+    Osaka,
+}
+
+impl SpecId {
+    /// Returns whether the rules of `other` apply under this spec.
+    pub fn is_enabled_in(self, other: SpecId) -> bool {
+        self >= other
+    }
+}
+
+impl FromStr for SpecId {
+    type Err = eyre::Report;
+
+    /// Parses the fork names used by ethereum/tests and geth.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spec = match s {
+            "Frontier" => Self::Frontier,
+            "Homestead" => Self::Homestead,
+            "EIP150" | "Tangerine" | "TangerineWhistle" => Self::Tangerine,
+            "EIP158" | "SpuriousDragon" => Self::SpuriousDragon,
+            "Byzantium" => Self::Byzantium,
+            "Constantinople" => Self::Constantinople,
+            "ConstantinopleFix" | "Petersburg" => Self::Petersburg,
+            // The glacier forks only delayed the difficulty bomb.
+            "Istanbul" | "MuirGlacier" => Self::Istanbul,
+            "Berlin" => Self::Berlin,
+            "London" | "ArrowGlacier" | "GrayGlacier" => Self::London,
+            "Merge" | "Paris" => Self::Merge,
+            "Shanghai" => Self::Shanghai,
+            "Cancun" => Self::Cancun,
+            "Prague" => Self::Prague,
+            "Osaka" => Self::Osaka,
+            _ => return Err(anyhow!("unknown fork: {s}")),
+        };
+        Ok(spec)
+    }
+}
+
+impl Display for SpecId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Frontier => "Frontier",
+            Self::Homestead => "Homestead",
+            Self::Tangerine => "EIP150",
+            Self::SpuriousDragon => "EIP158",
+            Self::Byzantium => "Byzantium",
+            Self::Constantinople => "Constantinople",
+            Self::Petersburg => "ConstantinopleFix",
+            Self::Istanbul => "Istanbul",
+            Self::Berlin => "Berlin",
+            Self::London => "London",
+            Self::Merge => "Merge",
+            Self::Shanghai => "Shanghai",
+            Self::Cancun => "Cancun",
+            Self::Prague => "Prague",
+            Self::Osaka => "Osaka",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::SpecId;
+
+    #[test]
+    fn parses_fork_names() {
+        assert_eq!(SpecId::Petersburg, "ConstantinopleFix".parse().unwrap());
+        assert_eq!(SpecId::Merge, "Paris".parse().unwrap());
+        assert_eq!(SpecId::Istanbul, "MuirGlacier".parse().unwrap());
+        assert_eq!(SpecId::London, "ArrowGlacier".parse().unwrap());
+        assert_eq!(SpecId::London, "GrayGlacier".parse().unwrap());
+        assert_eq!(true, "Unknown".parse::<SpecId>().is_err());
+        for spec in [SpecId::Tangerine, SpecId::Cancun] {
+            assert_eq!(spec, spec.to_string().parse().unwrap());
+        }
+    }
+
+    #[test]
+    fn orders_forks() {
+        assert_eq!(true, SpecId::Cancun.is_enabled_in(SpecId::London));
+        assert_eq!(false, SpecId::Berlin.is_enabled_in(SpecId::London));
+    }
+}
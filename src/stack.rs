@@ -68,7 +68,7 @@ impl Stack {
     }
 
     pub fn pop(&self) -> eyre::Result<U256> {
-        if self.stack.borrow().len() == 0 {
+        if self.stack.borrow().is_empty() {
             return Err(anyhow!("stack underflow"));
         }
 
@@ -105,13 +105,28 @@ impl Stack {
         Ok(())
     }
 
-    #[cfg(test)]
-    fn len(&self) -> usize {
+    /// Swaps the items `n` and `n + m` positions below the top.
+    pub fn exchange(&self, n: usize, m: usize) -> eyre::Result<()> {
+        if self.stack.borrow().len() < n + m + 1 {
+            return Err(anyhow!("stack underflow"));
+        }
+
+        let mut stack = self.stack.borrow_mut();
+        let top = stack.len() - 1;
+        stack.swap(top - n, top - n - m);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
         self.stack.borrow().len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.stack.borrow().is_empty()
+    }
+
     #[cfg(test)]
-    fn into_iter(&self) -> std::vec::IntoIter<alloy_primitives::Uint<256, 4>> {
+    fn iter(&self) -> std::vec::IntoIter<alloy_primitives::Uint<256, 4>> {
         let stack = self.stack.borrow().clone();
         stack.into_iter()
     }
@@ -236,7 +251,7 @@ mod tests {
             .push(U256::from(2))
             .expect("should push onto the stack");
         let e = stack.swap(0);
-        assert_eq!(true, matches!(e, Err(_)));
+        assert_eq!(true, e.is_err());
     }
 
     #[test]
@@ -274,8 +289,27 @@ mod tests {
             .expect("should dup the second to last element on the stack");
         let all_equal = vec![U256::from(3), U256::from(2), U256::from(1)]
             .into_iter()
-            .zip(stack.into_iter())
+            .zip(stack.iter())
             .all(|(a, b)| a == b);
         assert_eq!(true, all_equal);
     }
+
+    #[test]
+    fn exchanges() {
+        let stack = Stack::new(STACK_SIZE);
+        for i in 0..4 {
+            stack
+                .push(U256::from(i))
+                .expect("should push onto the stack");
+        }
+        stack.exchange(1, 2).expect("should exchange");
+        let items: Vec<U256> = stack.iter().collect();
+        assert_eq!(
+            vec![U256::from(2), U256::from(1), U256::from(0), U256::from(3)],
+            items
+        );
+
+        let err = stack.exchange(2, 2).unwrap_err();
+        assert_eq!("stack underflow", err.to_string());
+    }
 }
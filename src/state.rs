@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_rlp::RlpEncodable;
+use serde::Serialize;
+
+/// The world state, a mapping from addresses to accounts.
+pub type State = HashMap<Address, Account>;
+
+/// The hash of empty code, `keccak256("")`.
+pub const KECCAK_EMPTY: B256 = B256::new([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Bytes,
+    pub storage: HashMap<U256, U256>,
+}
+
+impl Account {
+    pub fn code_hash(&self) -> B256 {
+        if self.code.is_empty() {
+            KECCAK_EMPTY
+        } else {
+            keccak256(&self.code)
+        }
+    }
+
+    /// An account is empty when it has no code, zero nonce and zero balance,
+    /// as defined by EIP-161.
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty() && self.nonce == 0 && self.balance.is_zero()
+    }
+}
+
+/// A log emitted by one of the `LOG` opcodes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, RlpEncodable, Serialize)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::keccak256;
+    use pretty_assertions::assert_eq;
+
+    use super::{Account, KECCAK_EMPTY};
+
+    #[test]
+    fn hashes_empty_code() {
+        assert_eq!(keccak256([]), KECCAK_EMPTY);
+        assert_eq!(KECCAK_EMPTY, Account::default().code_hash());
+    }
+}
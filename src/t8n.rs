@@ -0,0 +1,773 @@
+//! The transition tool (`t8n`) interface of geth's `evm t8n`, used by test
+//! fillers to drive execution clients.
+//!
+//! A transition applies a list of transactions to a pre-state (the alloc)
+//! within the block described by an environment. It produces the post-state
+//! along with the roots, receipts and rejected transactions of the block.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use alloy_primitives::{Address, Bloom, Bytes, B256, U256};
+use alloy_rlp::Decodable;
+use eyre::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alloc::{self, Alloc},
+    block::{self, GasUsage, Withdrawal, BEACON_ROOTS_ADDRESS, HISTORY_STORAGE_ADDRESS},
+    env::{AccessListItem, CfgEnv, Env, SignedAuthorization},
+    envelope::{
+        Signature, SignedTransaction, Transaction, TxEip1559, TxEip2930, TxEip4844, TxEip7702,
+        TxKind, TxLegacy,
+    },
+    journal::JournaledState,
+    receipt,
+    spec::SpecId,
+    state::Log,
+    transaction, trie,
+};
+
+/// The block a transition happens in.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nEnv {
+    pub current_coinbase: Address,
+    /// Required before the merge.
+    pub current_difficulty: Option<U256>,
+    /// Required after the merge (EIP-4399).
+    pub current_random: Option<B256>,
+    pub current_gas_limit: U256,
+    pub current_number: U256,
+    pub current_timestamp: U256,
+    /// Required from London on (EIP-1559).
+    pub current_base_fee: Option<U256>,
+    /// Required from Cancun on (EIP-4844).
+    pub current_excess_blob_gas: Option<U256>,
+    /// Required from Cancun on (EIP-4788).
+    pub parent_beacon_block_root: Option<B256>,
+    /// The hashes of recent blocks by number. The parent hash is stored in
+    /// the history contract from Prague on (EIP-2935).
+    #[serde(default)]
+    pub block_hashes: BTreeMap<U256, B256>,
+    #[serde(default)]
+    pub ommers: Vec<Ommer>,
+    /// Required from Shanghai on (EIP-4895).
+    pub withdrawals: Option<Vec<T8nWithdrawal>>,
+}
+
+/// An ommer `delta` blocks older than the current block.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Ommer {
+    pub delta: u64,
+    pub address: Address,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nWithdrawal {
+    pub index: U256,
+    pub validator_index: U256,
+    pub address: Address,
+    pub amount: U256,
+}
+
+impl From<&T8nWithdrawal> for Withdrawal {
+    fn from(withdrawal: &T8nWithdrawal) -> Self {
+        Self {
+            index: withdrawal.index.saturating_to(),
+            validator_index: withdrawal.validator_index.saturating_to(),
+            address: withdrawal.address,
+            amount: withdrawal.amount.saturating_to(),
+        }
+    }
+}
+
+/// A transaction in `txs.json`. It is either signed with `v`, `r` and `s`,
+/// or signed by the tool with `secretKey`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nTransaction {
+    #[serde(rename = "type", default)]
+    pub tx_type: U256,
+    pub chain_id: Option<U256>,
+    pub nonce: U256,
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub gas: U256,
+    /// The callee, or `None` for contract creation.
+    pub to: Option<Address>,
+    pub value: U256,
+    #[serde(alias = "data")]
+    pub input: Bytes,
+    #[serde(default)]
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    #[serde(default)]
+    pub blob_versioned_hashes: Vec<B256>,
+    #[serde(default)]
+    pub authorization_list: Vec<T8nAuthorization>,
+    pub v: Option<U256>,
+    pub r: Option<U256>,
+    pub s: Option<U256>,
+    pub secret_key: Option<B256>,
+    /// Whether a legacy transaction signed with `secretKey` is replay
+    /// protected (EIP-155).
+    #[serde(default = "default_protected")]
+    pub protected: bool,
+}
+
+fn default_protected() -> bool {
+    true
+}
+
+/// An authorization of a set code transaction (EIP-7702). Fillers write the
+/// parity as `yParity`, `v` or both.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nAuthorization {
+    pub chain_id: U256,
+    pub address: Address,
+    pub nonce: U256,
+    pub y_parity: Option<U256>,
+    pub v: Option<U256>,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl From<&T8nAuthorization> for SignedAuthorization {
+    fn from(authorization: &T8nAuthorization) -> Self {
+        let y_parity = authorization.y_parity.or(authorization.v);
+        Self {
+            chain_id: authorization.chain_id,
+            address: authorization.address,
+            nonce: authorization.nonce.saturating_to(),
+            y_parity: y_parity.unwrap_or_default().saturating_to(),
+            r: authorization.r,
+            s: authorization.s,
+        }
+    }
+}
+
+impl T8nTransaction {
+    /// Builds the signed transaction, signing it if it has a secret key.
+    /// Typed transactions without a chain id default to `chain_id`.
+    pub fn into_signed(self, chain_id: u64) -> eyre::Result<SignedTransaction> {
+        let nonce = self.nonce.saturating_to();
+        let gas_limit = self.gas.saturating_to();
+        let to = self.to.map_or(TxKind::Create, TxKind::Call);
+        let gas_price = || self.gas_price.ok_or_else(|| anyhow!("missing gas price"));
+        let max_fee_per_gas = || {
+            self.max_fee_per_gas
+                .ok_or_else(|| anyhow!("missing max fee per gas"))
+        };
+        let max_priority_fee_per_gas = || {
+            self.max_priority_fee_per_gas
+                .ok_or_else(|| anyhow!("missing max priority fee per gas"))
+        };
+        let v = self.v.unwrap_or_default();
+        let typed_chain_id = self.chain_id.map_or(chain_id, |id| id.saturating_to());
+
+        let transaction = match self.tx_type.saturating_to::<u8>() {
+            0 => Transaction::Legacy(TxLegacy {
+                nonce,
+                gas_price: gas_price()?,
+                gas_limit,
+                to,
+                value: self.value,
+                input: self.input.clone(),
+                chain_id: match self.secret_key {
+                    Some(_) => self.protected.then_some(chain_id),
+                    None if v >= U256::from(35) => {
+                        Some(((v - U256::from(35)) / U256::from(2)).saturating_to())
+                    }
+                    None => None,
+                },
+            }),
+            1 => Transaction::Eip2930(TxEip2930 {
+                chain_id: typed_chain_id,
+                nonce,
+                gas_price: gas_price()?,
+                gas_limit,
+                to,
+                value: self.value,
+                input: self.input.clone(),
+                access_list: self.access_list.clone(),
+            }),
+            2 => Transaction::Eip1559(TxEip1559 {
+                chain_id: typed_chain_id,
+                nonce,
+                max_priority_fee_per_gas: max_priority_fee_per_gas()?,
+                max_fee_per_gas: max_fee_per_gas()?,
+                gas_limit,
+                to,
+                value: self.value,
+                input: self.input.clone(),
+                access_list: self.access_list.clone(),
+            }),
+            3 => Transaction::Eip4844(TxEip4844 {
+                chain_id: typed_chain_id,
+                nonce,
+                max_priority_fee_per_gas: max_priority_fee_per_gas()?,
+                max_fee_per_gas: max_fee_per_gas()?,
+                gas_limit,
+                to: self
+                    .to
+                    .ok_or_else(|| anyhow!("blob transactions can't create contracts"))?,
+                value: self.value,
+                input: self.input.clone(),
+                access_list: self.access_list.clone(),
+                max_fee_per_blob_gas: self
+                    .max_fee_per_blob_gas
+                    .ok_or_else(|| anyhow!("missing max fee per blob gas"))?,
+                blob_versioned_hashes: self.blob_versioned_hashes.clone(),
+            }),
+            4 => Transaction::Eip7702(TxEip7702 {
+                chain_id: typed_chain_id,
+                nonce,
+                max_priority_fee_per_gas: max_priority_fee_per_gas()?,
+                max_fee_per_gas: max_fee_per_gas()?,
+                gas_limit,
+                to: self
+                    .to
+                    .ok_or_else(|| anyhow!("set code transactions can't create contracts"))?,
+                value: self.value,
+                input: self.input.clone(),
+                access_list: self.access_list.clone(),
+                authorization_list: self.authorization_list.iter().map(Into::into).collect(),
+            }),
+            tx_type => return Err(anyhow!("unsupported transaction type {tx_type}")),
+        };
+
+        if let Some(secret_key) = self.secret_key {
+            return transaction.sign(&secret_key);
+        }
+        let y_parity = match &transaction {
+            Transaction::Legacy(TxLegacy {
+                chain_id: Some(_), ..
+            }) => (v - U256::from(35)) % U256::from(2),
+            Transaction::Legacy(_) => v.saturating_sub(U256::from(27)),
+            _ => v,
+        };
+        if y_parity > U256::from(1) {
+            return Err(anyhow!("invalid signature v {v}"));
+        }
+        let signature = Signature {
+            y_parity: y_parity == U256::from(1),
+            r: self.r.unwrap_or_default(),
+            s: self.s.unwrap_or_default(),
+        };
+        Ok(SignedTransaction::new(transaction, signature))
+    }
+}
+
+/// The inputs read from stdin when a path is `stdin`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nInput {
+    pub alloc: Option<Alloc>,
+    pub env: Option<T8nEnv>,
+    pub txs: Option<Vec<T8nTransaction>>,
+    /// The RLP encoded list of transactions, instead of `txs`.
+    pub txs_rlp: Option<Bytes>,
+}
+
+/// A transaction that didn't make it into the block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RejectedTransaction {
+    pub index: usize,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    pub block_number: U256,
+    pub transaction_hash: B256,
+    pub transaction_index: U256,
+    pub block_hash: B256,
+    pub log_index: U256,
+    pub removed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nReceipt {
+    #[serde(rename = "type")]
+    pub tx_type: U256,
+    /// The post-state root before Byzantium, empty afterwards.
+    pub root: Bytes,
+    pub status: U256,
+    pub cumulative_gas_used: U256,
+    pub logs_bloom: Bloom,
+    pub logs: Vec<T8nLog>,
+    pub transaction_hash: B256,
+    pub contract_address: Address,
+    pub gas_used: U256,
+    pub block_hash: B256,
+    pub transaction_index: U256,
+}
+
+/// The `result.json` of a transition.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct T8nResult {
+    pub state_root: B256,
+    pub tx_root: B256,
+    pub receipts_root: B256,
+    pub logs_hash: B256,
+    pub logs_bloom: Bloom,
+    pub receipts: Vec<T8nReceipt>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<RejectedTransaction>,
+    /// Only set before the merge.
+    pub current_difficulty: Option<U256>,
+    pub gas_used: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_base_fee: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_excess_blob_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U256>,
+    /// The requests to the consensus layer, from Prague on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests: Option<Vec<Bytes>>,
+}
+
+/// Everything a transition outputs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct T8nOutput {
+    pub result: T8nResult,
+    pub alloc: Alloc,
+    /// The RLP encoded list of included transactions.
+    pub body: Bytes,
+}
+
+/// Reads `txs.json`, or the JSON string holding the RLP encoded list of
+/// transactions if the file ends in `.rlp`.
+pub fn read_txs(path: &Path, chain_id: u64) -> eyre::Result<Vec<eyre::Result<SignedTransaction>>> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| anyhow!("failed to read {}: {e}", path.display()))?;
+    if path.extension().is_some_and(|extension| extension == "rlp") {
+        let rlp: Bytes = serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("invalid txs file {}: {e}", path.display()))?;
+        return decode_txs(&rlp);
+    }
+    let txs: Vec<T8nTransaction> = serde_json::from_str(&contents)
+        .map_err(|e| anyhow!("invalid txs file {}: {e}", path.display()))?;
+    Ok(sign_txs(txs, chain_id))
+}
+
+/// Builds the signed transactions, keeping the error of those that can't
+/// be signed so they can be rejected.
+pub fn sign_txs(txs: Vec<T8nTransaction>, chain_id: u64) -> Vec<eyre::Result<SignedTransaction>> {
+    txs.into_iter().map(|tx| tx.into_signed(chain_id)).collect()
+}
+
+/// Decodes an RLP encoded list of transactions.
+pub fn decode_txs(rlp: &[u8]) -> eyre::Result<Vec<eyre::Result<SignedTransaction>>> {
+    let txs = Vec::<SignedTransaction>::decode(&mut &rlp[..])
+        .map_err(|e| anyhow!("invalid transactions rlp: {e}"))?;
+    Ok(txs.into_iter().map(Ok).collect())
+}
+
+/// Applies `txs` to `alloc` in the block described by `env`. Invalid
+/// transactions are rejected rather than failing the transition. Before
+/// the merge, the coinbase and ommers are credited with `reward`, unless it
+/// is `None`.
+pub fn transition(
+    alloc: Alloc,
+    env: &T8nEnv,
+    txs: Vec<eyre::Result<SignedTransaction>>,
+    cfg: CfgEnv,
+    reward: Option<U256>,
+) -> eyre::Result<T8nOutput> {
+    let spec = cfg.spec;
+    let mut block_env = block_env(cfg, env)?;
+    let mut journal = JournaledState::new(alloc::to_state(alloc));
+
+    if spec.is_enabled_in(SpecId::Cancun) {
+        let root = env
+            .parent_beacon_block_root
+            .ok_or_else(|| anyhow!("post-cancun env requires parentBeaconBlockRoot to be set"))?;
+        block::system_call(
+            &mut journal,
+            &block_env,
+            BEACON_ROOTS_ADDRESS,
+            root.as_slice(),
+        )?;
+    }
+    if spec.is_enabled_in(SpecId::Prague) {
+        let parent = env.current_number.saturating_sub(U256::from(1));
+        if let Some(hash) = env.block_hashes.get(&parent) {
+            block::system_call(
+                &mut journal,
+                &block_env,
+                HISTORY_STORAGE_ADDRESS,
+                hash.as_slice(),
+            )?;
+        }
+    }
+
+    let mut usage = GasUsage::default();
+    let mut included = Vec::with_capacity(txs.len());
+    let mut results = Vec::with_capacity(txs.len());
+    let mut rejected = Vec::new();
+    for (index, tx) in txs.into_iter().enumerate() {
+        let outcome = tx.and_then(|tx| {
+            let result = block::apply_transaction(&mut journal, &mut block_env, &mut usage, &tx)?;
+            Ok((tx, result))
+        });
+        match outcome {
+            Ok((tx, result)) => {
+                included.push(tx);
+                results.push(result);
+            }
+            Err(e) => rejected.push(RejectedTransaction {
+                index,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let receipts = block::receipts(
+        spec,
+        included
+            .iter()
+            .zip(&results)
+            .map(|(tx, result)| (tx.transaction.tx_type(), result)),
+    );
+    let mut t8n_receipts = Vec::with_capacity(receipts.len());
+    let mut logs: Vec<Log> = Vec::new();
+    for (tx_index, ((tx, result), receipt)) in
+        included.iter().zip(&results).zip(&receipts).enumerate()
+    {
+        let tx_index = U256::from(tx_index);
+        t8n_receipts.push(T8nReceipt {
+            tx_type: U256::from(receipt.tx_type),
+            root: receipt
+                .state_root
+                .map(|root| Bytes::copy_from_slice(root.as_slice()))
+                .unwrap_or_default(),
+            status: U256::from(receipt.status as u8),
+            cumulative_gas_used: U256::from(receipt.cumulative_gas_used),
+            logs_bloom: receipt.logs_bloom,
+            logs: receipt
+                .logs
+                .iter()
+                .enumerate()
+                .map(|(i, log)| T8nLog {
+                    address: log.address,
+                    topics: log.topics.clone(),
+                    data: log.data.clone(),
+                    block_number: env.current_number,
+                    transaction_hash: tx.hash,
+                    transaction_index: tx_index,
+                    block_hash: B256::ZERO,
+                    log_index: U256::from(logs.len() + i),
+                    removed: false,
+                })
+                .collect(),
+            transaction_hash: tx.hash,
+            contract_address: result.contract_address.unwrap_or_default(),
+            gas_used: U256::from(result.gas_used),
+            block_hash: B256::ZERO,
+            transaction_index: tx_index,
+        });
+        logs.extend(receipt.logs.iter().cloned());
+    }
+
+    let withdrawals: Option<Vec<Withdrawal>> = env
+        .withdrawals
+        .as_ref()
+        .map(|withdrawals| withdrawals.iter().map(Withdrawal::from).collect());
+    block::apply_withdrawals(&mut journal, withdrawals.iter().flatten());
+    let requests = if spec.is_enabled_in(SpecId::Prague) {
+        Some(block::collect_requests(&mut journal, &block_env, &logs)?)
+    } else {
+        None
+    };
+    if let Some(reward) = reward.filter(|_| !spec.is_enabled_in(SpecId::Merge)) {
+        reward_miners(&mut journal, env, reward);
+    }
+    journal.finish_transaction();
+
+    let mut body = Vec::new();
+    alloy_rlp::encode_list(&included, &mut body);
+    let cancun = spec.is_enabled_in(SpecId::Cancun);
+    let result = T8nResult {
+        state_root: trie::state_root(&journal.state),
+        tx_root: trie::ordered_trie_root(included.iter().map(|tx| &tx.encoded)),
+        receipts_root: receipt::receipts_root(&receipts),
+        logs_hash: receipt::logs_hash(&logs),
+        logs_bloom: receipt::logs_bloom(&logs),
+        receipts: t8n_receipts,
+        rejected,
+        current_difficulty: (!spec.is_enabled_in(SpecId::Merge))
+            .then_some(block_env.block.difficulty),
+        gas_used: U256::from(usage.gas_used),
+        current_base_fee: spec
+            .is_enabled_in(SpecId::London)
+            .then_some(block_env.block.basefee),
+        withdrawals_root: withdrawals
+            .map(|withdrawals| trie::ordered_trie_root(withdrawals.iter().map(alloy_rlp::encode))),
+        current_excess_blob_gas: env.current_excess_blob_gas.filter(|_| cancun),
+        blob_gas_used: cancun.then_some(U256::from(usage.blob_gas_used)),
+        requests_hash: requests.as_deref().map(block::requests_hash),
+        requests,
+    };
+    Ok(T8nOutput {
+        result,
+        alloc: alloc::from_state(&journal.state),
+        body: body.into(),
+    })
+}
+
+/// The environment shared by the transactions, checking `env` has what
+/// the fork requires.
+fn block_env(cfg: CfgEnv, env: &T8nEnv) -> eyre::Result<Env> {
+    let spec = cfg.spec;
+    let mut block_env = Env {
+        cfg,
+        ..Default::default()
+    };
+    block_env.block.number = env.current_number;
+    block_env.block.coinbase = env.current_coinbase;
+    block_env.block.timestamp = env.current_timestamp;
+    block_env.block.gas_limit = env.current_gas_limit;
+    block_env.block.block_hashes = env
+        .block_hashes
+        .iter()
+        .map(|(number, hash)| (number.saturating_to(), *hash))
+        .collect();
+
+    if spec.is_enabled_in(SpecId::Merge) {
+        block_env.block.prevrandao =
+            Some(env.current_random.ok_or_else(|| {
+                anyhow!("post-merge requires currentRandom to be defined in env")
+            })?);
+    } else {
+        block_env.block.prevrandao = None;
+        block_env.block.difficulty = env
+            .current_difficulty
+            .ok_or_else(|| anyhow!("currentDifficulty was not provided"))?;
+    }
+    if spec.is_enabled_in(SpecId::London) {
+        block_env.block.basefee = env.current_base_fee.ok_or_else(|| {
+            anyhow!("EIP-1559 config but missing 'currentBaseFee' in env section")
+        })?;
+    }
+    if spec.is_enabled_in(SpecId::Shanghai) && env.withdrawals.is_none() {
+        return Err(anyhow!(
+            "Shanghai config but missing 'withdrawals' in env section"
+        ));
+    }
+    block_env.block.blob_basefee = if spec.is_enabled_in(SpecId::Cancun) {
+        let excess = env.current_excess_blob_gas.ok_or_else(|| {
+            anyhow!("Cancun config but missing 'currentExcessBlobGas' in env section")
+        })?;
+        Some(transaction::blob_basefee(excess.saturating_to(), spec))
+    } else {
+        None
+    };
+    Ok(block_env)
+}
+
+/// Credits the block reward to the coinbase and the ommer rewards, which
+/// shrink the older the ommer is.
+fn reward_miners(journal: &mut JournaledState, env: &T8nEnv, reward: U256) {
+    if reward.is_zero() {
+        return;
+    }
+    for ommer in &env.ommers {
+        let ommer_reward = reward * U256::from(8u64.saturating_sub(ommer.delta)) / U256::from(8);
+        let balance = journal.balance(&ommer.address);
+        journal.set_balance(ommer.address, balance + ommer_reward);
+    }
+    let miner_reward = reward + reward / U256::from(32) * U256::from(env.ommers.len());
+    let balance = journal.balance(&env.current_coinbase);
+    journal.set_balance(env.current_coinbase, balance + miner_reward);
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, b256, Address, U256};
+    use pretty_assertions::assert_eq;
+
+    use k256::ecdsa::SigningKey;
+
+    use crate::{
+        alloc,
+        block::{CONSOLIDATION_REQUEST_ADDRESS, WITHDRAWAL_REQUEST_ADDRESS},
+        bytecode,
+        env::{CfgEnv, SignedAuthorization},
+        spec::SpecId,
+        trie,
+    };
+
+    use super::{sign_txs, transition, T8nEnv, T8nTransaction};
+
+    const SECRET_KEY: &str = "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+
+    fn tx(nonce: u64) -> T8nTransaction {
+        serde_json::from_value(serde_json::json!({
+            "type": "0x2",
+            "nonce": format!("{nonce:#x}"),
+            "maxPriorityFeePerGas": "0x1",
+            "maxFeePerGas": "0xa",
+            "gas": "0x5208",
+            "to": "0x00000000000000000000000000000000000000b0",
+            "value": "0x64",
+            "input": "0x",
+            "secretKey": SECRET_KEY,
+        }))
+        .expect("should parse the transaction")
+    }
+
+    #[test]
+    fn applies_transactions() {
+        let sender = tx(0)
+            .into_signed(1)
+            .and_then(|tx| tx.recover_sender())
+            .expect("should recover the sender");
+        let alloc = serde_json::from_value(serde_json::json!({
+            sender.to_string(): { "balance": "0x1000000" }
+        }))
+        .expect("should parse the alloc");
+        let env: T8nEnv = serde_json::from_value(serde_json::json!({
+            "currentCoinbase": "0x00000000000000000000000000000000000000c0",
+            "currentGasLimit": "0x1000000",
+            "currentNumber": "0x1",
+            "currentTimestamp": "0x3e8",
+            "currentRandom": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "currentBaseFee": "0x7",
+            "withdrawals": [],
+        }))
+        .expect("should parse the env");
+        let cfg = CfgEnv {
+            spec: SpecId::Shanghai,
+            ..Default::default()
+        };
+
+        // The second transaction reuses the first one's nonce.
+        let txs = sign_txs(vec![tx(0), tx(0), tx(1)], 1);
+        let output = transition(alloc, &env, txs, cfg, Some(U256::ZERO))
+            .expect("should apply the transactions");
+        let result = &output.result;
+        assert_eq!(U256::from(42_000), result.gas_used);
+        assert_eq!(2, result.receipts.len());
+        assert_eq!(U256::from(42_000), result.receipts[1].cumulative_gas_used);
+        assert_eq!(1, result.rejected.len());
+        assert_eq!(1, result.rejected[0].index);
+        assert_eq!(None, result.current_difficulty);
+        assert_eq!(U256::from(7), result.current_base_fee.unwrap());
+
+        let recipient = address!("00000000000000000000000000000000000000b0");
+        let coinbase = address!("00000000000000000000000000000000000000c0");
+        assert_eq!(U256::from(200), output.alloc[&recipient].balance);
+        // The coinbase only earns the priority fee.
+        assert_eq!(U256::from(42_000), output.alloc[&coinbase].balance);
+        assert_eq!(U256::from(2), output.alloc[&sender].nonce);
+        assert_eq!(
+            trie::state_root(&alloc::to_state(output.alloc.clone())),
+            result.state_root
+        );
+        assert_eq!(
+            b256!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"),
+            result.withdrawals_root.unwrap()
+        );
+        assert_eq!(Address::ZERO, result.receipts[0].contract_address);
+    }
+
+    #[test]
+    fn applies_set_code_transactions() {
+        // The account of the secret key `0x4646..46`.
+        let authority = address!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+        let code = address!("000000000000000000000000000000000000c0de");
+        let mut authorization = SignedAuthorization {
+            chain_id: U256::from(1),
+            address: code,
+            nonce: 0,
+            ..Default::default()
+        };
+        let key = SigningKey::from_slice(&[0x46; 32]).expect("should be a valid key");
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(authorization.signing_hash().as_slice())
+            .expect("should sign");
+        authorization.y_parity = recovery_id.is_y_odd() as u8;
+        authorization.r = U256::from_be_slice(&signature.r().to_bytes());
+        authorization.s = U256::from_be_slice(&signature.s().to_bytes());
+
+        let tx: T8nTransaction = serde_json::from_value(serde_json::json!({
+            "type": "0x4",
+            "nonce": "0x0",
+            "maxPriorityFeePerGas": "0x1",
+            "maxFeePerGas": "0xa",
+            "gas": "0x186a0",
+            "to": authority,
+            "value": "0x0",
+            "input": "0x",
+            "authorizationList": [{
+                "chainId": "0x1",
+                "address": code,
+                "nonce": "0x0",
+                "v": format!("{:#x}", authorization.y_parity),
+                "yParity": format!("{:#x}", authorization.y_parity),
+                "r": authorization.r,
+                "s": authorization.s,
+            }],
+            "secretKey": SECRET_KEY,
+        }))
+        .expect("should parse the transaction");
+        let sender = tx
+            .clone()
+            .into_signed(1)
+            .and_then(|tx| tx.recover_sender())
+            .expect("should recover the sender");
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // SSTORE
+        // STOP
+        let alloc = serde_json::from_value(serde_json::json!({
+            sender.to_string(): { "balance": "0x1000000" },
+            code.to_string(): { "balance": "0x0", "code": "0x600160005500" },
+            // Stand-ins for the request contracts, which Prague requires.
+            WITHDRAWAL_REQUEST_ADDRESS.to_string(): { "balance": "0x0", "code": "0x00" },
+            CONSOLIDATION_REQUEST_ADDRESS.to_string(): { "balance": "0x0", "code": "0x00" },
+        }))
+        .expect("should parse the alloc");
+        let env: T8nEnv = serde_json::from_value(serde_json::json!({
+            "currentCoinbase": "0x00000000000000000000000000000000000000c0",
+            "currentGasLimit": "0x1000000",
+            "currentNumber": "0x1",
+            "currentTimestamp": "0x3e8",
+            "currentRandom": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "currentBaseFee": "0x7",
+            "currentExcessBlobGas": "0x0",
+            "parentBeaconBlockRoot": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "withdrawals": [],
+        }))
+        .expect("should parse the env");
+        let cfg = CfgEnv {
+            spec: SpecId::Prague,
+            ..Default::default()
+        };
+
+        let txs = sign_txs(vec![tx], 1);
+        let output = transition(alloc, &env, txs, cfg, Some(U256::ZERO))
+            .expect("should apply the transactions");
+        assert_eq!(true, output.result.rejected.is_empty());
+        assert_eq!(1, output.result.receipts.len());
+        let account = &output.alloc[&authority];
+        assert_eq!(bytecode::delegation_designator(code), account.code);
+        assert_eq!(U256::from(1), account.nonce);
+        assert_eq!(Some(&U256::from(1)), account.storage.get(&U256::ZERO));
+    }
+}
@@ -0,0 +1,643 @@
+use std::rc::Rc;
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use eyre::anyhow;
+
+use crate::{
+    bytecode,
+    env::{Env, SignedAuthorization, TxEnv},
+    frame::{self, CallInputs, CallKind, ExitStatus, MAX_INITCODE_SIZE},
+    gas,
+    journal::JournaledState,
+    spec::SpecId,
+    state::Log,
+    trie, Contract,
+};
+
+/// The blob gas used by each blob (EIP-4844).
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+
+/// Returns the blob base fee of a block with `excess_blob_gas`, which grows
+/// exponentially with the excess (EIP-4844). Prague raised the target and so
+/// slowed the growth down (EIP-7691).
+pub fn blob_basefee(excess_blob_gas: u64, spec: SpecId) -> U256 {
+    let update_fraction = if spec.is_enabled_in(SpecId::Prague) {
+        5_007_716
+    } else {
+        3_338_477
+    };
+    fake_exponential(
+        U256::from(1),
+        U256::from(excess_blob_gas),
+        U256::from(update_fraction),
+    )
+}
+
+/// Approximates `factor * e ** (numerator / denominator)` with a Taylor
+/// expansion.
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut output = U256::ZERO;
+    let mut accumulator = factor * denominator;
+    let mut i = U256::from(1);
+    while !accumulator.is_zero() {
+        output += accumulator;
+        accumulator = accumulator * numerator / (denominator * i);
+        i += U256::from(1);
+    }
+    output / denominator
+}
+
+/// The outcome of a transaction, much like a receipt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionResult {
+    pub status: ExitStatus,
+    /// The gas paid for by the sender, after refunds.
+    pub gas_used: u64,
+    pub gas_refunded: u64,
+    pub output: Bytes,
+    pub logs: Vec<Log>,
+    /// The address of the deployed contract, for contract creations.
+    pub contract_address: Option<Address>,
+    /// The state root after the transaction.
+    pub state_root: B256,
+}
+
+impl ExecutionResult {
+    pub fn is_success(&self) -> bool {
+        self.status == ExitStatus::Success
+    }
+}
+
+/// Runs the transaction in `env.tx` against `journal`.
+///
+/// Invalid transactions are rejected with an error and leave the state
+/// untouched. Valid transactions always get included: the sender pays for
+/// the gas used and bumps its nonce even if execution fails.
+pub fn transact(journal: &mut JournaledState, env: Env) -> eyre::Result<ExecutionResult> {
+    let spec = env.cfg.spec;
+    let tx = &env.tx;
+    let caller = tx.caller;
+
+    if !spec.is_enabled_in(introduced_in(tx)) {
+        return Err(anyhow!("transaction type not supported"));
+    }
+    if let Some(chain_id) = tx.chain_id {
+        if chain_id != env.cfg.chain_id {
+            return Err(anyhow!("invalid chain id"));
+        }
+    }
+    if U256::from(tx.gas_limit) > env.block.gas_limit {
+        return Err(anyhow!("gas limit exceeds block gas limit"));
+    }
+    if spec.is_enabled_in(SpecId::London) {
+        if let Some(priority_fee) = tx.gas_priority_fee {
+            if priority_fee > tx.gas_price {
+                return Err(anyhow!("max priority fee greater than max fee"));
+            }
+        }
+        if tx.gas_price < env.block.basefee {
+            return Err(anyhow!("max fee less than block base fee"));
+        }
+    }
+    if spec.is_enabled_in(SpecId::Shanghai) && tx.to.is_none() && tx.data.len() > MAX_INITCODE_SIZE
+    {
+        return Err(anyhow!("initcode size exceeded"));
+    }
+    if tx.authorization_list.as_ref().is_some_and(Vec::is_empty) {
+        return Err(anyhow!("empty authorization list"));
+    }
+
+    let intrinsic_gas = gas::intrinsic_gas(tx, spec);
+    let floor_gas = gas::calldata_floor_gas(tx, spec);
+    if tx.gas_limit < intrinsic_gas.max(floor_gas) {
+        return Err(anyhow!("intrinsic gas too low"));
+    }
+
+    let nonce = journal.nonce(&caller);
+    match tx.nonce {
+        Some(n) if n < nonce => return Err(anyhow!("nonce too low")),
+        Some(n) if n > nonce => return Err(anyhow!("nonce too high")),
+        _ => {}
+    }
+    // Senders must not have deployed code (EIP-3607), though they may
+    // delegate to some (EIP-7702).
+    let code = journal.code(&caller);
+    if !code.is_empty() && bytecode::delegation(&code).is_none() {
+        return Err(anyhow!("sender not an eoa"));
+    }
+
+    let blob_gas = GAS_PER_BLOB * tx.blob_hashes.len() as u64;
+    let blob_basefee = env.block.blob_basefee.unwrap_or_default();
+    if blob_gas != 0 && tx.max_fee_per_blob_gas.unwrap_or_default() < blob_basefee {
+        return Err(anyhow!("max fee per blob gas less than block blob gas fee"));
+    }
+
+    let gas_limit = U256::from(tx.gas_limit);
+    let blob_fee = U256::from(blob_gas) * blob_basefee;
+    let max_cost = gas_limit
+        .checked_mul(tx.gas_price)
+        .and_then(|cost| cost.checked_add(tx.value))
+        .and_then(|cost| {
+            cost.checked_add(U256::from(blob_gas) * tx.max_fee_per_blob_gas.unwrap_or_default())
+        })
+        .ok_or_else(|| anyhow!("insufficient funds"))?;
+    let balance = journal.balance(&caller);
+    if balance < max_cost {
+        return Err(anyhow!("insufficient funds"));
+    }
+
+    // Buy the gas up front, at the effective price.
+    let gas_price = env.effective_gas_price();
+    journal.set_balance(caller, balance - gas_limit * gas_price - blob_fee);
+
+    journal.warm_address(caller);
+    if spec.is_enabled_in(SpecId::Berlin) {
+        for address in env.cfg.precompiles.addresses(spec) {
+            journal.warm_address(address);
+        }
+    }
+    if spec.is_enabled_in(SpecId::Shanghai) {
+        // EIP-3651.
+        journal.warm_address(env.block.coinbase);
+    }
+    for item in tx.access_list.iter().flatten() {
+        journal.warm_address(item.address);
+        for key in &item.storage_keys {
+            journal.warm_slot(item.address, (*key).into());
+        }
+    }
+
+    let env = Rc::new(env);
+    let tx = &env.tx;
+    let execution_gas = tx.gas_limit - intrinsic_gas;
+    let mut authorization_refund = 0;
+    let frame = match tx.to {
+        Some(to) => {
+            journal.increment_nonce(caller)?;
+            if let Some(authorizations) = &tx.authorization_list {
+                authorization_refund =
+                    apply_authorizations(journal, env.cfg.chain_id, authorizations)?;
+            }
+            journal.warm_address(to);
+            if let Some(target) = bytecode::delegation(&journal.code(&to)) {
+                journal.warm_address(target);
+            }
+            let mut contract = Contract::new(to, Bytes::new(), tx.data.clone());
+            contract.caller = caller;
+            contract.value = tx.value;
+            let inputs = CallInputs {
+                contract,
+                code_address: to,
+                gas_limit: execution_gas,
+                kind: CallKind::Call,
+                is_static: false,
+                depth: 0,
+            };
+            frame::call(journal, env.clone(), inputs)
+        }
+        None => {
+            let address = caller.create(nonce);
+            journal.increment_nonce(caller)?;
+            journal.warm_address(address);
+            let mut contract = Contract::new(address, tx.data.clone(), Bytes::new());
+            contract.caller = caller;
+            contract.value = tx.value;
+            frame::create(journal, env.clone(), contract, execution_gas, 0)
+        }
+    };
+
+    let mut gas_used = tx.gas_limit - frame.gas.remaining;
+    let frame_refund = if frame.status == ExitStatus::Success {
+        frame.gas.refunded.max(0) as u64
+    } else {
+        0
+    };
+    // EIP-3529 lowered the refund cap from a half to a fifth.
+    let quotient = if spec.is_enabled_in(SpecId::London) {
+        5
+    } else {
+        2
+    };
+    let gas_refunded = (authorization_refund + frame_refund).min(gas_used / quotient);
+    gas_used = (gas_used - gas_refunded).max(floor_gas);
+
+    let reimbursement = U256::from(tx.gas_limit - gas_used) * gas_price;
+    journal.set_balance(caller, journal.balance(&caller) + reimbursement);
+
+    // Only the priority fee goes to the coinbase, the base fee is burnt.
+    let coinbase_gas_price = if spec.is_enabled_in(SpecId::London) {
+        gas_price.saturating_sub(env.block.basefee)
+    } else {
+        gas_price
+    };
+    let coinbase = env.block.coinbase;
+    let reward = U256::from(gas_used) * coinbase_gas_price;
+    journal.set_balance(coinbase, journal.balance(&coinbase) + reward);
+    journal.remove_selfdestructed();
+    // A zero reward still touches the coinbase, which is then removed along
+    // with any other touched account left empty (EIP-161).
+    if spec.is_enabled_in(SpecId::SpuriousDragon) {
+        journal.remove_touched_empty();
+    }
+
+    let logs = journal.finish_transaction();
+    Ok(ExecutionResult {
+        status: frame.status,
+        gas_used,
+        gas_refunded,
+        output: frame.output,
+        logs,
+        contract_address: frame.address,
+        state_root: trie::state_root(&journal.state),
+    })
+}
+
+/// The fork that introduced the type of `tx`, told apart by the fields only
+/// that type and later ones carry.
+fn introduced_in(tx: &TxEnv) -> SpecId {
+    if tx.authorization_list.is_some() {
+        SpecId::Prague
+    } else if tx.max_fee_per_blob_gas.is_some() {
+        SpecId::Cancun
+    } else if tx.gas_priority_fee.is_some() {
+        SpecId::London
+    } else if tx.access_list.is_some() {
+        SpecId::Berlin
+    } else {
+        SpecId::Frontier
+    }
+}
+
+/// Sets the code of each valid authorization's authority to a delegation
+/// designator (EIP-7702). Invalid authorizations are skipped rather than
+/// failing the transaction. Returns the refund for authorities whose
+/// accounts already existed.
+fn apply_authorizations(
+    journal: &mut JournaledState,
+    chain_id: u64,
+    authorizations: &[SignedAuthorization],
+) -> eyre::Result<u64> {
+    let mut refund = 0;
+    for authorization in authorizations {
+        if !authorization.chain_id.is_zero() && authorization.chain_id != U256::from(chain_id) {
+            continue;
+        }
+        if authorization.nonce == u64::MAX {
+            continue;
+        }
+        let Some(authority) = authorization.authority() else {
+            continue;
+        };
+        journal.warm_address(authority);
+        let code = journal.code(&authority);
+        if !code.is_empty() && bytecode::delegation(&code).is_none() {
+            continue;
+        }
+        if journal.nonce(&authority) != authorization.nonce {
+            continue;
+        }
+
+        if journal.exists(&authority) {
+            refund += gas::PER_EMPTY_ACCOUNT_COST - gas::PER_AUTH_BASE_COST;
+        }
+        // Delegating to the zero address clears the delegation.
+        let code = if authorization.address.is_zero() {
+            Bytes::new()
+        } else {
+            bytecode::delegation_designator(authorization.address)
+        };
+        journal.set_code(authority, code);
+        journal.increment_nonce(authority)?;
+    }
+    Ok(refund)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, Address, Bytes, U256};
+    use k256::ecdsa::SigningKey;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        bytecode,
+        env::{Env, SignedAuthorization},
+        frame::ExitStatus,
+        journal::JournaledState,
+        spec::SpecId,
+        state::{Account, State},
+        trie,
+    };
+
+    use super::{blob_basefee, transact};
+
+    const SENDER: Address = Address::with_last_byte(0xA1);
+    const CONTRACT: Address = Address::with_last_byte(0xA2);
+    const COINBASE: Address = Address::with_last_byte(0xA3);
+
+    fn setup(code: &str) -> (JournaledState, Env) {
+        let mut state = State::new();
+        state.insert(
+            SENDER,
+            Account {
+                balance: U256::from(10_000_000),
+                ..Default::default()
+            },
+        );
+        state.insert(
+            CONTRACT,
+            Account {
+                code: code.parse().unwrap(),
+                ..Default::default()
+            },
+        );
+
+        let mut env = Env::default();
+        env.block.coinbase = COINBASE;
+        env.block.basefee = U256::from(7);
+        env.tx.caller = SENDER;
+        env.tx.to = Some(CONTRACT);
+        env.tx.gas_limit = 100_000;
+        env.tx.gas_price = U256::from(10);
+        env.tx.gas_priority_fee = Some(U256::from(2));
+        env.tx.nonce = Some(0);
+        (JournaledState::new(state), env)
+    }
+
+    #[test]
+    fn pays_for_gas() {
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // SSTORE
+        // STOP
+        let (mut journal, env) = setup("0x600160005500");
+        let result = transact(&mut journal, env).expect("should be valid");
+
+        // Intrinsic gas, two pushes and a cold SSTORE.
+        let gas_used = 21000 + 3 + 3 + 22100;
+        assert_eq!(ExitStatus::Success, result.status);
+        assert_eq!(gas_used, result.gas_used);
+        assert_eq!(
+            U256::from(10_000_000 - gas_used * 9),
+            journal.balance(&SENDER)
+        );
+        assert_eq!(U256::from(gas_used * 2), journal.balance(&COINBASE));
+        assert_eq!(1, journal.nonce(&SENDER));
+        assert_eq!(U256::from(1), journal.sload(&CONTRACT, U256::ZERO));
+        assert_eq!(trie::state_root(&journal.state), result.state_root);
+    }
+
+    #[test]
+    fn removes_touched_empty_accounts() {
+        let empty = Address::with_last_byte(0xB1);
+        let missing = Address::with_last_byte(0xB2);
+        let run = |spec| {
+            // PUSH1 0x00
+            // PUSH1 0x00
+            // PUSH1 0x00
+            // PUSH1 0x00
+            // PUSH1 0x00
+            // PUSH1 0xb1
+            // PUSH1 0x00
+            // CALL
+            // POP
+            // ...the same zero-value call to 0xb2
+            // STOP
+            let (mut journal, mut env) = setup(
+                "0x6000600060006000600060b16000f150\
+                 6000600060006000600060b26000f15000",
+            );
+            journal.state.insert(empty, Account::default());
+            env.cfg.spec = spec;
+            env.tx.gas_priority_fee = None;
+            transact(&mut journal, env).expect("should be valid");
+            journal
+        };
+
+        let journal = run(SpecId::Cancun);
+        assert_eq!(false, journal.exists(&empty));
+        assert_eq!(false, journal.exists(&missing));
+
+        // Before EIP-161, calls created their target and left it there.
+        let journal = run(SpecId::Homestead);
+        assert_eq!(true, journal.exists(&empty));
+        assert_eq!(true, journal.exists(&missing));
+    }
+
+    #[test]
+    fn computes_blob_basefee() {
+        assert_eq!(U256::from(1), blob_basefee(0, SpecId::Cancun));
+        assert_eq!(U256::from(2), blob_basefee(2_314_058, SpecId::Cancun));
+        assert_eq!(U256::from(1), blob_basefee(2_314_058, SpecId::Prague));
+    }
+
+    #[test]
+    fn caps_refunds() {
+        // PUSH0
+        // PUSH0
+        // SSTORE
+        // PUSH0
+        // PUSH1 0x01
+        // SSTORE
+        // STOP
+        let (mut journal, env) = setup("0x5f5f555f60015500");
+        journal.sstore(CONTRACT, U256::ZERO, U256::from(1));
+        journal.sstore(CONTRACT, U256::from(1), U256::from(1));
+        journal.finish_transaction();
+        let result = transact(&mut journal, env).expect("should be valid");
+
+        // Clearing both slots refunds 9600, which gets capped at a fifth.
+        let gas_used = 21000 + 2 + 2 + 5000 + 2 + 3 + 5000;
+        assert_eq!(gas_used / 5, result.gas_refunded);
+        assert_eq!(gas_used - gas_used / 5, result.gas_used);
+    }
+
+    #[test]
+    fn charges_all_gas_on_halt() {
+        // INVALID
+        let (mut journal, env) = setup("0xfe");
+        let result = transact(&mut journal, env).expect("should be valid");
+        assert_eq!(ExitStatus::Halt, result.status);
+        assert_eq!(100_000, result.gas_used);
+        assert_eq!(1, journal.nonce(&SENDER));
+    }
+
+    #[test]
+    fn rejects_invalid_transactions() {
+        let (mut journal, mut env) = setup("0x00");
+        env.tx.nonce = Some(1);
+        let err = transact(&mut journal, env.clone()).unwrap_err();
+        assert_eq!("nonce too high", err.to_string());
+
+        env.tx.nonce = Some(0);
+        env.tx.gas_limit = 21000;
+        env.tx.data = Bytes::from_static(&[1]);
+        let err = transact(&mut journal, env.clone()).unwrap_err();
+        assert_eq!("intrinsic gas too low", err.to_string());
+
+        env.tx.data = Bytes::new();
+        env.tx.value = U256::from(10_000_000);
+        let err = transact(&mut journal, env.clone()).unwrap_err();
+        assert_eq!("insufficient funds", err.to_string());
+
+        env.tx.value = U256::ZERO;
+        env.tx.gas_price = U256::from(6);
+        let err = transact(&mut journal, env).unwrap_err();
+        assert_eq!("max fee less than block base fee", err.to_string());
+        assert_eq!(U256::from(10_000_000), journal.balance(&SENDER));
+    }
+
+    #[test]
+    fn rejects_transaction_types_before_their_fork() {
+        let (mut journal, mut env) = setup("0x00");
+        env.cfg.spec = SpecId::Berlin;
+        let err = transact(&mut journal, env.clone()).unwrap_err();
+        assert_eq!("transaction type not supported", err.to_string());
+
+        env.tx.gas_priority_fee = None;
+        env.tx.access_list = Some(Vec::new());
+        env.cfg.spec = SpecId::Istanbul;
+        let err = transact(&mut journal, env.clone()).unwrap_err();
+        assert_eq!("transaction type not supported", err.to_string());
+
+        env.tx.access_list = None;
+        env.tx.max_fee_per_blob_gas = Some(U256::from(1));
+        env.cfg.spec = SpecId::Shanghai;
+        let err = transact(&mut journal, env.clone()).unwrap_err();
+        assert_eq!("transaction type not supported", err.to_string());
+
+        // The type is checked before the intrinsic gas.
+        env.tx.max_fee_per_blob_gas = None;
+        env.tx.access_list = Some(Vec::new());
+        env.tx.gas_limit = 0;
+        env.cfg.spec = SpecId::Istanbul;
+        let err = transact(&mut journal, env).unwrap_err();
+        assert_eq!("transaction type not supported", err.to_string());
+    }
+
+    #[test]
+    fn creates_contracts() {
+        // PUSH1 0x2a
+        // PUSH1 0x00
+        // MSTORE8
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // RETURN
+        let (mut journal, mut env) = setup("0x00");
+        env.tx.to = None;
+        env.tx.data = "0x602a60005360016000f3".parse().unwrap();
+        let result = transact(&mut journal, env).expect("should be valid");
+
+        let address = SENDER.create(0);
+        assert_eq!(Some(address), result.contract_address);
+        assert_eq!(Bytes::from_static(&[0x2a]), journal.code(&address));
+        assert_eq!(1, journal.nonce(&address));
+    }
+
+    #[test]
+    fn rejects_creation_over_storage() {
+        let (mut journal, mut env) = setup("0x00");
+        let address = SENDER.create(0);
+        let mut account = Account::default();
+        account.storage.insert(U256::ZERO, U256::from(1));
+        journal.state.insert(address, account);
+        env.tx.to = None;
+        env.tx.data = "0x602a60005360016000f3".parse().unwrap();
+        let result = transact(&mut journal, env).expect("should be valid");
+
+        // An address with storage collides even without code or a nonce
+        // (EIP-7610).
+        assert_eq!(ExitStatus::Halt, result.status);
+        assert_eq!(None, result.contract_address);
+        assert_eq!(Bytes::new(), journal.code(&address));
+    }
+
+    /// The account of the secret key `0x4646..46`.
+    const AUTHORITY: Address = address!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+
+    fn authorize(chain_id: u64, address: Address, nonce: u64) -> SignedAuthorization {
+        let mut authorization = SignedAuthorization {
+            chain_id: U256::from(chain_id),
+            address,
+            nonce,
+            ..Default::default()
+        };
+        let key = SigningKey::from_slice(&[0x46; 32]).expect("should be a valid key");
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(authorization.signing_hash().as_slice())
+            .expect("should sign");
+        authorization.y_parity = recovery_id.is_y_odd() as u8;
+        authorization.r = U256::from_be_slice(&signature.r().to_bytes());
+        authorization.s = U256::from_be_slice(&signature.s().to_bytes());
+        authorization
+    }
+
+    #[test]
+    fn delegates_to_code() {
+        // PUSH1 0x01
+        // PUSH1 0x00
+        // SSTORE
+        // STOP
+        let (mut journal, mut env) = setup("0x600160005500");
+        env.cfg.spec = SpecId::Prague;
+        env.tx.to = Some(AUTHORITY);
+        env.tx.gas_limit = 200_000;
+        env.tx.authorization_list = Some(vec![
+            authorize(0, CONTRACT, 0),
+            // Skipped, as the nonce was bumped by the first one.
+            authorize(1, Address::ZERO, 0),
+            // Skipped, as it is for another chain.
+            authorize(5, Address::ZERO, 1),
+        ]);
+        let result = transact(&mut journal, env).expect("should be valid");
+
+        // Three authorizations, two pushes and a cold SSTORE in the
+        // authority's storage.
+        let gas_used = 21000 + 3 * 25000 + 3 + 3 + 22100;
+        assert_eq!(ExitStatus::Success, result.status);
+        assert_eq!(gas_used, result.gas_used);
+        assert_eq!(
+            bytecode::delegation_designator(CONTRACT),
+            journal.code(&AUTHORITY)
+        );
+        assert_eq!(
+            Some(CONTRACT),
+            bytecode::delegation(&journal.code(&AUTHORITY))
+        );
+        assert_eq!(1, journal.nonce(&AUTHORITY));
+        assert_eq!(U256::from(1), journal.sload(&AUTHORITY, U256::ZERO));
+        assert_eq!(U256::ZERO, journal.sload(&CONTRACT, U256::ZERO));
+    }
+
+    #[test]
+    fn refunds_existing_authorities() {
+        let (mut journal, mut env) = setup("0x00");
+        journal.set_balance(AUTHORITY, U256::from(1));
+        journal.finish_transaction();
+        env.cfg.spec = SpecId::Prague;
+        env.tx.authorization_list = Some(vec![authorize(1, CONTRACT, 0)]);
+        let result = transact(&mut journal, env.clone()).expect("should be valid");
+        // The refund of 12500 gets capped at a fifth.
+        assert_eq!((21000 + 25000) / 5, result.gas_refunded);
+        assert_eq!(21000 + 25000 - 9200, result.gas_used);
+
+        // Delegating to the zero address clears the delegation.
+        env.tx.nonce = Some(1);
+        env.tx.authorization_list = Some(vec![authorize(1, Address::ZERO, 1)]);
+        transact(&mut journal, env).expect("should be valid");
+        assert_eq!(Bytes::new(), journal.code(&AUTHORITY));
+        assert_eq!(2, journal.nonce(&AUTHORITY));
+    }
+
+    #[test]
+    fn rejects_invalid_set_code_transactions() {
+        let (mut journal, mut env) = setup("0x00");
+        env.tx.authorization_list = Some(vec![authorize(1, CONTRACT, 0)]);
+        let err = transact(&mut journal, env.clone()).unwrap_err();
+        assert_eq!("transaction type not supported", err.to_string());
+
+        env.cfg.spec = SpecId::Prague;
+        env.tx.authorization_list = Some(vec![]);
+        let err = transact(&mut journal, env).unwrap_err();
+        assert_eq!("empty authorization list", err.to_string());
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{b256, keccak256, B256, U256};
+use alloy_rlp::{Encodable, Header, EMPTY_STRING_CODE};
+
+use crate::state::State;
+
+/// The root of an empty trie, the hash of the empty string's encoding.
+pub const EMPTY_ROOT: B256 =
+    b256!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+
+/// Computes the root of the Merkle Patricia Trie holding `entries`.
+///
+/// The trie is built in memory from scratch, which is all that is needed to
+/// compare roots against fixtures. Later entries win over earlier ones with
+/// the same key.
+pub fn trie_root<K, V>(entries: impl IntoIterator<Item = (K, V)>) -> B256
+where
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+{
+    let mut entries: Vec<(Vec<u8>, V)> = entries
+        .into_iter()
+        .map(|(key, value)| (to_nibbles(key.as_ref()), value))
+        .collect();
+    // A stable sort keeps duplicates in insertion order, so keeping the last
+    // of each run keeps the latest value.
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut deduplicated: Vec<(Vec<u8>, V)> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match deduplicated.last_mut() {
+            Some(last) if last.0 == entry.0 => *last = entry,
+            _ => deduplicated.push(entry),
+        }
+    }
+
+    let entries: Vec<(&[u8], &[u8])> = deduplicated
+        .iter()
+        .map(|(key, value)| (key.as_slice(), value.as_ref()))
+        .collect();
+    keccak256(encode_node(&entries, 0))
+}
+
+/// Computes the root of a trie keyed by the RLP encoding of each item's
+/// index, as used for transactions and receipts.
+pub fn ordered_trie_root<V: AsRef<[u8]>>(items: impl IntoIterator<Item = V>) -> B256 {
+    trie_root(
+        items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| (alloy_rlp::encode(i), item)),
+    )
+}
+
+/// Computes the root of an account's storage trie. Slots are keyed by their
+/// hash and hold their RLP encoded value. Zero slots are not stored.
+pub fn storage_root(storage: &HashMap<U256, U256>) -> B256 {
+    trie_root(
+        storage
+            .iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(slot, value)| {
+                let key = keccak256(B256::from(*slot));
+                (key, alloy_rlp::encode(value))
+            }),
+    )
+}
+
+/// Computes the state root. Accounts are keyed by the hash of their address
+/// and hold `[nonce, balance, storage_root, code_hash]`.
+pub fn state_root(state: &State) -> B256 {
+    trie_root(state.iter().map(|(address, account)| {
+        let fields = (
+            account.nonce,
+            account.balance,
+            storage_root(&account.storage),
+            account.code_hash(),
+        );
+        let payload_length =
+            fields.0.length() + fields.1.length() + fields.2.length() + fields.3.length();
+        let mut out = Vec::with_capacity(payload_length + 4);
+        Header {
+            list: true,
+            payload_length,
+        }
+        .encode(&mut out);
+        fields.0.encode(&mut out);
+        fields.1.encode(&mut out);
+        fields.2.encode(&mut out);
+        fields.3.encode(&mut out);
+        (keccak256(address), out)
+    }))
+}
+
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0F])
+        .collect()
+}
+
+/// Encodes the node holding `entries`, whose keys share their first `depth`
+/// nibbles. Keys must be sorted and unique.
+fn encode_node(entries: &[(&[u8], &[u8])], depth: usize) -> Vec<u8> {
+    match entries {
+        [] => vec![EMPTY_STRING_CODE],
+        [(key, value)] => encode_list(&[string(&hex_prefix(&key[depth..], true)), string(value)]),
+        _ => {
+            // Sorted keys share a prefix if the first and last ones do.
+            let first = &entries[0].0[depth..];
+            let last = &entries[entries.len() - 1].0[depth..];
+            let shared = first.iter().zip(last).take_while(|(a, b)| a == b).count();
+            if shared > 0 {
+                let child = encode_node(entries, depth + shared);
+                return encode_list(&[
+                    string(&hex_prefix(&first[..shared], false)),
+                    reference(child),
+                ]);
+            }
+
+            // Only the first key can end here, as it sorts before its
+            // extensions.
+            let (value, rest) = match entries[0].0.len() == depth {
+                true => (entries[0].1, &entries[1..]),
+                false => (&[][..], entries),
+            };
+            let mut items = Vec::with_capacity(17);
+            let mut start = 0;
+            for nibble in 0..16 {
+                let end = start
+                    + rest[start..]
+                        .iter()
+                        .take_while(|(key, _)| key[depth] == nibble)
+                        .count();
+                items.push(match end - start {
+                    0 => vec![EMPTY_STRING_CODE],
+                    _ => reference(encode_node(&rest[start..end], depth + 1)),
+                });
+                start = end;
+            }
+            items.push(string(value));
+            encode_list(&items)
+        }
+    }
+}
+
+/// Nodes shorter than a hash are embedded in their parent, larger ones are
+/// referenced by their hash.
+fn reference(node: Vec<u8>) -> Vec<u8> {
+    if node.len() < 32 {
+        node
+    } else {
+        string(keccak256(node).as_slice())
+    }
+}
+
+/// The compact encoding of a path, flagging whether it ends in a leaf and
+/// whether it has an odd number of nibbles.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 };
+    let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        out.push(((flag + 1) << 4) | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        out.push(flag << 4);
+        nibbles
+    };
+    out.extend(rest.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]));
+    out
+}
+
+fn string(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 3);
+    bytes.encode(&mut out);
+    out
+}
+
+/// Wraps already encoded items in a list.
+fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_length = items.iter().map(Vec::len).sum();
+    let mut out = Vec::with_capacity(payload_length + 3);
+    Header {
+        list: true,
+        payload_length,
+    }
+    .encode(&mut out);
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use alloy_primitives::{b256, Address, U256};
+    use pretty_assertions::assert_eq;
+
+    use crate::state::{Account, State};
+
+    use super::{ordered_trie_root, state_root, storage_root, trie_root, EMPTY_ROOT};
+
+    #[test]
+    fn computes_empty_root() {
+        assert_eq!(EMPTY_ROOT, trie_root(Vec::<(&[u8], &[u8])>::new()));
+        assert_eq!(EMPTY_ROOT, storage_root(&HashMap::new()));
+        assert_eq!(EMPTY_ROOT, state_root(&State::new()));
+        assert_eq!(EMPTY_ROOT, ordered_trie_root(Vec::<Vec<u8>>::new()));
+    }
+
+    // From the ethereum/tests trie tests.
+    #[test]
+    fn computes_roots() {
+        let root = trie_root([
+            ("do", "verb"),
+            ("horse", "stallion"),
+            ("doge", "coin"),
+            ("dog", "puppy"),
+        ]);
+        assert_eq!(
+            b256!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"),
+            root
+        );
+
+        let root = trie_root([("foo", "bar"), ("food", "bass")]);
+        assert_eq!(
+            b256!("17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3"),
+            root
+        );
+
+        let root = trie_root([("be", "e"), ("dog", "puppy"), ("bed", "d")]);
+        assert_eq!(
+            b256!("3f67c7a47520f79faa29255d2d3c084a7a6df0453116ed7232ff10277a8be68b"),
+            root
+        );
+
+        let root = trie_root([("test", "test"), ("te", "testy")]);
+        assert_eq!(
+            b256!("8452568af70d8d140f58d941338542f645fcca50094b20f3c3d8c3df49337928"),
+            root
+        );
+
+        let root = trie_root([
+            ("doe", "reindeer"),
+            ("dog", "puppy"),
+            ("dogglesworth", "cat"),
+        ]);
+        assert_eq!(
+            b256!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"),
+            root
+        );
+    }
+
+    #[test]
+    fn keeps_the_latest_duplicate() {
+        let root = trie_root([("foo", "baz"), ("foo", "bar"), ("food", "bass")]);
+        assert_eq!(trie_root([("foo", "bar"), ("food", "bass")]), root);
+    }
+
+    #[test]
+    fn ignores_zero_slots() {
+        let mut storage = HashMap::new();
+        storage.insert(U256::from(1), U256::from(2));
+        let root = storage_root(&storage);
+        storage.insert(U256::from(3), U256::ZERO);
+        assert_eq!(root, storage_root(&storage));
+        assert_eq!(false, root == EMPTY_ROOT);
+    }
+
+    #[test]
+    fn computes_state_root() {
+        let mut state = State::new();
+        state.insert(
+            Address::with_last_byte(1),
+            Account {
+                balance: U256::from(1),
+                ..Default::default()
+            },
+        );
+        let root = state_root(&state);
+        assert_eq!(false, root == EMPTY_ROOT);
+
+        // Storage changes the account and so the root.
+        state
+            .get_mut(&Address::with_last_byte(1))
+            .unwrap()
+            .storage
+            .insert(U256::from(1), U256::from(1));
+        assert_eq!(false, root == state_root(&state));
+    }
+}
@@ -84,3 +84,54 @@ fn disassembler_reversability() {
     let actual = String::from_utf8(output.stdout).unwrap();
     assert_eq!(expected.trim(), actual.trim());
 }
+
+#[test]
+fn t8n_runs_with_the_default_fork() {
+    // `--state.fork` defaults to GrayGlacier, which follows London's rules.
+    let input = r#"{
+        "alloc": {
+            "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": { "balance": "0x10000000" }
+        },
+        "env": {
+            "currentCoinbase": "0x00000000000000000000000000000000000000cc",
+            "currentDifficulty": "0x20000",
+            "currentGasLimit": "0x1000000",
+            "currentNumber": "0x1",
+            "currentTimestamp": "0x3e8",
+            "currentBaseFee": "0x7"
+        },
+        "txs": [{
+            "type": "0x0",
+            "nonce": "0x0",
+            "gasPrice": "0xa",
+            "gas": "0x5208",
+            "to": "0x00000000000000000000000000000000000000bb",
+            "value": "0x1",
+            "input": "0x",
+            "secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8"
+        }]
+    }"#;
+    let binary_path = get_binary_path();
+    let args = [
+        "--input.alloc=stdin",
+        "--input.env=stdin",
+        "--input.txs=stdin",
+        "--output.result=stdout",
+        "--output.alloc=stdout",
+    ];
+    let output = cmd(&binary_path, "t8n", &args, Some(input));
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(None, output["result"].get("rejected"));
+    assert_eq!("0x5208", output["result"]["gasUsed"]);
+    // Only the priority fee of 3 wei per gas goes to the coinbase.
+    let alloc = &output["alloc"];
+    assert_eq!(
+        "0x1",
+        alloc["0x00000000000000000000000000000000000000bb"]["balance"]
+    );
+    assert_eq!(
+        "0xf618",
+        alloc["0x00000000000000000000000000000000000000cc"]["balance"]
+    );
+}